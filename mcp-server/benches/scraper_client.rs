@@ -0,0 +1,94 @@
+//! Latency of `RustScraper` with a fresh client per scrape vs the shared pooled client.
+//!
+//! Serves a static article from a local axum server and scrapes it repeatedly.
+//! Run with: `cargo bench --bench scraper_client`
+//! Set `BENCH_URL` to benchmark against a real (e.g. HTTPS) page instead.
+
+use axum::{response::Html, routing::get, Router};
+use mcp_server::{build_http_client, rust_scraper::RustScraper};
+use std::time::{Duration, Instant};
+
+const ITERATIONS: usize = 50;
+
+const PAGE: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head><title>Benchmark Article</title><meta name="description" content="Pooled client benchmark"></head>
+<body>
+<main>
+<article>
+<h1>Connection pooling</h1>
+<p>Reusing one HTTP client keeps TCP connections and TLS sessions alive between requests,
+so each scrape skips the handshake and the client setup cost.</p>
+<h2>Example</h2>
+<pre><code class="language-rust">let client = reqwest::Client::builder().build()?;</code></pre>
+<p>Building a client per request also reloads the system root certificates every time.</p>
+</article>
+</main>
+</body>
+</html>"#;
+
+struct Stats {
+    mean: Duration,
+    p50: Duration,
+    p95: Duration,
+}
+
+fn summarize(mut samples: Vec<Duration>) -> Stats {
+    samples.sort();
+    let total: Duration = samples.iter().sum();
+    Stats {
+        mean: total / samples.len() as u32,
+        p50: samples[samples.len() / 2],
+        p95: samples[(samples.len() * 95 / 100).min(samples.len() - 1)],
+    }
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let url = match std::env::var("BENCH_URL") {
+        Ok(url) => url,
+        Err(_) => {
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+            let addr = listener.local_addr()?;
+            let app = Router::new().route("/article", get(|| async { Html(PAGE) }));
+            tokio::spawn(async move { axum::serve(listener, app).await });
+            format!("http://{}/article", addr)
+        }
+    };
+    println!("Benchmarking {} scrapes of {}", ITERATIONS, url);
+
+    // Per-scrape client (previous behaviour of scrape::scrape_url)
+    let mut fresh = Vec::with_capacity(ITERATIONS);
+    for _ in 0..ITERATIONS {
+        let start = Instant::now();
+        let scraper = RustScraper::new();
+        scraper.scrape_url(&url).await?;
+        fresh.push(start.elapsed());
+    }
+
+    // Shared pooled client (AppState::scraper)
+    let shared_scraper = RustScraper::with_client(build_http_client()?);
+    shared_scraper.scrape_url(&url).await?; // warm the pool
+    let mut shared = Vec::with_capacity(ITERATIONS);
+    for _ in 0..ITERATIONS {
+        let start = Instant::now();
+        shared_scraper.scrape_url(&url).await?;
+        shared.push(start.elapsed());
+    }
+
+    let fresh = summarize(fresh);
+    let shared = summarize(shared);
+    println!("{:<22} {:>10} {:>10} {:>10}", "client", "mean", "p50", "p95");
+    for (name, stats) in [("new client per scrape", &fresh), ("shared pooled client", &shared)] {
+        println!(
+            "{:<22} {:>10.2?} {:>10.2?} {:>10.2?}",
+            name, stats.mean, stats.p50, stats.p95
+        );
+    }
+    println!(
+        "mean latency saved per scrape: {:.2?}",
+        fresh.mean.saturating_sub(shared.mean)
+    );
+
+    Ok(())
+}
@@ -0,0 +1,166 @@
+use crate::error::FetchError;
+use anyhow::Result;
+use tracing::warn;
+
+/// Default cap on how much of a response body is downloaded (`MAX_DOWNLOAD_MB`)
+const DEFAULT_MAX_DOWNLOAD_MB: usize = 10;
+
+/// Bytes inspected to decide whether an untyped (`application/octet-stream`) body is text
+const SNIFF_BYTES: usize = 1024;
+
+/// A truncated body that can't be used partially (e.g. a PDF, whose index is at the end)
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("size_limit_exceeded: {url} is larger than the {limit_bytes} byte download limit (MAX_DOWNLOAD_MB)")]
+pub struct BodyTooLarge {
+    pub url: String,
+    pub limit_bytes: usize,
+}
+
+/// A body read up to the download limit
+#[derive(Debug, Default)]
+pub struct LimitedBody {
+    pub bytes: Vec<u8>,
+    /// The body was longer than the limit and has been cut off
+    pub truncated: bool,
+}
+
+/// Configured download cap in bytes
+pub fn max_download_bytes() -> usize {
+    std::env::var("MAX_DOWNLOAD_MB")
+        .ok()
+        .and_then(|v| v.trim().parse::<usize>().ok())
+        .unwrap_or(DEFAULT_MAX_DOWNLOAD_MB)
+        .max(1)
+        * 1024
+        * 1024
+}
+
+/// Content types we know how to extract. `None` means the type is unknown
+/// or generic and the body has to be sniffed.
+fn classify(content_type: &str) -> Option<bool> {
+    let mime = content_type.split(';').next().unwrap_or_default().trim().to_lowercase();
+    match mime.as_str() {
+        "" | "application/octet-stream" | "binary/octet-stream" | "application/unknown" => None,
+        "application/pdf" | "application/x-pdf" | "application/xhtml+xml" | "application/xml"
+        | "application/json" | "application/javascript" | "application/x-javascript" => Some(true),
+        m if m.starts_with("text/") || m.ends_with("+xml") || m.ends_with("+json") => Some(true),
+        _ => Some(false),
+    }
+}
+
+/// Looks like a PDF or text (no NUL bytes near the start)
+fn sniff_supported(prefix: &[u8]) -> bool {
+    let head = &prefix[..prefix.len().min(SNIFF_BYTES)];
+    head.starts_with(b"%PDF-") || !head.contains(&0)
+}
+
+/// Stream a response body, stopping at `max_bytes`.
+/// Binary content types are refused before any of the body is downloaded;
+/// untyped bodies are refused after the first chunk if they don't look like text or PDF.
+pub async fn read_limited(
+    mut response: reqwest::Response,
+    url: &str,
+    content_type: &str,
+    max_bytes: usize,
+) -> Result<LimitedBody> {
+    // The response isn't something we can extract text from (images, archives, video, ...)
+    let unsupported = || FetchError::ContentTypeUnsupported { url: url.to_string(), content_type: content_type.to_string() };
+    let supported = classify(content_type);
+    if supported == Some(false) {
+        return Err(unsupported().into());
+    }
+
+    if let Some(len) = response.content_length() {
+        if len as usize > max_bytes {
+            warn!("{} declares {} bytes; reading only the first {}", url, len, max_bytes);
+        }
+    }
+
+    let mut body = LimitedBody {
+        bytes: Vec::with_capacity(response.content_length().map(|l| l as usize).unwrap_or(0).min(max_bytes)),
+        truncated: false,
+    };
+    let mut sniffed = supported.is_some();
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| FetchError::from_reqwest(url, e))?
+    {
+        let room = max_bytes - body.bytes.len();
+        if chunk.len() > room {
+            body.bytes.extend_from_slice(&chunk[..room]);
+            body.truncated = true;
+        } else {
+            body.bytes.extend_from_slice(&chunk);
+        }
+        if !sniffed && body.bytes.len() >= SNIFF_BYTES.min(max_bytes) {
+            if !sniff_supported(&body.bytes) {
+                return Err(unsupported().into());
+            }
+            sniffed = true;
+        }
+        if body.truncated {
+            // Dropping the response closes the connection without downloading the rest
+            warn!("Body of {} exceeded {} bytes; keeping the partial content", url, max_bytes);
+            break;
+        }
+    }
+    if !sniffed && !sniff_supported(&body.bytes) {
+        return Err(unsupported().into());
+    }
+    Ok(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_content_types() {
+        assert_eq!(classify("text/html; charset=utf-8"), Some(true));
+        assert_eq!(classify("application/rss+xml"), Some(true));
+        assert_eq!(classify("application/ld+json"), Some(true));
+        assert_eq!(classify("application/pdf"), Some(true));
+        assert_eq!(classify("image/png"), Some(false));
+        assert_eq!(classify("application/zip"), Some(false));
+        assert_eq!(classify("application/octet-stream"), None);
+        assert_eq!(classify(""), None);
+
+        assert!(sniff_supported(b"%PDF-1.7\x00\x01"));
+        assert!(sniff_supported(b"<html>plain text</html>"));
+        assert!(!sniff_supported(b"PK\x03\x04\x00\x00"));
+    }
+
+    #[tokio::test]
+    async fn test_streaming_limit_and_early_abort() {
+        use axum::{routing::get, Router};
+
+        let app = Router::new()
+            .route("/big", get(|| async { ([("content-type", "text/html")], "<p>word </p>".repeat(100_000)) }))
+            .route("/image", get(|| async { ([("content-type", "image/png")], vec![0u8; 4096]) }))
+            .route("/blob", get(|| async { ([("content-type", "application/octet-stream")], vec![0u8; 4096]) }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        let client = reqwest::Client::new();
+
+        let url = format!("http://{}/big", addr);
+        let response = client.get(&url).send().await.unwrap();
+        let body = read_limited(response, &url, "text/html", 64 * 1024).await.unwrap();
+        assert!(body.truncated);
+        assert_eq!(body.bytes.len(), 64 * 1024);
+
+        for path in ["image", "blob"] {
+            let url = format!("http://{}/{}", addr, path);
+            let response = client.get(&url).send().await.unwrap();
+            let content_type = response.headers()["content-type"].to_str().unwrap().to_string();
+            let err = read_limited(response, &url, &content_type, 64 * 1024).await.unwrap_err();
+            assert!(
+                matches!(err.downcast_ref::<FetchError>(), Some(FetchError::ContentTypeUnsupported { .. })),
+                "{}: {}",
+                path,
+                err
+            );
+        }
+    }
+}
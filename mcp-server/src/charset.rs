@@ -0,0 +1,142 @@
+use encoding_rs::Encoding;
+use regex::bytes::Regex;
+use std::sync::OnceLock;
+
+/// How many leading bytes are scanned for a `<meta>` charset declaration
+const META_PRESCAN_BYTES: usize = 4096;
+
+/// Where the encoding decision came from, in order of precedence
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CharsetSource {
+    Bom,
+    HttpHeader,
+    MetaTag,
+    Detected,
+}
+
+/// Result of sniffing a body's character encoding
+#[derive(Debug, Clone, Copy)]
+pub struct Charset {
+    pub encoding: &'static Encoding,
+    pub source: CharsetSource,
+}
+
+/// Determine a document's encoding the way browsers do:
+/// byte order mark, then the Content-Type charset, then `<meta charset>` /
+/// `<meta http-equiv="Content-Type">` in the first few KB, then statistical
+/// detection (hinted by the host's TLD).
+pub fn sniff(body: &[u8], content_type: &str, host: Option<&str>) -> Charset {
+    if let Some((encoding, _)) = Encoding::for_bom(body) {
+        return Charset { encoding, source: CharsetSource::Bom };
+    }
+    if let Some(encoding) = header_charset(content_type) {
+        return Charset { encoding, source: CharsetSource::HttpHeader };
+    }
+    if let Some(encoding) = meta_charset(body) {
+        return Charset { encoding, source: CharsetSource::MetaTag };
+    }
+    let mut detector = chardetng::EncodingDetector::new();
+    detector.feed(body, true);
+    let tld = host.and_then(|h| h.rsplit('.').next()).map(|t| t.as_bytes());
+    Charset { encoding: detector.guess(tld, true), source: CharsetSource::Detected }
+}
+
+/// Sniff and transcode a body to UTF-8 (malformed sequences become U+FFFD)
+pub fn decode(body: &[u8], content_type: &str, host: Option<&str>) -> (String, Charset) {
+    let charset = sniff(body, content_type, host);
+    // `decode` strips a BOM for any encoding and handles BOM-vs-label precedence itself
+    let (text, _, _) = charset.encoding.decode(body);
+    (text.into_owned(), charset)
+}
+
+fn header_charset(content_type: &str) -> Option<&'static Encoding> {
+    content_type
+        .split(';')
+        .skip(1)
+        .filter_map(|param| {
+            let (name, value) = param.split_once('=')?;
+            name.trim().eq_ignore_ascii_case("charset").then(|| value.trim().trim_matches(|c| c == '"' || c == '\''))
+        })
+        .find_map(|label| Encoding::for_label(label.as_bytes()))
+}
+
+fn meta_charset(body: &[u8]) -> Option<&'static Encoding> {
+    static META: OnceLock<Regex> = OnceLock::new();
+    static CHARSET: OnceLock<Regex> = OnceLock::new();
+    let meta = META.get_or_init(|| Regex::new(r"(?i-u)<meta\b[^>]*>").expect("valid regex"));
+    let charset = CHARSET.get_or_init(|| {
+        Regex::new(r#"(?i-u)charset\s*=\s*["']?\s*([a-z0-9_:.+-]+)"#).expect("valid regex")
+    });
+
+    let head = &body[..body.len().min(META_PRESCAN_BYTES)];
+    for tag in meta.find_iter(head) {
+        let tag = tag.as_bytes();
+        // Covers both <meta charset="..."> and http-equiv's content="text/html; charset=..."
+        let Some(label) = charset.captures(tag).and_then(|c| c.get(1)) else {
+            continue;
+        };
+        if let Some(encoding) = Encoding::for_label(label.as_bytes()) {
+            // A meta tag can't truthfully declare UTF-16 (the tag itself was ASCII-readable)
+            if encoding == encoding_rs::UTF_16LE || encoding == encoding_rs::UTF_16BE {
+                return Some(encoding_rs::UTF_8);
+            }
+            // x-user-defined is treated as windows-1252 by browsers
+            if encoding == encoding_rs::X_USER_DEFINED {
+                return Some(encoding_rs::WINDOWS_1252);
+            }
+            return Some(encoding);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_precedence() {
+        let (sjis, _, _) = encoding_rs::SHIFT_JIS.encode("<html><head><meta charset=\"Shift_JIS\"></head><body>日本語のテキスト</body></html>");
+
+        // Meta tag wins when the header has no charset
+        let (text, charset) = decode(&sjis, "text/html", None);
+        assert_eq!(charset.encoding, encoding_rs::SHIFT_JIS);
+        assert_eq!(charset.source, CharsetSource::MetaTag);
+        assert!(text.contains("日本語のテキスト"));
+
+        // Header charset wins over meta
+        let charset = sniff(&sjis, "text/html; charset=\"EUC-JP\"", None);
+        assert_eq!(charset.encoding, encoding_rs::EUC_JP);
+        assert_eq!(charset.source, CharsetSource::HttpHeader);
+
+        // BOM wins over everything
+        let mut bom = vec![0xEF, 0xBB, 0xBF];
+        bom.extend_from_slice("<p>héllo</p>".as_bytes());
+        let (text, charset) = decode(&bom, "text/html; charset=windows-1252", None);
+        assert_eq!(charset.source, CharsetSource::Bom);
+        assert_eq!(text, "<p>héllo</p>");
+    }
+
+    #[test]
+    fn test_http_equiv_and_detection() {
+        let (cp1251, _, _) = encoding_rs::WINDOWS_1251.encode(
+            "<meta http-equiv='Content-Type' content='text/html; charset=windows-1251'><p>Привет, мир</p>",
+        );
+        let (text, charset) = decode(&cp1251, "", None);
+        assert_eq!(charset.encoding, encoding_rs::WINDOWS_1251);
+        assert!(text.contains("Привет, мир"));
+
+        // No declaration at all: statistical detection
+        let (gbk, _, _) = encoding_rs::GBK.encode(
+            "<p>这是一个没有声明字符集的中文网页，用于测试统计检测。中文内容需要足够长才能被可靠地识别出来。</p>",
+        );
+        let (text, charset) = decode(&gbk, "text/html", Some("example.cn"));
+        assert_eq!(charset.source, CharsetSource::Detected);
+        assert_eq!(charset.encoding, encoding_rs::GBK);
+        assert!(text.contains("中文网页"));
+
+        // Plain UTF-8 is detected as such
+        let charset = sniff("<p>naïve café</p>".as_bytes(), "text/html", None);
+        assert_eq!(charset.encoding, encoding_rs::UTF_8);
+    }
+}
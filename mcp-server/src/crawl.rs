@@ -0,0 +1,316 @@
+use crate::error::FetchError;
+use crate::pagination::page_key;
+use crate::scrape;
+use crate::types::*;
+use crate::AppState;
+use anyhow::Result;
+use futures::stream::{self, StreamExt};
+use std::collections::HashSet;
+use std::sync::Arc;
+use tracing::{info, warn};
+use url::Url;
+
+pub const DEFAULT_MAX_DEPTH: usize = 2;
+pub const MAX_DEPTH_LIMIT: usize = 5;
+pub const DEFAULT_MAX_PAGES: usize = 20;
+pub const MAX_PAGES_LIMIT: usize = 100;
+
+/// Pages scraped at once; per-host politeness limits still apply underneath
+const CRAWL_CONCURRENCY: usize = 4;
+
+/// Length of the content-based summary when a page has no meta description
+const SUMMARY_CHARS: usize = 300;
+
+/// Link targets that are never HTML or text
+const SKIPPED_EXTENSIONS: &[&str] = &[
+    "png", "jpg", "jpeg", "gif", "svg", "webp", "ico", "bmp", "css", "js", "mjs", "map", "woff", "woff2", "ttf",
+    "zip", "gz", "tgz", "tar", "bz2", "xz", "7z", "rar", "exe", "dmg", "msi", "deb", "rpm", "iso", "mp3", "mp4",
+    "webm", "avi", "mov", "wav", "ogg",
+];
+
+#[derive(Debug, Clone)]
+pub struct CrawlOptions {
+    /// Link distance from the seed to crawl (the seed is depth 0)
+    pub max_depth: usize,
+    /// Maximum pages fetched, including failures and duplicates
+    pub max_pages: usize,
+    /// Only follow links whose path starts with one of these prefixes (any path when empty)
+    pub include_paths: Vec<String>,
+    /// Never follow links whose path starts with one of these prefixes
+    pub exclude_paths: Vec<String>,
+    /// Stay on the seed's host (a leading `www.` is ignored)
+    pub same_host: bool,
+}
+
+impl Default for CrawlOptions {
+    fn default() -> Self {
+        Self {
+            max_depth: DEFAULT_MAX_DEPTH,
+            max_pages: DEFAULT_MAX_PAGES,
+            include_paths: Vec::new(),
+            exclude_paths: Vec::new(),
+            same_host: true,
+        }
+    }
+}
+
+impl CrawlOptions {
+    fn in_scope(&self, url: &Url, seed: &Url) -> bool {
+        if !matches!(url.scheme(), "http" | "https") {
+            return false;
+        }
+        if self.same_host && bare_host(url) != bare_host(seed) {
+            return false;
+        }
+        let path = url.path();
+        let extension = path.rsplit('/').next().and_then(|f| f.rsplit_once('.')).map(|(_, ext)| ext.to_lowercase());
+        if extension.map(|ext| SKIPPED_EXTENSIONS.contains(&ext.as_str())).unwrap_or(false) {
+            return false;
+        }
+        (self.include_paths.is_empty() || self.include_paths.iter().any(|p| path.starts_with(p.as_str())))
+            && !self.exclude_paths.iter().any(|p| path.starts_with(p.as_str()))
+    }
+}
+
+/// Breadth-first crawl from `seed` over the content links of each page, within the
+/// depth/page limits and path filters. Pages are deduplicated by canonical URL.
+pub async fn crawl_site(state: &Arc<AppState>, seed: &str, options: CrawlOptions) -> Result<CrawlResponse> {
+    let seed_url = Url::parse(seed).map_err(|e| FetchError::invalid_url(seed, e))?;
+    if !matches!(seed_url.scheme(), "http" | "https") {
+        return Err(FetchError::invalid_url(seed, "must start with http:// or https://").into());
+    }
+    let max_depth = options.max_depth.min(MAX_DEPTH_LIMIT);
+    let max_pages = options.max_pages.clamp(1, MAX_PAGES_LIMIT);
+    info!("Crawling {} (depth {}, up to {} pages)", seed_url, max_depth, max_pages);
+
+    let mut seen = HashSet::from([page_key(&seed_url)]);
+    let mut canonical_seen = HashSet::new();
+    let mut frontier = vec![seed_url.clone()];
+    let mut fetched = 0;
+    let mut response = CrawlResponse {
+        seed_url: seed_url.to_string(),
+        pages: Vec::new(),
+        errors: Vec::new(),
+        duplicates: 0,
+        unvisited: 0,
+        table_of_contents: String::new(),
+    };
+
+    for depth in 0..=max_depth {
+        let budget = max_pages - fetched;
+        if frontier.len() > budget {
+            response.unvisited += frontier.len() - budget;
+            frontier.truncate(budget);
+        }
+        fetched += frontier.len();
+
+        let results: Vec<_> = stream::iter(frontier.drain(..))
+            .map(|url| async move {
+                let result = scrape::scrape_url(state, url.as_str()).await;
+                (url, result)
+            })
+            .buffered(CRAWL_CONCURRENCY)
+            .collect()
+            .await;
+
+        for (url, result) in results {
+            let page = match result {
+                Ok(page) => page,
+                Err(e) => {
+                    warn!("Crawl failed for {}: {}", url, e);
+                    response.errors.push(CrawlError { url: url.to_string(), depth, error: e.to_string() });
+                    continue;
+                }
+            };
+            let canonical = page.canonical_url.as_deref().and_then(|c| Url::parse(c).ok());
+            if !canonical_seen.insert(page_key(canonical.as_ref().unwrap_or(&url))) {
+                response.duplicates += 1;
+                continue;
+            }
+            if let Some(canonical) = &canonical {
+                seen.insert(page_key(canonical));
+            }
+
+            for link in &page.links {
+                let Ok(mut link_url) = Url::parse(&link.url) else { continue };
+                link_url.set_fragment(None);
+                if options.in_scope(&link_url, &seed_url) && seen.insert(page_key(&link_url)) {
+                    if depth < max_depth {
+                        frontier.push(link_url);
+                    } else {
+                        response.unvisited += 1;
+                    }
+                }
+            }
+            response.pages.push(crawl_page(page, depth));
+        }
+
+        if frontier.is_empty() {
+            break;
+        }
+        if fetched >= max_pages {
+            response.unvisited += frontier.len();
+            break;
+        }
+    }
+
+    response.table_of_contents = table_of_contents(&response.pages);
+    info!(
+        "Crawl of {} finished: {} pages, {} errors, {} duplicates, {} unvisited",
+        seed_url,
+        response.pages.len(),
+        response.errors.len(),
+        response.duplicates,
+        response.unvisited
+    );
+    Ok(response)
+}
+
+fn crawl_page(page: ScrapeResponse, depth: usize) -> CrawlPage {
+    let summary = if page.meta_description.trim().is_empty() {
+        let text = page.clean_content.split_whitespace().collect::<Vec<_>>().join(" ");
+        if text.chars().count() > SUMMARY_CHARS {
+            let cut: String = text.chars().take(SUMMARY_CHARS).collect();
+            let cut = cut.rsplit_once(' ').map(|(head, _)| head.to_string()).unwrap_or(cut);
+            format!("{}…", cut)
+        } else {
+            text
+        }
+    } else {
+        page.meta_description.trim().to_string()
+    };
+    CrawlPage {
+        url: page.url,
+        canonical_url: page.canonical_url,
+        title: page.title,
+        depth,
+        word_count: page.word_count,
+        summary,
+        headings: page.headings.into_iter().filter(|h| matches!(h.level.as_str(), "h1" | "h2" | "h3")).collect(),
+    }
+}
+
+/// Pages ordered by URL path and indented by path depth, each followed by its h2/h3 headings
+fn table_of_contents(pages: &[CrawlPage]) -> String {
+    let segments = |url: &str| {
+        Url::parse(url)
+            .ok()
+            .map(|u| u.path_segments().map(|s| s.filter(|s| !s.is_empty()).count()).unwrap_or(0))
+            .unwrap_or(0)
+    };
+    let mut ordered: Vec<&CrawlPage> = pages.iter().collect();
+    ordered.sort_by_key(|p| Url::parse(&p.url).map(|u| u.path().to_string()).unwrap_or_default());
+    let base_depth = ordered.iter().map(|p| segments(&p.url)).min().unwrap_or(0);
+
+    let mut lines = Vec::new();
+    for page in ordered {
+        let indent = "  ".repeat((segments(&page.url) - base_depth).min(3));
+        let title = if page.title.is_empty() { page.url.as_str() } else { page.title.as_str() };
+        lines.push(format!("{}- [{}]({})", indent, title, page.url));
+        for heading in &page.headings {
+            let level = match heading.level.as_str() {
+                "h2" => 1,
+                "h3" => 2,
+                _ => continue,
+            };
+            lines.push(format!("{}{}- {}", indent, "  ".repeat(level), heading.text));
+        }
+    }
+    lines.join("\n")
+}
+
+/// Text rendering of a crawl for the MCP tools
+pub fn render_text(crawl: &CrawlResponse) -> String {
+    let mut out = format!(
+        "Crawled {} pages from {} ({} errors, {} duplicates, {} unvisited links)\n",
+        crawl.pages.len(),
+        crawl.seed_url,
+        crawl.errors.len(),
+        crawl.duplicates,
+        crawl.unvisited
+    );
+    out.push_str("\n**Pages:**\n");
+    for (i, page) in crawl.pages.iter().enumerate() {
+        out.push_str(&format!(
+            "{}. **{}** (depth {}, {} words)\n   {}\n   {}\n",
+            i + 1,
+            page.title,
+            page.depth,
+            page.word_count,
+            page.url,
+            page.summary
+        ));
+    }
+    if !crawl.errors.is_empty() {
+        out.push_str("\n**Errors:**\n");
+        for error in &crawl.errors {
+            out.push_str(&format!("- {} (depth {}): {}\n", error.url, error.depth, error.error));
+        }
+    }
+    out.push_str("\n**Table of Contents:**\n");
+    out.push_str(&crawl.table_of_contents);
+    out
+}
+
+fn bare_host(url: &Url) -> String {
+    let host = url.host_str().unwrap_or_default().to_lowercase();
+    host.strip_prefix("www.").map(str::to_string).unwrap_or(host)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_crawl_limits_and_dedup() {
+        use axum::{extract::Path, routing::get, Router};
+
+        fn page(title: &str, canonical: Option<&str>, links: &[&str]) -> String {
+            let canonical = canonical.map(|c| format!(r#"<link rel="canonical" href="{}">"#, c)).unwrap_or_default();
+            let links: String = links.iter().map(|l| format!(r#"<a href="{}">{}</a> "#, l, l)).collect();
+            format!(
+                "<html><head><title>{}</title>{}</head><body><main><h1>{}</h1><h2>Overview of {}</h2><p>{}</p><p>{}</p></main></body></html>",
+                title,
+                canonical,
+                title,
+                title,
+                "Documentation text for the crawler test. ".repeat(10),
+                links
+            )
+        }
+        let app = Router::new().route("/*path", get(|Path(path): Path<String>| async move {
+            let body = match path.as_str() {
+                "docs/" => page("Index", None, &["/docs/a", "/docs/b", "/docs/b?ref=nav", "/blog/post", "/docs/logo.png"]),
+                "docs/a" => page("Page A", None, &["/docs/a/deep", "/docs/", "/docs/b"]),
+                "docs/b" => page("Page B", Some("/docs/b"), &["/docs/a", "/docs/", "/docs/c"]),
+                "docs/a/deep" => page("Deep", None, &["/docs/a/deeper", "/docs/", "/docs/a"]),
+                other => page(other, None, &[]),
+            };
+            ([("content-type", "text/html")], body)
+        }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let state = AppState::new("http://localhost:8888".to_string(), reqwest::Client::new())
+            .with_ssrf_policy(crate::ssrf::SsrfPolicy::disabled());
+        let state = Arc::new(state);
+
+        let options = CrawlOptions { max_depth: 2, include_paths: vec!["/docs".to_string()], ..Default::default() };
+        let crawl = crawl_site(&state, &format!("http://{}/docs/", addr), options).await.unwrap();
+        let titles: Vec<&str> = crawl.pages.iter().map(|p| p.title.as_str()).collect();
+        // /docs/b?ref=nav declares /docs/b as canonical; /blog and the image are out of scope
+        assert_eq!(titles, vec!["Index", "Page A", "Page B", "Deep", "docs/c"]);
+        assert_eq!(crawl.duplicates, 1);
+        assert_eq!(crawl.pages[3].depth, 2);
+        // /docs/a/deeper is beyond max_depth
+        assert_eq!(crawl.unvisited, 1);
+        assert!(crawl.table_of_contents.starts_with(&format!("- [Index](http://{}/docs/)\n  - Overview of Index", addr)));
+        assert!(crawl.table_of_contents.contains(&format!("    - [Deep](http://{}/docs/a/deep)", addr)));
+
+        let options = CrawlOptions { max_pages: 2, ..Default::default() };
+        let crawl = crawl_site(&state, &format!("http://{}/docs/", addr), options).await.unwrap();
+        assert_eq!(crawl.pages.len(), 2);
+        assert!(crawl.unvisited > 0);
+    }
+}
@@ -0,0 +1,437 @@
+use anyhow::{Context, Result};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime};
+use tracing::{debug, info, warn};
+
+const DEFAULT_MAX_MB: u64 = 512;
+const DEFAULT_TTL_SECS: u64 = 60 * 60 * 24 * 7;
+/// Search results go stale much faster than pages; same as the in-memory search cache
+const DEFAULT_SEARCH_TTL_SECS: u64 = 60 * 10;
+
+/// Eviction trims the cache down to this fraction of `max_bytes` so it doesn't run on every write
+const EVICT_TARGET_RATIO: f64 = 0.9;
+
+/// Disk cache configuration
+#[derive(Debug, Clone)]
+pub struct DiskCacheConfig {
+    pub dir: PathBuf,
+    /// Total size cap across all namespaces
+    pub max_bytes: u64,
+    /// Entries older than this are treated as missing and deleted on read
+    pub ttl: Duration,
+    /// Per-namespace overrides of `ttl`
+    pub namespace_ttls: HashMap<String, Duration>,
+    /// Gzip entries on disk
+    pub compress: bool,
+}
+
+impl DiskCacheConfig {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            max_bytes: DEFAULT_MAX_MB * 1024 * 1024,
+            ttl: Duration::from_secs(DEFAULT_TTL_SECS),
+            namespace_ttls: HashMap::from([(
+                crate::search::SEARCH_NAMESPACE.to_string(),
+                Duration::from_secs(DEFAULT_SEARCH_TTL_SECS),
+            )]),
+            compress: true,
+        }
+    }
+
+    /// TTL for entries in `namespace`
+    pub fn ttl_for(&self, namespace: &str) -> Duration {
+        self.namespace_ttls.get(namespace).copied().unwrap_or(self.ttl)
+    }
+
+    /// Read `DISK_CACHE_DIR`, `DISK_CACHE_MAX_MB`, `DISK_CACHE_TTL_SECS`, `DISK_CACHE_SEARCH_TTL_SECS`
+    /// and `DISK_CACHE_COMPRESS`.
+    /// Returns `None` (disk cache disabled) when `DISK_CACHE_DIR` is unset or empty.
+    pub fn from_env() -> Option<Self> {
+        let dir = std::env::var("DISK_CACHE_DIR").ok().filter(|d| !d.trim().is_empty())?;
+        let env_num = |name: &str| std::env::var(name).ok().and_then(|v| v.trim().parse::<u64>().ok());
+        let mut config = Self::new(dir.trim());
+        if let Some(mb) = env_num("DISK_CACHE_MAX_MB") {
+            config.max_bytes = mb.max(1) * 1024 * 1024;
+        }
+        if let Some(secs) = env_num("DISK_CACHE_TTL_SECS") {
+            config.ttl = Duration::from_secs(secs);
+        }
+        if let Some(secs) = env_num("DISK_CACHE_SEARCH_TTL_SECS") {
+            config.namespace_ttls.insert(crate::search::SEARCH_NAMESPACE.to_string(), Duration::from_secs(secs));
+        }
+        if let Ok(v) = std::env::var("DISK_CACHE_COMPRESS") {
+            config.compress = !matches!(v.trim().to_lowercase().as_str(), "0" | "false" | "off" | "no");
+        }
+        Some(config)
+    }
+}
+
+/// On-disk envelope; the full key is stored so hash collisions read as misses
+#[derive(Serialize, Deserialize)]
+struct Envelope<T> {
+    key: String,
+    stored_at: chrono::DateTime<chrono::Utc>,
+    value: T,
+}
+
+/// Snapshot of disk cache usage and counters
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct DiskCacheStats {
+    pub dir: String,
+    pub entries: u64,
+    pub size_bytes: u64,
+    pub max_bytes: u64,
+    pub ttl_secs: u64,
+    pub search_ttl_secs: u64,
+    pub compress: bool,
+    pub hits: u64,
+    pub misses: u64,
+    pub writes: u64,
+    pub expired: u64,
+    pub evictions: u64,
+}
+
+/// Persistent cache layer under the in-memory moka caches.
+/// Entries are JSON files (optionally gzipped) grouped by namespace, e.g. `<dir>/scrape/<hash>.json.gz`;
+/// the oldest files are evicted once the total size passes `max_bytes`.
+pub struct DiskCache {
+    config: DiskCacheConfig,
+    size_bytes: AtomicU64,
+    entries: AtomicU64,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    writes: AtomicU64,
+    expired: AtomicU64,
+    evictions: AtomicU64,
+    evicting: tokio::sync::Mutex<()>,
+}
+
+impl std::fmt::Debug for DiskCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DiskCache").field("config", &self.config).finish()
+    }
+}
+
+impl DiskCache {
+    /// Create the cache directory and take stock of what a previous run left in it
+    pub fn open(config: DiskCacheConfig) -> Result<Self> {
+        std::fs::create_dir_all(&config.dir)
+            .with_context(|| format!("Failed to create disk cache directory {}", config.dir.display()))?;
+        let files = list_files(&config.dir);
+        let size: u64 = files.iter().map(|(_, len, _)| len).sum();
+        info!(
+            "Disk cache at {} ({} entries, {} bytes, cap {} bytes)",
+            config.dir.display(),
+            files.len(),
+            size,
+            config.max_bytes
+        );
+        Ok(Self {
+            config,
+            size_bytes: AtomicU64::new(size),
+            entries: AtomicU64::new(files.len() as u64),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            writes: AtomicU64::new(0),
+            expired: AtomicU64::new(0),
+            evictions: AtomicU64::new(0),
+            evicting: tokio::sync::Mutex::new(()),
+        })
+    }
+
+    /// `DiskCacheConfig::from_env`, logging (not failing) when the directory is unusable
+    pub fn from_env() -> Option<Self> {
+        let config = DiskCacheConfig::from_env()?;
+        match Self::open(config) {
+            Ok(cache) => Some(cache),
+            Err(e) => {
+                warn!("Disk cache disabled: {:#}", e);
+                None
+            }
+        }
+    }
+
+    pub fn config(&self) -> &DiskCacheConfig {
+        &self.config
+    }
+
+    fn path_for(&self, namespace: &str, key: &str) -> PathBuf {
+        let ext = if self.config.compress { "json.gz" } else { "json" };
+        self.config
+            .dir
+            .join(namespace)
+            .join(format!("{:016x}.{}", fnv1a(key.as_bytes()), ext))
+    }
+
+    /// Read an entry; expired, corrupt or colliding entries count as misses
+    pub async fn get<T: DeserializeOwned>(&self, namespace: &str, key: &str) -> Option<T> {
+        let path = self.path_for(namespace, key);
+        let bytes = match tokio::fs::read(&path).await {
+            Ok(bytes) => bytes,
+            Err(_) => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                return None;
+            }
+        };
+        let envelope = match decode::<T>(&bytes, self.config.compress) {
+            Ok(envelope) => envelope,
+            Err(e) => {
+                debug!("Dropping unreadable disk cache entry {}: {}", path.display(), e);
+                self.remove_file(&path).await;
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                return None;
+            }
+        };
+        if envelope.key != key {
+            self.misses.fetch_add(1, Ordering::Relaxed);
+            return None;
+        }
+        let age = (chrono::Utc::now() - envelope.stored_at).to_std().unwrap_or_default();
+        if age > self.config.ttl_for(namespace) {
+            self.remove_file(&path).await;
+            self.expired.fetch_add(1, Ordering::Relaxed);
+            self.misses.fetch_add(1, Ordering::Relaxed);
+            return None;
+        }
+        self.hits.fetch_add(1, Ordering::Relaxed);
+        Some(envelope.value)
+    }
+
+    /// Write an entry, replacing any previous one, then evict if over the size cap.
+    /// Failures are logged: the disk layer is best-effort and never fails a request.
+    pub async fn put<T: Serialize>(&self, namespace: &str, key: &str, value: &T) {
+        if let Err(e) = self.try_put(namespace, key, value).await {
+            warn!("Disk cache write failed for {}: {:#}", key, e);
+            return;
+        }
+        if self.size_bytes.load(Ordering::Relaxed) > self.config.max_bytes {
+            self.evict().await;
+        }
+    }
+
+    async fn try_put<T: Serialize>(&self, namespace: &str, key: &str, value: &T) -> Result<()> {
+        let path = self.path_for(namespace, key);
+        let envelope = Envelope { key: key.to_string(), stored_at: chrono::Utc::now(), value };
+        let bytes = encode(&envelope, self.config.compress)?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let previous = tokio::fs::metadata(&path).await.ok().map(|m| m.len());
+        // Write to a temp file and rename so readers never see a partial entry
+        let tmp = path.with_extension(format!("tmp{}", std::process::id()));
+        tokio::fs::write(&tmp, &bytes).await?;
+        tokio::fs::rename(&tmp, &path).await?;
+
+        match previous {
+            Some(len) => {
+                let _ = self.size_bytes.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |s| Some(s.saturating_sub(len)));
+            }
+            None => {
+                self.entries.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        self.size_bytes.fetch_add(bytes.len() as u64, Ordering::Relaxed);
+        self.writes.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+
+    pub async fn remove(&self, namespace: &str, key: &str) {
+        let path = self.path_for(namespace, key);
+        self.remove_file(&path).await;
+    }
+
+    async fn remove_file(&self, path: &Path) {
+        if let Ok(meta) = tokio::fs::metadata(path).await {
+            if tokio::fs::remove_file(path).await.is_ok() {
+                self.release(meta.len());
+            }
+        }
+    }
+
+    fn release(&self, len: u64) {
+        let _ = self.size_bytes.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |s| Some(s.saturating_sub(len)));
+        let _ = self.entries.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| Some(n.saturating_sub(1)));
+    }
+
+    /// Delete least recently written files until the cache is below the eviction target
+    async fn evict(&self) {
+        let Ok(_guard) = self.evicting.try_lock() else {
+            return; // another writer is already evicting
+        };
+        let dir = self.config.dir.clone();
+        let mut files = tokio::task::spawn_blocking(move || list_files(&dir)).await.unwrap_or_default();
+        files.sort_by_key(|(_, _, modified)| *modified);
+
+        // Resync counters with what is actually on disk
+        let mut size: u64 = files.iter().map(|(_, len, _)| len).sum();
+        self.size_bytes.store(size, Ordering::Relaxed);
+        self.entries.store(files.len() as u64, Ordering::Relaxed);
+
+        let target = (self.config.max_bytes as f64 * EVICT_TARGET_RATIO) as u64;
+        let mut evicted = 0u64;
+        for (path, len, _) in files {
+            if size <= target {
+                break;
+            }
+            if tokio::fs::remove_file(&path).await.is_ok() {
+                size = size.saturating_sub(len);
+                self.release(len);
+                evicted += 1;
+            }
+        }
+        if evicted > 0 {
+            self.evictions.fetch_add(evicted, Ordering::Relaxed);
+            debug!("Disk cache evicted {} entries ({} bytes remain)", evicted, size);
+        }
+    }
+
+    pub fn stats(&self) -> DiskCacheStats {
+        DiskCacheStats {
+            dir: self.config.dir.display().to_string(),
+            entries: self.entries.load(Ordering::Relaxed),
+            size_bytes: self.size_bytes.load(Ordering::Relaxed),
+            max_bytes: self.config.max_bytes,
+            ttl_secs: self.config.ttl.as_secs(),
+            search_ttl_secs: self.config.ttl_for(crate::search::SEARCH_NAMESPACE).as_secs(),
+            compress: self.config.compress,
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            writes: self.writes.load(Ordering::Relaxed),
+            expired: self.expired.load(Ordering::Relaxed),
+            evictions: self.evictions.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Stable 64-bit FNV-1a hash for file names (std's hasher isn't stable across releases)
+fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for b in bytes {
+        hash ^= *b as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+fn encode<T: Serialize>(envelope: &Envelope<&T>, compress: bool) -> Result<Vec<u8>> {
+    let json = serde_json::to_vec(envelope)?;
+    if !compress {
+        return Ok(json);
+    }
+    let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::fast());
+    encoder.write_all(&json)?;
+    Ok(encoder.finish()?)
+}
+
+fn decode<T: DeserializeOwned>(bytes: &[u8], compressed: bool) -> Result<Envelope<T>> {
+    if !compressed {
+        return Ok(serde_json::from_slice(bytes)?);
+    }
+    let mut json = Vec::new();
+    GzDecoder::new(bytes).read_to_end(&mut json)?;
+    Ok(serde_json::from_slice(&json)?)
+}
+
+/// All cache files under `dir` (one namespace level deep) with size and mtime
+fn list_files(dir: &Path) -> Vec<(PathBuf, u64, SystemTime)> {
+    let mut files = Vec::new();
+    let Ok(namespaces) = std::fs::read_dir(dir) else {
+        return files;
+    };
+    for namespace in namespaces.flatten() {
+        let Ok(entries) = std::fs::read_dir(namespace.path()) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let Ok(meta) = entry.metadata() else { continue };
+            if !meta.is_file() {
+                continue;
+            }
+            let modified = meta.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            files.push((entry.path(), meta.len(), modified));
+        }
+    }
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("disk-cache-{}-{}", name, uuid::Uuid::new_v4()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[tokio::test]
+    async fn test_roundtrip_persists_across_reopen() {
+        let dir = temp_dir("roundtrip");
+        for compress in [true, false] {
+            let mut config = DiskCacheConfig::new(dir.join(compress.to_string()));
+            config.compress = compress;
+            let cache = DiskCache::open(config.clone()).unwrap();
+            cache.put("search", "q=rust", &vec!["a".to_string(), "b".to_string()]).await;
+            assert_eq!(cache.get::<Vec<String>>("search", "q=rust").await, Some(vec!["a".into(), "b".into()]));
+            assert_eq!(cache.get::<Vec<String>>("scrape", "q=rust").await, None);
+
+            // A new instance (i.e. after a restart) sees the same entry
+            let reopened = DiskCache::open(config).unwrap();
+            assert_eq!(reopened.stats().entries, 1);
+            assert!(reopened.get::<Vec<String>>("search", "q=rust").await.is_some());
+
+            reopened.remove("search", "q=rust").await;
+            assert!(reopened.get::<Vec<String>>("search", "q=rust").await.is_none());
+            assert_eq!(reopened.stats().entries, 0);
+        }
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_ttl_and_size_cap() {
+        let dir = temp_dir("limits");
+        let mut config = DiskCacheConfig::new(&dir);
+        config.ttl = Duration::ZERO;
+        let cache = DiskCache::open(config).unwrap();
+        cache.put("scrape", "old", &"value").await;
+        tokio::time::sleep(Duration::from_millis(5)).await;
+        assert_eq!(cache.get::<String>("scrape", "old").await, None);
+        assert_eq!(cache.stats().expired, 1);
+
+        // Search results keep their own, shorter TTL regardless of the default
+        let mut config = DiskCacheConfig::new(dir.join("search"));
+        config.ttl = Duration::from_secs(3600);
+        assert_eq!(config.ttl_for("search"), Duration::from_secs(DEFAULT_SEARCH_TTL_SECS));
+        config.namespace_ttls.insert("search".to_string(), Duration::ZERO);
+        let cache = DiskCache::open(config).unwrap();
+        cache.put("search", "q=rust", &"results").await;
+        cache.put("scrape", "https://example.com", &"page").await;
+        tokio::time::sleep(Duration::from_millis(5)).await;
+        assert_eq!(cache.get::<String>("search", "q=rust").await, None);
+        assert!(cache.get::<String>("scrape", "https://example.com").await.is_some());
+
+        let mut config = DiskCacheConfig::new(dir.join("capped"));
+        config.compress = false;
+        config.max_bytes = 4096;
+        let cache = DiskCache::open(config).unwrap();
+        let body = "x".repeat(1000);
+        for i in 0..10 {
+            cache.put("scrape", &format!("https://example.com/{}", i), &body).await;
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        let stats = cache.stats();
+        assert!(stats.size_bytes <= 4096, "size {} over cap", stats.size_bytes);
+        assert!(stats.evictions > 0);
+        // Most recent entry survives eviction
+        assert!(cache.get::<String>("scrape", "https://example.com/9").await.is_some());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
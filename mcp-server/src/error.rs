@@ -0,0 +1,207 @@
+use crate::body::BodyTooLarge;
+use crate::robots::RobotsDisallowed;
+use crate::ssrf::{find_ssrf_error, SsrfError};
+use serde::{Deserialize, Serialize};
+
+/// Categorized search/scrape failure. Carried inside `anyhow::Error`; use [`ErrorInfo::from_error`]
+/// to get the category of any error, including the policy errors raised elsewhere
+/// ([`RobotsDisallowed`], [`SsrfError`], [`BodyTooLarge`]).
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum FetchError {
+    #[error("invalid URL '{url}': {reason}")]
+    InvalidUrl { url: String, reason: String },
+    #[error("{url} returned HTTP {status}")]
+    HttpStatus { url: String, status: u16 },
+    #[error("{url} did not respond in time")]
+    Timeout { url: String },
+    /// Refused by the site (401/403/451) rather than by our own policies
+    #[error("{url} refused the request ({reason})")]
+    Blocked { url: String, reason: String },
+    /// Not something we can extract text from (images, archives, video, ...)
+    #[error("{content_type} at {url} is not a text, HTML, XML, JSON or PDF document")]
+    ContentTypeUnsupported { url: String, content_type: String },
+    /// DNS failure, refused connection, dropped body or an unusable SearXNG response
+    #[error("{target}: {reason}")]
+    UpstreamUnavailable { target: String, reason: String },
+}
+
+impl FetchError {
+    pub fn invalid_url(url: &str, reason: impl std::fmt::Display) -> Self {
+        Self::InvalidUrl { url: url.to_string(), reason: reason.to_string() }
+    }
+
+    /// Categorize a non-success response status
+    pub fn from_status(url: &str, status: u16) -> Self {
+        match status {
+            401 | 403 | 451 | 999 => Self::Blocked { url: url.to_string(), reason: format!("HTTP {}", status) },
+            _ => Self::HttpStatus { url: url.to_string(), status },
+        }
+    }
+
+    /// Categorize a reqwest failure. SSRF refusals from the guarded resolver/redirect policy
+    /// are surfaced as [`SsrfError`] so they stay distinguishable from network errors.
+    pub fn from_reqwest(url: &str, err: reqwest::Error) -> anyhow::Error {
+        if let Some(ssrf) = find_ssrf_error(&err) {
+            return ssrf.into();
+        }
+        let error = if err.is_timeout() {
+            Self::Timeout { url: url.to_string() }
+        } else if let Some(status) = err.status() {
+            Self::from_status(url, status.as_u16())
+        } else if err.is_builder() {
+            Self::invalid_url(url, err)
+        } else {
+            Self::UpstreamUnavailable { target: url.to_string(), reason: err.to_string() }
+        };
+        error.into()
+    }
+}
+
+/// Error category as reported to MCP clients
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    InvalidUrl,
+    HttpStatus,
+    Timeout,
+    Blocked,
+    ContentTypeUnsupported,
+    TooLarge,
+    UpstreamUnavailable,
+    /// The tool needs configuration that isn't set (e.g. `QDRANT_URL` for research history)
+    NotConfigured,
+    Internal,
+}
+
+/// The serde name, so the text and structured forms of an error agree
+impl std::fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = serde_json::to_value(self).map_err(|_| std::fmt::Error)?;
+        f.write_str(name.as_str().unwrap_or_default())
+    }
+}
+
+/// Machine-readable summary of a failure: category, whether retrying may help, and the HTTP status if any
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErrorInfo {
+    pub code: ErrorKind,
+    pub retryable: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub http_status: Option<u16>,
+    pub message: String,
+}
+
+impl ErrorInfo {
+    pub fn new(code: ErrorKind, message: impl Into<String>) -> Self {
+        Self { code, retryable: false, http_status: None, message: message.into() }
+    }
+
+    /// Categorize any error from search/scrape, looking through its context chain.
+    /// Errors without a known category are `internal` and not retryable.
+    pub fn from_error(err: &anyhow::Error) -> Self {
+        let message = err.to_string();
+        let (code, http_status) = err
+            .chain()
+            .find_map(|cause| {
+                if let Some(e) = cause.downcast_ref::<FetchError>() {
+                    return Some(match e {
+                        FetchError::InvalidUrl { .. } => (ErrorKind::InvalidUrl, None),
+                        FetchError::HttpStatus { status, .. } => (ErrorKind::HttpStatus, Some(*status)),
+                        FetchError::Timeout { .. } => (ErrorKind::Timeout, None),
+                        FetchError::Blocked { .. } => (ErrorKind::Blocked, None),
+                        FetchError::ContentTypeUnsupported { .. } => (ErrorKind::ContentTypeUnsupported, None),
+                        FetchError::UpstreamUnavailable { .. } => (ErrorKind::UpstreamUnavailable, None),
+                    });
+                }
+                if cause.is::<RobotsDisallowed>() || cause.is::<SsrfError>() {
+                    return Some((ErrorKind::Blocked, None));
+                }
+                if cause.is::<BodyTooLarge>() {
+                    return Some((ErrorKind::TooLarge, None));
+                }
+                None
+            })
+            .unwrap_or((ErrorKind::Internal, None));
+        let retryable = match code {
+            ErrorKind::Timeout | ErrorKind::UpstreamUnavailable => true,
+            // Server errors, rate limiting and request timeouts may clear up; other 4xx won't
+            ErrorKind::HttpStatus => http_status.map(|s| s >= 500 || s == 408 || s == 425 || s == 429).unwrap_or(false),
+            _ => false,
+        };
+        Self { code, retryable, http_status, message }
+    }
+
+    /// Tool output for a failure: `<context>: <message>` followed by the code and retry hint
+    pub fn describe(&self, context: &str) -> String {
+        let status = self.http_status.map(|s| format!(", HTTP {}", s)).unwrap_or_default();
+        let hint = if self.retryable { "may succeed if retried later" } else { "retrying won't help" };
+        format!("{}: {}\n\nError code: {}{} ({})", context, self.message, self.code, status, hint)
+    }
+
+    /// MCP error for protocol-level failures, with this info as `data`
+    pub fn to_error_data(&self) -> rmcp::model::ErrorData {
+        let code = match self.code {
+            ErrorKind::InvalidUrl => rmcp::model::ErrorCode::INVALID_PARAMS,
+            _ => rmcp::model::ErrorCode::INTERNAL_ERROR,
+        };
+        rmcp::model::ErrorData::new(code, self.message.clone(), serde_json::to_value(self).ok())
+    }
+}
+
+/// Whether retrying the operation that produced `err` may succeed
+pub fn is_retryable(err: &anyhow::Error) -> bool {
+    ErrorInfo::from_error(err).retryable
+}
+
+/// Wrap `err` for `backoff::retry`: transient when retrying may help, permanent otherwise
+pub fn backoff_error(err: anyhow::Error) -> backoff::Error<anyhow::Error> {
+    if is_retryable(&err) {
+        backoff::Error::transient(err)
+    } else {
+        backoff::Error::permanent(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_categories_and_retryability() {
+        let info = |e: anyhow::Error| {
+            let info = ErrorInfo::from_error(&e);
+            (info.code, info.retryable)
+        };
+        assert_eq!(info(FetchError::from_status("u", 404).into()), (ErrorKind::HttpStatus, false));
+        assert_eq!(info(FetchError::from_status("u", 503).into()), (ErrorKind::HttpStatus, true));
+        assert_eq!(info(FetchError::from_status("u", 429).into()), (ErrorKind::HttpStatus, true));
+        assert_eq!(info(FetchError::from_status("u", 403).into()), (ErrorKind::Blocked, false));
+        assert_eq!(info(FetchError::Timeout { url: "u".into() }.into()), (ErrorKind::Timeout, true));
+        assert_eq!(info(FetchError::invalid_url("ftp://x", "unsupported scheme").into()), (ErrorKind::InvalidUrl, false));
+        let robots = RobotsDisallowed { url: "u".into(), user_agent: "bot".into() };
+        assert_eq!(info(anyhow::Error::new(robots).context("scrape failed")), (ErrorKind::Blocked, false));
+        assert_eq!(info(BodyTooLarge { url: "u".into(), limit_bytes: 1 }.into()), (ErrorKind::TooLarge, false));
+        assert_eq!(info(anyhow::anyhow!("something odd")), (ErrorKind::Internal, false));
+
+        let data = ErrorInfo::from_error(&FetchError::from_status("https://x.test/a", 502).into()).to_error_data();
+        let data = data.data.unwrap();
+        assert_eq!(data["code"], "http_status");
+        assert_eq!(data["http_status"], 502);
+        assert_eq!(data["retryable"], true);
+
+        let text = ErrorInfo::from_error(&FetchError::from_status("https://x.test/a", 404).into()).describe("Scraping failed");
+        assert!(text.starts_with("Scraping failed: https://x.test/a returned HTTP 404\n"), "{}", text);
+        assert!(text.ends_with("Error code: http_status, HTTP 404 (retrying won't help)"), "{}", text);
+    }
+
+    #[tokio::test]
+    async fn test_from_reqwest() {
+        let client = reqwest::Client::builder().timeout(std::time::Duration::from_millis(200)).build().unwrap();
+        // Nothing listens on port 9 of the loopback interface
+        let url = "http://127.0.0.1:9/";
+        let err = FetchError::from_reqwest(url, client.get(url).send().await.unwrap_err());
+        let info = ErrorInfo::from_error(&err);
+        assert!(matches!(info.code, ErrorKind::UpstreamUnavailable | ErrorKind::Timeout), "{:?}", info);
+        assert!(info.retryable);
+    }
+}
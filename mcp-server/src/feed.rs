@@ -0,0 +1,432 @@
+use crate::types::{Feed, FeedEntry, FeedLink, Heading};
+use anyhow::{anyhow, Result};
+use chrono::DateTime;
+use scraper::{ElementRef, Html, Selector};
+use std::collections::HashSet;
+use url::Url;
+
+/// Entries kept from a feed
+const MAX_ENTRIES: usize = 100;
+
+/// Length of an entry summary after HTML is stripped
+const SUMMARY_CHARS: usize = 500;
+
+/// Whether a response body is a feed rather than a web page: by feed media type, by the
+/// root element of an XML body (`<rss>`, `<feed>`, `<rdf:RDF>`), or by a JSON Feed `version`
+pub fn is_feed(content_type: &str, body: &str) -> bool {
+    let mime = content_type.split(';').next().unwrap_or_default().trim().to_lowercase();
+    if matches!(mime.as_str(), "application/rss+xml" | "application/atom+xml" | "application/feed+json") {
+        return true;
+    }
+    let body = body.trim_start_matches('\u{feff}').trim_start();
+    if body.starts_with('{') {
+        let head: String = body.chars().take(512).collect();
+        return head.contains("jsonfeed.org/version");
+    }
+    match xml_root_name(body) {
+        Some("rss") | Some("feed") => true,
+        Some(name) => name.ends_with(":RDF") && body.contains("purl.org/rss/1.0"),
+        None => false,
+    }
+}
+
+/// Name of the first element of an XML document, skipping the declaration, comments and doctype
+fn xml_root_name(mut text: &str) -> Option<&str> {
+    loop {
+        text = text.trim_start();
+        if let Some(rest) = text.strip_prefix("<?") {
+            text = &rest[rest.find("?>")? + 2..];
+        } else if let Some(rest) = text.strip_prefix("<!--") {
+            text = &rest[rest.find("-->")? + 3..];
+        } else if let Some(rest) = text.strip_prefix("<!") {
+            text = &rest[rest.find('>')? + 1..];
+        } else {
+            let rest = text.strip_prefix('<')?;
+            let end = rest.find(|c: char| c.is_whitespace() || c == '>' || c == '/').unwrap_or(rest.len());
+            return Some(&rest[..end]);
+        }
+    }
+}
+
+/// Parse an RSS 2.0 / 1.0, Atom or JSON Feed document. Relative links resolve against `base`.
+pub fn parse(text: &str, base: &Url) -> Result<Feed> {
+    let text = text.trim_start_matches('\u{feff}').trim_start();
+    let mut feed = if text.starts_with('{') { parse_json_feed(text, base)? } else { parse_xml_feed(text, base)? };
+    feed.entries.truncate(MAX_ENTRIES);
+    Ok(feed)
+}
+
+fn parse_xml_feed(text: &str, base: &Url) -> Result<Feed> {
+    let options = roxmltree::ParsingOptions { allow_dtd: true, ..Default::default() };
+    let document = roxmltree::Document::parse_with_options(text, options).map_err(|e| anyhow!("invalid feed XML: {}", e))?;
+    let root = document.root_element();
+    match root.tag_name().name() {
+        "rss" => {
+            let channel = child(root, "channel").ok_or_else(|| anyhow!("RSS feed has no <channel>"))?;
+            Ok(rss_feed(channel, channel, base))
+        }
+        // RSS 1.0: items are siblings of the channel
+        "RDF" => {
+            let channel = child(root, "channel").ok_or_else(|| anyhow!("RSS feed has no <channel>"))?;
+            Ok(rss_feed(channel, root, base))
+        }
+        "feed" => Ok(atom_feed(root, base)),
+        other => Err(anyhow!("unexpected feed root element <{}>", other)),
+    }
+}
+
+fn rss_feed(channel: roxmltree::Node, items: roxmltree::Node, base: &Url) -> Feed {
+    let entries = children(items, "item")
+        .map(|item| {
+            let summary = child_text(item, "description").or_else(|| child_text(item, "encoded"));
+            entry(
+                child_text(item, "title"),
+                child_text(item, "link").or_else(|| permalink_guid(item)).and_then(|l| resolve(base, &l)),
+                child_text(item, "pubDate").or_else(|| child_text(item, "date")),
+                child_text(item, "creator").or_else(|| child_text(item, "author")),
+                summary,
+            )
+        })
+        .collect();
+    Feed {
+        format: "rss".to_string(),
+        title: child_text(channel, "title").unwrap_or_default(),
+        link: child_text(channel, "link").and_then(|l| resolve(base, &l)),
+        description: child_text(channel, "description").map(|d| plain_text(&d, SUMMARY_CHARS)),
+        updated: child_text(channel, "lastBuildDate")
+            .or_else(|| child_text(channel, "pubDate"))
+            .or_else(|| child_text(channel, "date"))
+            .map(|d| normalize_date(&d)),
+        entries,
+    }
+}
+
+/// An RSS `<guid>` doubles as the item link unless `isPermaLink="false"`
+fn permalink_guid(item: roxmltree::Node) -> Option<String> {
+    let guid = child(item, "guid")?;
+    if guid.attribute("isPermaLink") == Some("false") {
+        return None;
+    }
+    child_text(item, "guid").filter(|g| g.starts_with("http://") || g.starts_with("https://"))
+}
+
+fn atom_feed(feed: roxmltree::Node, base: &Url) -> Feed {
+    let entries = children(feed, "entry")
+        .map(|e| {
+            entry(
+                child_text(e, "title"),
+                atom_link(e).and_then(|l| resolve(base, &l)),
+                child_text(e, "published").or_else(|| child_text(e, "updated")),
+                atom_author(e).or_else(|| atom_author(feed)),
+                child_text(e, "summary").or_else(|| child_text(e, "content")),
+            )
+        })
+        .collect();
+    Feed {
+        format: "atom".to_string(),
+        title: child_text(feed, "title").unwrap_or_default(),
+        link: atom_link(feed).and_then(|l| resolve(base, &l)),
+        description: child_text(feed, "subtitle").map(|d| plain_text(&d, SUMMARY_CHARS)),
+        updated: child_text(feed, "updated").map(|d| normalize_date(&d)),
+        entries,
+    }
+}
+
+/// The `rel="alternate"` (or rel-less) `<link>` of an Atom feed or entry
+fn atom_link(node: roxmltree::Node) -> Option<String> {
+    children(node, "link")
+        .find(|l| matches!(l.attribute("rel"), None | Some("alternate")))
+        .and_then(|l| l.attribute("href"))
+        .map(|h| h.trim().to_string())
+}
+
+fn atom_author(node: roxmltree::Node) -> Option<String> {
+    let names: Vec<String> = children(node, "author").filter_map(|a| child_text(a, "name")).collect();
+    (!names.is_empty()).then(|| names.join(", "))
+}
+
+fn parse_json_feed(text: &str, base: &Url) -> Result<Feed> {
+    let json: serde_json::Value = serde_json::from_str(text).map_err(|e| anyhow!("invalid JSON Feed: {}", e))?;
+    let str_field = |v: &serde_json::Value, key: &str| {
+        v.get(key).and_then(|s| s.as_str()).map(|s| s.trim().to_string()).filter(|s| !s.is_empty())
+    };
+    if !str_field(&json, "version").map(|v| v.contains("jsonfeed.org")).unwrap_or(false) {
+        return Err(anyhow!("not a JSON Feed: missing jsonfeed.org version"));
+    }
+    // Version 1.1 has `authors`, 1.0 a single `author`
+    let authors = |v: &serde_json::Value| {
+        let names: Vec<String> = match v.get("authors").and_then(|a| a.as_array()) {
+            Some(list) => list.iter().filter_map(|a| str_field(a, "name")).collect(),
+            None => v.get("author").and_then(|a| str_field(a, "name")).into_iter().collect(),
+        };
+        (!names.is_empty()).then(|| names.join(", "))
+    };
+    let entries = json
+        .get("items")
+        .and_then(|i| i.as_array())
+        .map(|items| {
+            items
+                .iter()
+                .map(|item| {
+                    entry(
+                        str_field(item, "title"),
+                        str_field(item, "url").or_else(|| str_field(item, "external_url")).and_then(|l| resolve(base, &l)),
+                        str_field(item, "date_published").or_else(|| str_field(item, "date_modified")),
+                        authors(item).or_else(|| authors(&json)),
+                        str_field(item, "summary")
+                            .or_else(|| str_field(item, "content_text"))
+                            .or_else(|| str_field(item, "content_html")),
+                    )
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    Ok(Feed {
+        format: "json".to_string(),
+        title: str_field(&json, "title").unwrap_or_default(),
+        link: str_field(&json, "home_page_url").and_then(|l| resolve(base, &l)),
+        description: str_field(&json, "description").map(|d| plain_text(&d, SUMMARY_CHARS)),
+        updated: None,
+        entries,
+    })
+}
+
+fn entry(
+    title: Option<String>,
+    link: Option<String>,
+    published: Option<String>,
+    author: Option<String>,
+    summary: Option<String>,
+) -> FeedEntry {
+    let summary = summary.map(|s| plain_text(&s, SUMMARY_CHARS)).filter(|s| !s.is_empty());
+    // Title-less items (allowed in RSS) are named after the start of their summary
+    let title = title
+        .map(|t| plain_text(&t, SUMMARY_CHARS))
+        .filter(|t| !t.is_empty())
+        .or_else(|| summary.as_deref().map(|s| shorten(s, 80)))
+        .unwrap_or_else(|| "Untitled".to_string());
+    FeedEntry { title, link, published: published.map(|d| normalize_date(&d)), author, summary }
+}
+
+/// Feeds a page advertises with `<link rel="alternate" type="application/rss+xml|atom+xml|feed+json">`
+pub fn discover(document: &Html, base: &Url) -> Vec<FeedLink> {
+    let Ok(selector) = Selector::parse(r#"link[rel~="alternate" i][href][type]"#) else {
+        return Vec::new();
+    };
+    let mut seen = HashSet::new();
+    document
+        .select(&selector)
+        .filter_map(|link| {
+            let el = link.value();
+            let format = match el.attr("type")?.split(';').next()?.trim().to_lowercase().as_str() {
+                "application/rss+xml" | "application/rdf+xml" => "rss",
+                "application/atom+xml" => "atom",
+                "application/feed+json" => "json",
+                _ => return None,
+            };
+            let url = base.join(el.attr("href")?.trim()).ok()?.to_string();
+            seen.insert(url.clone()).then(|| FeedLink {
+                url,
+                title: el.attr("title").map(|t| t.trim().to_string()).filter(|t| !t.is_empty()),
+                format: format.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Render feed entries as `clean_content`: one `## title` section per entry with its date,
+/// author, link and summary. Returns the text and the entry titles as h2 headings.
+pub fn render_entries(feed: &Feed) -> (String, Vec<Heading>) {
+    let mut out = String::new();
+    let mut headings = Vec::new();
+    for entry in &feed.entries {
+        if !out.is_empty() {
+            out.push_str("\n\n");
+        }
+        out.push_str("## ");
+        let start = out.chars().count();
+        out.push_str(&entry.title);
+        headings.push(Heading {
+            level: "h2".to_string(),
+            text: entry.title.clone(),
+            start_char: Some(start),
+            end_char: Some(start + entry.title.chars().count()),
+        });
+        let byline: Vec<&str> = [entry.published.as_deref(), entry.author.as_deref()].into_iter().flatten().collect();
+        if !byline.is_empty() {
+            out.push('\n');
+            out.push_str(&byline.join(" · "));
+        }
+        if let Some(link) = &entry.link {
+            out.push('\n');
+            out.push_str(link);
+        }
+        if let Some(summary) = &entry.summary {
+            out.push_str("\n\n");
+            out.push_str(summary);
+        }
+    }
+    (out, headings)
+}
+
+fn children<'a, 'input>(
+    node: roxmltree::Node<'a, 'input>,
+    name: &'static str,
+) -> impl Iterator<Item = roxmltree::Node<'a, 'input>> {
+    node.children().filter(move |n| n.is_element() && n.tag_name().name() == name)
+}
+
+fn child<'a, 'input>(node: roxmltree::Node<'a, 'input>, name: &'static str) -> Option<roxmltree::Node<'a, 'input>> {
+    children(node, name).next()
+}
+
+/// All text under the named child (Atom `type="xhtml"` content is markup, not a single text node)
+fn child_text(node: roxmltree::Node, name: &'static str) -> Option<String> {
+    let text: String = child(node, name)?.descendants().filter(|n| n.is_text()).filter_map(|n| n.text()).collect();
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+fn resolve(base: &Url, link: &str) -> Option<String> {
+    base.join(link.trim()).ok().map(|u| u.to_string())
+}
+
+/// RSS dates are RFC 822, Atom and JSON Feed RFC 3339; both become RFC 3339
+fn normalize_date(value: &str) -> String {
+    let value = value.trim();
+    DateTime::parse_from_rfc2822(value)
+        .or_else(|_| DateTime::parse_from_rfc3339(value))
+        .map(|d| d.to_rfc3339())
+        .unwrap_or_else(|_| value.to_string())
+}
+
+/// Strip markup (descriptions are often escaped HTML) and collapse whitespace
+fn plain_text(value: &str, max_chars: usize) -> String {
+    let text = if value.contains('<') || value.contains('&') {
+        let mut text = String::new();
+        collect_text(Html::parse_fragment(value).root_element(), &mut text);
+        text
+    } else {
+        value.to_string()
+    };
+    shorten(&text.split_whitespace().collect::<Vec<_>>().join(" "), max_chars)
+}
+
+/// Text content with block elements separated by spaces
+fn collect_text(element: ElementRef, out: &mut String) {
+    for child in element.children() {
+        if let Some(text) = child.value().as_text() {
+            out.push_str(text);
+        } else if let Some(child) = ElementRef::wrap(child) {
+            let block = matches!(
+                child.value().name(),
+                "p" | "div" | "br" | "li" | "ul" | "ol" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "tr" | "td" | "th" | "blockquote" | "pre"
+            );
+            if block {
+                out.push(' ');
+            }
+            collect_text(child, out);
+            if block {
+                out.push(' ');
+            }
+        }
+    }
+}
+
+fn shorten(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    let cut: String = text.chars().take(max_chars).collect();
+    let cut = cut.rsplit_once(' ').map(|(head, _)| head).unwrap_or(&cut);
+    format!("{}…", cut.trim_end_matches(|c: char| c.is_ascii_punctuation()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn base() -> Url {
+        Url::parse("https://blog.example.com/feed/").unwrap()
+    }
+
+    #[test]
+    fn test_rss_atom_and_json_feed() {
+        let rss = r#"<?xml version="1.0"?>
+            <rss version="2.0" xmlns:dc="http://purl.org/dc/elements/1.1/"><channel>
+              <title>Example Blog</title><link>https://blog.example.com/</link>
+              <item><title>Release 1.2</title><link>/posts/1-2</link><pubDate>Tue, 04 Jun 2024 10:00:00 GMT</pubDate>
+                <dc:creator>Ada</dc:creator><description>&lt;p&gt;New &lt;b&gt;features&lt;/b&gt;.&lt;/p&gt;</description></item>
+              <item><guid>https://blog.example.com/notes/7</guid><description>A short note without a title</description></item>
+            </channel></rss>"#;
+        assert!(is_feed("text/xml; charset=utf-8", rss));
+        let feed = parse(rss, &base()).unwrap();
+        assert_eq!((feed.format.as_str(), feed.title.as_str()), ("rss", "Example Blog"));
+        let first = &feed.entries[0];
+        assert_eq!(first.link.as_deref(), Some("https://blog.example.com/posts/1-2"));
+        assert_eq!(first.published.as_deref(), Some("2024-06-04T10:00:00+00:00"));
+        assert_eq!(first.author.as_deref(), Some("Ada"));
+        assert_eq!(first.summary.as_deref(), Some("New features."));
+        assert_eq!(feed.entries[1].title, "A short note without a title");
+        assert_eq!(feed.entries[1].link.as_deref(), Some("https://blog.example.com/notes/7"));
+
+        let atom = r#"<feed xmlns="http://www.w3.org/2005/Atom"><title>Changelog</title>
+            <author><name>Docs Team</name></author><updated>2024-06-05T00:00:00Z</updated>
+            <entry><title type="html">v2 &amp;amp; more</title><link rel="edit" href="/edit/2"/><link href="https://example.com/v2"/>
+              <updated>2024-06-05T00:00:00Z</updated><content type="xhtml"><div xmlns="http://www.w3.org/1999/xhtml"><p>Breaking changes</p></div></content></entry>
+            </feed>"#;
+        assert!(is_feed("application/octet-stream", atom));
+        let feed = parse(atom, &base()).unwrap();
+        assert_eq!(feed.format, "atom");
+        assert_eq!(feed.entries[0].title, "v2 & more");
+        assert_eq!(feed.entries[0].link.as_deref(), Some("https://example.com/v2"));
+        assert_eq!(feed.entries[0].author.as_deref(), Some("Docs Team"));
+        assert_eq!(feed.entries[0].summary.as_deref(), Some("Breaking changes"));
+
+        let json = r#"{"version": "https://jsonfeed.org/version/1.1", "title": "JSON Blog",
+            "items": [{"id": "1", "url": "https://example.com/1", "content_html": "<p>Hello</p>",
+                       "date_published": "2024-06-01T08:00:00-05:00", "authors": [{"name": "Grace"}]}]}"#;
+        assert!(is_feed("application/json", json));
+        let feed = parse(json, &base()).unwrap();
+        assert_eq!(feed.entries[0].title, "Hello");
+        assert_eq!(feed.entries[0].author.as_deref(), Some("Grace"));
+
+        assert!(!is_feed("text/html", "<!DOCTYPE html><html><body>rss feed</body></html>"));
+        assert!(!is_feed("application/json", r#"{"version": "1.0"}"#));
+    }
+
+    #[test]
+    fn test_discover_and_render() {
+        let html = r#"<head>
+            <link rel="alternate" type="application/rss+xml" title="Posts" href="/feed.xml">
+            <link rel="Alternate" type="application/atom+xml" href="https://blog.example.com/feed.xml">
+            <link rel="alternate" type="application/json" href="/wp-json/wp/v2/pages/1">
+            <link rel="alternate" hreflang="de" href="/de/"></head>"#;
+        let feeds = discover(&Html::parse_document(html), &base());
+        assert_eq!(
+            feeds,
+            vec![FeedLink {
+                url: "https://blog.example.com/feed.xml".to_string(),
+                title: Some("Posts".to_string()),
+                format: "rss".to_string()
+            }]
+        );
+
+        let feed = Feed {
+            format: "rss".to_string(),
+            title: "Blog".to_string(),
+            link: None,
+            description: None,
+            updated: None,
+            entries: vec![
+                entry(Some("First".into()), Some("https://x.test/1".into()), None, Some("Ada".into()), Some("One".into())),
+                entry(Some("Second".into()), None, None, None, None),
+            ],
+        };
+        let (text, headings) = render_entries(&feed);
+        assert_eq!(text, "## First\nAda\nhttps://x.test/1\n\nOne\n\n## Second");
+        let second = &headings[1];
+        let span: String = text.chars().skip(second.start_char.unwrap()).take(second.end_char.unwrap() - second.start_char.unwrap()).collect();
+        assert_eq!(span, "Second");
+    }
+}
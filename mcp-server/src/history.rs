@@ -7,8 +7,11 @@ use std::sync::Arc;
 use tokio::sync::OnceCell;
 use uuid::Uuid;
 
+/// Most entries `recent_entries` reads before sorting by timestamp
+const HISTORY_SCAN_LIMIT: usize = 1000;
+
 /// Entry type for history records
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum EntryType {
     Search,
@@ -16,7 +19,7 @@ pub enum EntryType {
 }
 
 /// History entry stored in Qdrant
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
 pub struct HistoryEntry {
     pub id: String,
     pub entry_type: EntryType,
@@ -264,6 +267,50 @@ impl MemoryManager {
         Ok(entries)
     }
 
+    /// Look up one entry by id; `None` when it doesn't exist
+    pub async fn get_entry(&self, id: &str) -> Result<Option<HistoryEntry>> {
+        use qdrant_client::qdrant::GetPointsBuilder;
+        let request = GetPointsBuilder::new(&self.collection_name, vec![id.into()]).with_payload(true);
+        let response = self
+            .qdrant
+            .get_points(request)
+            .await
+            .context("Failed to get entry from Qdrant")?;
+        Ok(response.result.into_iter().find_map(|point| Self::entry_from_payload(point.payload)))
+    }
+
+    /// Newest entries first. Points come back in id order, so up to `HISTORY_SCAN_LIMIT`
+    /// entries are scanned and sorted by timestamp.
+    pub async fn recent_entries(&self, limit: usize) -> Result<Vec<HistoryEntry>> {
+        use qdrant_client::qdrant::ScrollPointsBuilder;
+        let mut entries = Vec::new();
+        let mut offset = None;
+        loop {
+            let mut request = ScrollPointsBuilder::new(&self.collection_name).limit(256).with_payload(true);
+            if let Some(offset) = offset.take() {
+                request = request.offset(offset);
+            }
+            let response = self
+                .qdrant
+                .scroll(request)
+                .await
+                .context("Failed to scroll Qdrant")?;
+            entries.extend(response.result.into_iter().filter_map(|point| Self::entry_from_payload(point.payload)));
+            match response.next_page_offset {
+                Some(next) if entries.len() < HISTORY_SCAN_LIMIT => offset = Some(next),
+                _ => break,
+            }
+        }
+        entries.sort_by_key(|e| std::cmp::Reverse(e.timestamp));
+        entries.truncate(limit);
+        Ok(entries)
+    }
+
+    fn entry_from_payload(payload: std::collections::HashMap<String, qdrant_client::qdrant::Value>) -> Option<HistoryEntry> {
+        let value = serde_json::to_value(&payload).ok()?;
+        serde_json::from_value(value).ok()
+    }
+
     /// Log a search operation
     pub async fn log_search(
         &self,
@@ -380,6 +427,7 @@ impl MemoryManager {
         }
 
         let mut sorted: Vec<_> = domain_counts.into_iter().collect();
+        #[allow(clippy::unnecessary_sort_by)]
         sorted.sort_by(|a, b| b.1.cmp(&a.1));
         sorted.truncate(limit);
 
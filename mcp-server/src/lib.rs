@@ -6,11 +6,30 @@ pub mod rust_scraper;
 pub mod stdio_service;
 pub mod history;
 pub mod query_rewriter;
+pub mod robots;
+pub mod politeness;
+pub mod ssrf;
+pub mod disk_cache;
+pub mod pdf;
+pub mod charset;
+pub mod body;
+pub mod proxy;
+pub mod markdown;
+pub mod structured_data;
+pub mod pagination;
+pub mod crawl;
+pub mod sitemap;
+pub mod feed;
+pub mod error;
+pub mod tools;
+pub mod mcp_http;
+pub mod resources;
+pub mod prompts;
 
 use anyhow::Context;
 use std::env;
 use std::path::{Path, PathBuf};
-use tracing::info;
+use tracing::{info, warn};
 
 const CERT_DIR: &str = "/app/certificates";
 
@@ -20,17 +39,31 @@ pub struct AppState {
     pub http_client: reqwest::Client,
     // Caches for performance
     pub search_cache: moka::future::Cache<String, Vec<types::SearchResult>>, // key: query
-    pub scrape_cache: moka::future::Cache<String, types::ScrapeResponse>,     // key: url
-    // Concurrency control for external calls
-    pub outbound_limit: std::sync::Arc<tokio::sync::Semaphore>,
+    pub scrape_cache: moka::future::Cache<String, std::sync::Arc<types::CachedScrape>>, // key: url
+    // Optional persistent layer under both caches (DISK_CACHE_DIR)
+    pub disk_cache: Option<std::sync::Arc<disk_cache::DiskCache>>,
+    // Per-host politeness scheduler for external calls
+    pub outbound: std::sync::Arc<politeness::HostScheduler>,
+    // SSRF policy for scrape targets
+    pub ssrf: std::sync::Arc<ssrf::SsrfPolicy>,
+    // Shared scraper built on the SSRF-guarded scrape client (see `build_scrape_client`)
+    pub scraper: std::sync::Arc<rust_scraper::RustScraper>,
+    // robots.txt fetcher with per-host cache
+    pub robots: std::sync::Arc<robots::RobotsChecker>,
     // Memory manager for research history (optional)
     pub memory: Option<std::sync::Arc<history::MemoryManager>>,
+    // URLs whose cached scrape was replaced by a fresh fetch (drives resource subscriptions)
+    pub resource_updates: tokio::sync::broadcast::Sender<String>,
+    // MCP prompt templates (built-ins; the binaries overlay PROMPTS_FILE via with_prompts)
+    pub prompts: std::sync::Arc<prompts::PromptRegistry>,
 }
 
 impl std::fmt::Debug for AppState {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("AppState")
             .field("searxng_url", &self.searxng_url)
+            .field("robots_mode", &self.robots.mode())
+            .field("disk_cache_enabled", &self.disk_cache.is_some())
             .field("memory_enabled", &self.memory.is_some())
             .finish()
     }
@@ -41,19 +74,79 @@ pub use types::*;
 
 impl AppState {
     pub fn new(searxng_url: String, http_client: reqwest::Client) -> Self {
+        let ssrf = std::sync::Arc::new(ssrf::SsrfPolicy::from_env());
+        let scrape_client = guarded_client(std::sync::Arc::clone(&ssrf), true);
+        let robots = std::sync::Arc::new(robots::RobotsChecker::from_env(scrape_client.clone()));
+        let outbound = std::sync::Arc::new(politeness::HostScheduler::from_env());
+        // SearXNG is our own infrastructure: no spacing delay, higher concurrency
+        if let Some(host) = url::Url::parse(&searxng_url).ok().and_then(|u| u.host_str().map(|h| h.to_string())) {
+            let max_concurrency = env::var("SEARXNG_MAX_CONCURRENCY")
+                .ok()
+                .and_then(|v| v.parse::<usize>().ok())
+                .unwrap_or(8)
+                .max(1);
+            outbound.set_host_limits(
+                &host,
+                politeness::HostLimits { max_concurrency, min_delay: std::time::Duration::ZERO },
+            );
+        }
+        let scraper = std::sync::Arc::new(
+            rust_scraper::RustScraper::with_client(scrape_client)
+                .with_page_client(guarded_client(std::sync::Arc::clone(&ssrf), false))
+                .with_robots(std::sync::Arc::clone(&robots))
+                .with_outbound(std::sync::Arc::clone(&outbound))
+                .with_ssrf(std::sync::Arc::clone(&ssrf)),
+        );
         Self {
             searxng_url,
             http_client,
+            ssrf,
             search_cache: moka::future::Cache::builder()
                 .max_capacity(10_000)
                 .time_to_live(std::time::Duration::from_secs(60 * 10))
                 .build(),
+            // Entries outlive their max_age so they can be revalidated with ETag/Last-Modified
             scrape_cache: moka::future::Cache::builder()
                 .max_capacity(10_000)
-                .time_to_live(std::time::Duration::from_secs(60 * 30))
+                .time_to_live(std::time::Duration::from_secs(60 * 60 * 24))
                 .build(),
-            outbound_limit: std::sync::Arc::new(tokio::sync::Semaphore::new(32)),
+            disk_cache: disk_cache::DiskCache::from_env().map(std::sync::Arc::new),
+            outbound,
+            scraper,
+            robots,
             memory: None, // Will be initialized if QDRANT_URL is set
+            resource_updates: tokio::sync::broadcast::channel(256).0,
+            prompts: std::sync::Arc::new(prompts::PromptRegistry::builtin()),
+        }
+    }
+
+    /// Use a different client for scraping and robots.txt (the default is `build_scrape_client`).
+    /// Page requests keep a guarded client that doesn't follow redirects (see `build_page_client`).
+    pub fn with_scrape_client(mut self, client: reqwest::Client) -> Self {
+        self.robots = std::sync::Arc::new(robots::RobotsChecker::from_env(client.clone()));
+        self.scraper = std::sync::Arc::new(
+            rust_scraper::RustScraper::with_client(client)
+                .with_page_client(guarded_client(std::sync::Arc::clone(&self.ssrf), false))
+                .with_robots(std::sync::Arc::clone(&self.robots))
+                .with_outbound(std::sync::Arc::clone(&self.outbound))
+                .with_ssrf(std::sync::Arc::clone(&self.ssrf)),
+        );
+        self
+    }
+
+    /// Replace the SSRF policy and rebuild the guarded scrape client around it
+    pub fn with_ssrf_policy(mut self, policy: ssrf::SsrfPolicy) -> Self {
+        self.ssrf = std::sync::Arc::new(policy);
+        let client = guarded_client(std::sync::Arc::clone(&self.ssrf), true);
+        self.with_scrape_client(client)
+    }
+
+    /// Entry counts for the in-memory caches plus disk cache usage, if enabled
+    pub fn cache_stats(&self) -> types::CacheStats {
+        types::CacheStats {
+            search_memory_entries: self.search_cache.entry_count(),
+            scrape_memory_entries: self.scrape_cache.entry_count(),
+            disk: self.disk_cache.as_ref().map(|d| d.stats()),
         }
     }
 
@@ -61,11 +154,81 @@ impl AppState {
         self.memory = Some(memory);
         self
     }
+
+    pub fn with_prompts(mut self, prompts: std::sync::Arc<prompts::PromptRegistry>) -> Self {
+        self.prompts = prompts;
+        self
+    }
 }
 
 pub fn build_http_client() -> anyhow::Result<reqwest::Client> {
+    let proxy = proxy::ProxyConfig::from_env()?;
+    base_client_builder(proxy.as_ref())?
+        .redirect(reqwest::redirect::Policy::limited(10))
+        .build()
+        .context("Failed to build HTTP client")
+}
+
+/// Pooled client for scraping arbitrary URLs: same settings as `build_http_client`,
+/// plus a resolver and redirect policy that refuse non-public addresses
+pub fn build_scrape_client(policy: std::sync::Arc<ssrf::SsrfPolicy>) -> anyhow::Result<reqwest::Client> {
+    build_guarded_client(policy, true)
+}
+
+/// `build_scrape_client` without redirect following: `RustScraper` follows page redirects
+/// itself so each hop is checked against the SSRF policy and robots.txt first
+pub fn build_page_client(policy: std::sync::Arc<ssrf::SsrfPolicy>) -> anyhow::Result<reqwest::Client> {
+    build_guarded_client(policy, false)
+}
+
+fn build_guarded_client(policy: std::sync::Arc<ssrf::SsrfPolicy>, follow_redirects: bool) -> anyhow::Result<reqwest::Client> {
+    let proxy = proxy::ProxyConfig::from_env()?;
+    let resolver = ssrf::GuardedResolver::new(std::sync::Arc::clone(&policy))
+        .with_exempt_hosts(proxy.as_ref().map(|p| p.proxy_hosts()).unwrap_or_default());
+    let redirects = if follow_redirects {
+        ssrf::redirect_policy(policy, proxy.clone())
+    } else {
+        reqwest::redirect::Policy::none()
+    };
+    base_client_builder(proxy.as_ref())?
+        .dns_resolver(std::sync::Arc::new(resolver))
+        .redirect(redirects)
+        .build()
+        .context("Failed to build scrape HTTP client")
+}
+
+/// `build_scrape_client` or `build_page_client`, falling back to a guarded client without proxy or custom CA
+fn guarded_client(policy: std::sync::Arc<ssrf::SsrfPolicy>, follow_redirects: bool) -> reqwest::Client {
+    build_guarded_client(std::sync::Arc::clone(&policy), follow_redirects).unwrap_or_else(|e| {
+        warn!("Falling back to a scrape client without proxy/CA settings: {:#}", e);
+        let redirects = if follow_redirects {
+            ssrf::redirect_policy(std::sync::Arc::clone(&policy), None)
+        } else {
+            reqwest::redirect::Policy::none()
+        };
+        reqwest::Client::builder()
+            .dns_resolver(std::sync::Arc::new(ssrf::GuardedResolver::new(policy)))
+            .redirect(redirects)
+            .build()
+            .expect("Failed to build scrape HTTP client")
+    })
+}
+
+fn base_client_builder(proxy: Option<&proxy::ProxyConfig>) -> anyhow::Result<reqwest::ClientBuilder> {
+    // Pooled clients are shared across requests so keep-alive
+    // connections and TLS sessions are reused
     let mut builder = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(30));
+        .timeout(std::time::Duration::from_secs(30))
+        .connect_timeout(std::time::Duration::from_secs(10))
+        .pool_idle_timeout(std::time::Duration::from_secs(90))
+        .pool_max_idle_per_host(8)
+        .tcp_keepalive(std::time::Duration::from_secs(60))
+        .http2_adaptive_window(true);
+
+    // Without OUTBOUND_PROXY/OUTBOUND_PROXY_RULES reqwest falls back to HTTP(S)_PROXY/NO_PROXY
+    if let Some(proxy) = proxy {
+        builder = proxy.apply(builder);
+    }
 
     if let Ok(ca_cert_name) = env::var("TLS_CA_CERT") {
         let cert_path = Path::new(CERT_DIR).join(&ca_cert_name);
@@ -78,7 +241,7 @@ pub fn build_http_client() -> anyhow::Result<reqwest::Client> {
         builder = builder.add_root_certificate(cert);
     }
 
-    builder.build().context("Failed to build HTTP client")
+    Ok(builder)
 }
 
 fn configure_tls_ca_bundle(cert_path: &Path) -> anyhow::Result<()> {
@@ -6,16 +6,24 @@ use axum::{
     Router,
 };
 use std::env;
+use std::net::SocketAddr;
 use std::path::Path;
 use std::sync::Arc;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
 use tower_http::cors::CorsLayer;
 use tower_http::trace::TraceLayer;
 use tracing::{info, warn, error};
 
-use mcp_server::{build_http_client, search, scrape, types::*, mcp, AppState};
+use mcp_server::{build_http_client, search, scrape, types::*, mcp, mcp_http, AppState};
+use mcp_server::error::{ErrorInfo, ErrorKind};
+use mcp_server::prompts::PromptRegistry;
 
 const CERT_DIR: &str = "/app/certificates";
 
+/// How long open connections (e.g. MCP event streams) may take to finish after a shutdown signal
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(10);
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     // Initialize tracing
@@ -34,7 +42,8 @@ async fn main() -> anyhow::Result<()> {
     let http_client = build_http_client()?;
 
     // Create application state
-    let mut state = AppState::new(searxng_url, http_client);
+    let mut state = AppState::new(searxng_url, http_client)
+        .with_prompts(Arc::new(PromptRegistry::from_env()));
 
     // Initialize memory if QDRANT_URL is set
     if let Ok(qdrant_url) = env::var("QDRANT_URL") {
@@ -54,6 +63,11 @@ async fn main() -> anyhow::Result<()> {
     }
 
     let state = Arc::new(state);
+    let bind: SocketAddr = "0.0.0.0:5000".parse()?;
+
+    // Cancelled on Ctrl+C / SIGTERM: ends MCP SSE sessions and drains the server
+    let ct = CancellationToken::new();
+    tokio::spawn(cancel_on_shutdown_signal(ct.clone()));
 
     // Build router
     let app = Router::new()
@@ -62,9 +76,14 @@ async fn main() -> anyhow::Result<()> {
         .route("/search", post(search_web_handler))
         .route("/scrape", post(scrape_url_handler))
         .route("/chat", post(chat_handler))
+        .route("/stats/outbound", get(outbound_stats_handler))
+        .route("/stats/cache", get(cache_stats_handler))
         .route("/mcp/tools", get(mcp::list_tools))
         .route("/mcp/call", post(mcp::call_tool))
         .layer(CorsLayer::permissive())
+        // Standard MCP transports (Streamable HTTP at /mcp, legacy SSE at /sse + /message),
+        // merged after the CORS layer so browsers get no CORS grant; Origin and token are checked instead
+        .merge(mcp_http::router(Arc::clone(&state), bind, ct.clone(), mcp_http::AccessPolicy::from_env()))
         .layer(TraceLayer::new_for_http())
         .with_state(state);
 
@@ -81,25 +100,79 @@ async fn main() -> anyhow::Result<()> {
                 key_path,
             )
             .await?;
-            info!("MCP Server listening on https://0.0.0.0:5000");
-            axum_server::bind_rustls("0.0.0.0:5000".parse()?, tls_config)
+            info!("MCP Server listening on https://{}", bind);
+            let handle = axum_server::Handle::new();
+            let shutdown = handle.clone();
+            let cancelled = ct.clone();
+            tokio::spawn(async move {
+                cancelled.cancelled().await;
+                shutdown.graceful_shutdown(Some(SHUTDOWN_GRACE));
+            });
+            axum_server::bind_rustls(bind, tls_config)
+                .handle(handle)
                 .serve(app.into_make_service())
                 .await?;
         }
         (None, None) => {
-            let listener = tokio::net::TcpListener::bind("0.0.0.0:5000").await?;
-            info!("MCP Server listening on http://0.0.0.0:5000");
-            axum::serve(listener, app).await?;
+            let listener = tokio::net::TcpListener::bind(bind).await?;
+            info!("MCP Server listening on http://{}", bind);
+            serve_http(listener, app, ct).await?;
         }
         _ => {
             warn!("TLS_HOST_CERT and TLS_HOST_KEY must both be set to enable inbound TLS. Falling back to HTTP.");
-            let listener = tokio::net::TcpListener::bind("0.0.0.0:5000").await?;
-            info!("MCP Server listening on http://0.0.0.0:5000");
-            axum::serve(listener, app).await?;
+            let listener = tokio::net::TcpListener::bind(bind).await?;
+            info!("MCP Server listening on http://{}", bind);
+            serve_http(listener, app, ct).await?;
         }
     }
     
     Ok(())
+}
+
+/// Serve until `ct` is cancelled, then give open connections `SHUTDOWN_GRACE` to finish
+async fn serve_http(listener: tokio::net::TcpListener, app: Router, ct: CancellationToken) -> std::io::Result<()> {
+    let server = axum::serve(listener, app).with_graceful_shutdown(ct.clone().cancelled_owned());
+    tokio::select! {
+        result = std::future::IntoFuture::into_future(server) => result,
+        _ = async {
+            ct.cancelled().await;
+            tokio::time::sleep(SHUTDOWN_GRACE).await;
+        } => {
+            warn!("Connections still open {:?} after shutdown signal; exiting anyway", SHUTDOWN_GRACE);
+            Ok(())
+        }
+    }
+}
+
+/// Cancel `ct` on Ctrl+C or SIGTERM
+async fn cancel_on_shutdown_signal(ct: CancellationToken) {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            warn!("Failed to listen for Ctrl+C: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                warn!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+    info!("Shutdown signal received, stopping");
+    ct.cancel();
 }
 
 async fn health_check() -> Json<serde_json::Value> {
@@ -110,6 +183,18 @@ async fn health_check() -> Json<serde_json::Value> {
     }))
 }
 
+async fn outbound_stats_handler(
+    State(state): State<Arc<AppState>>,
+) -> Json<mcp_server::politeness::SchedulerMetrics> {
+    Json(state.outbound.metrics())
+}
+
+async fn cache_stats_handler(
+    State(state): State<Arc<AppState>>,
+) -> Json<mcp_server::CacheStats> {
+    Json(state.cache_stats())
+}
+
 async fn search_web_handler(
     State(state): State<Arc<AppState>>,
     Json(request): Json<SearchRequest>,
@@ -119,7 +204,7 @@ async fn search_web_handler(
         Err(e) => {
             error!("Search error: {}", e);
             Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
+                error_status(&e),
                 Json(ErrorResponse {
                     error: e.to_string(),
                 }),
@@ -132,12 +217,22 @@ async fn scrape_url_handler(
     State(state): State<Arc<AppState>>,
     Json(request): Json<ScrapeRequest>,
 ) -> Result<Json<ScrapeResponse>, (StatusCode, Json<ErrorResponse>)> {
-    match scrape::scrape_url(&state, &request.url).await {
+    let options = scrape::ScrapeOptions {
+        max_age: request.max_age.map(std::time::Duration::from_secs),
+        force_refresh: request.force_refresh.unwrap_or(false),
+    };
+    let result = if request.follow_pagination.unwrap_or(false) {
+        let max_pages = request.max_pages.unwrap_or(mcp_server::pagination::DEFAULT_MAX_PAGES);
+        scrape::scrape_url_paginated(&state, &request.url, options, max_pages).await
+    } else {
+        scrape::scrape_url_with_options(&state, &request.url, options).await
+    };
+    match result {
         Ok(content) => Ok(Json(content)),
         Err(e) => {
             error!("Scrape error: {}", e);
             Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
+                error_status(&e),
                 Json(ErrorResponse {
                     error: e.to_string(),
                 }),
@@ -146,6 +241,20 @@ async fn scrape_url_handler(
     }
 }
 
+/// HTTP status for a failed search/scrape, by error category
+fn error_status(e: &anyhow::Error) -> StatusCode {
+    match ErrorInfo::from_error(e).code {
+        ErrorKind::InvalidUrl => StatusCode::BAD_REQUEST,
+        ErrorKind::Blocked => StatusCode::FORBIDDEN,
+        ErrorKind::ContentTypeUnsupported => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+        ErrorKind::TooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+        ErrorKind::Timeout => StatusCode::GATEWAY_TIMEOUT,
+        ErrorKind::HttpStatus | ErrorKind::UpstreamUnavailable => StatusCode::BAD_GATEWAY,
+        ErrorKind::NotConfigured => StatusCode::SERVICE_UNAVAILABLE,
+        ErrorKind::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
 async fn chat_handler(
     State(state): State<Arc<AppState>>,
     Json(request): Json<ChatRequest>,
@@ -158,7 +267,7 @@ async fn chat_handler(
         Err(e) => {
             error!("Search failed: {}", e);
             return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
+                error_status(&e),
                 Json(ErrorResponse {
                     error: format!("Search failed: {}", e),
                 }),
@@ -0,0 +1,342 @@
+use crate::types::Table;
+use scraper::{ElementRef, Html, Node};
+use url::Url;
+
+/// Elements dropped entirely from Markdown output
+const SKIP_TAGS: &[&str] = &[
+    "script", "style", "noscript", "svg", "canvas", "iframe", "form", "button", "input", "select",
+    "textarea", "nav", "header", "footer", "aside", "template", "head",
+];
+
+/// Elements rendered as their own block (anything else is treated as inline)
+const BLOCK_TAGS: &[&str] = &[
+    "address", "article", "blockquote", "body", "center", "dd", "details", "dialog", "div", "dl", "dt",
+    "fieldset", "figcaption", "figure", "h1", "h2", "h3", "h4", "h5", "h6", "hr", "html", "li", "main",
+    "ol", "p", "pre", "section", "summary", "table", "ul",
+];
+
+/// Convert an HTML document or fragment to CommonMark (with GFM pipe tables).
+/// Relative link and image targets are resolved against `base`.
+pub fn html_to_markdown(html: &str, base: &Url) -> String {
+    let document = Html::parse_document(html);
+    let converter = Converter { base };
+    let mut blocks = Vec::new();
+    converter.blocks(document.root_element(), &mut blocks);
+    let mut out = blocks.join("\n\n");
+    out.push('\n');
+    out
+}
+
+struct Converter<'a> {
+    base: &'a Url,
+}
+
+impl Converter<'_> {
+    /// Render the children of `el` as a sequence of blocks, wrapping loose inline content in paragraphs
+    fn blocks(&self, el: ElementRef, out: &mut Vec<String>) {
+        let mut inline = String::new();
+        for child in el.children() {
+            match child.value() {
+                Node::Text(text) => inline.push_str(&escape_text(text)),
+                Node::Element(_) => {
+                    let Some(child_el) = ElementRef::wrap(child) else { continue };
+                    let name = child_el.value().name();
+                    if SKIP_TAGS.contains(&name) {
+                        continue;
+                    }
+                    if BLOCK_TAGS.contains(&name) {
+                        push_paragraph(&mut inline, out);
+                        self.block(child_el, out);
+                    } else {
+                        inline.push_str(&self.inline(child_el));
+                    }
+                }
+                _ => {}
+            }
+        }
+        push_paragraph(&mut inline, out);
+    }
+
+    fn block(&self, el: ElementRef, out: &mut Vec<String>) {
+        let name = el.value().name();
+        match name {
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                let level = name[1..].parse::<usize>().unwrap_or(1);
+                let text = collapse(&self.inline_children(el));
+                if !text.is_empty() {
+                    out.push(format!("{} {}", "#".repeat(level), text));
+                }
+            }
+            "p" | "dt" | "summary" | "figcaption" => {
+                let text = collapse(&self.inline_children(el));
+                if !text.is_empty() {
+                    out.push(if name == "dt" { format!("**{}**", text) } else { text });
+                }
+            }
+            "pre" => out.push(self.code_block(el)),
+            "hr" => out.push("---".to_string()),
+            "ul" | "ol" => {
+                let list = self.list(el);
+                if !list.is_empty() {
+                    out.push(list);
+                }
+            }
+            "blockquote" => {
+                let mut inner = Vec::new();
+                self.blocks(el, &mut inner);
+                if !inner.is_empty() {
+                    out.push(prefix_lines(&inner.join("\n\n"), "> ", "> "));
+                }
+            }
+            "table" => {
+                if let Some(table) = self.table(el) {
+                    out.push(table);
+                }
+            }
+            // Containers (div, section, li outside a list, ...) contribute their children
+            _ => self.blocks(el, out),
+        }
+    }
+
+    fn inline_children(&self, el: ElementRef) -> String {
+        let mut s = String::new();
+        for child in el.children() {
+            match child.value() {
+                Node::Text(text) => s.push_str(&escape_text(text)),
+                Node::Element(_) => {
+                    if let Some(child_el) = ElementRef::wrap(child) {
+                        s.push_str(&self.inline(child_el));
+                    }
+                }
+                _ => {}
+            }
+        }
+        s
+    }
+
+    fn inline(&self, el: ElementRef) -> String {
+        let name = el.value().name();
+        if SKIP_TAGS.contains(&name) {
+            return String::new();
+        }
+        match name {
+            "br" => "\\\n".to_string(),
+            "code" | "kbd" | "samp" | "tt" => code_span(&el.text().collect::<String>()),
+            "strong" | "b" => wrap_emphasis(&self.inline_children(el), "**"),
+            "em" | "i" | "cite" => wrap_emphasis(&self.inline_children(el), "*"),
+            "del" | "s" | "strike" => wrap_emphasis(&self.inline_children(el), "~~"),
+            "a" => {
+                let text = collapse(&self.inline_children(el));
+                match el.value().attr("href").and_then(|h| self.resolve(h)) {
+                    Some(href) if !text.is_empty() => format!("[{}]({})", text, href),
+                    Some(href) => format!("<{}>", href),
+                    None => text,
+                }
+            }
+            "img" => {
+                let alt = escape_text(el.value().attr("alt").unwrap_or_default());
+                match el.value().attr("src").and_then(|s| self.resolve(s)) {
+                    Some(src) => format!("![{}]({})", collapse(&alt), src),
+                    None => String::new(),
+                }
+            }
+            // Block content nested in inline context (e.g. <a><div>..</div></a>) is flattened
+            _ => self.inline_children(el),
+        }
+    }
+
+    fn resolve(&self, href: &str) -> Option<String> {
+        let href = href.trim();
+        if href.is_empty() || href.starts_with("javascript:") {
+            return None;
+        }
+        let url = self.base.join(href).ok()?;
+        // Spaces and parentheses would end the link destination early
+        Some(url.as_str().replace(' ', "%20").replace('(', "%28").replace(')', "%29"))
+    }
+
+    fn code_block(&self, pre: ElementRef) -> String {
+        let code_el = pre
+            .children()
+            .filter_map(ElementRef::wrap)
+            .find(|c| c.value().name() == "code");
+        let language = code_el.and_then(code_language).or_else(|| code_language(pre)).unwrap_or_default();
+        let code = pre.text().collect::<String>();
+        let code = code.trim_end_matches(['\n', '\r', ' ', '\t']).trim_start_matches(['\n', '\r']);
+
+        // The fence must be longer than any backtick run inside the code
+        let longest_run = code.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+        let fence = "`".repeat(longest_run.max(2) + 1);
+        format!("{}{}\n{}\n{}", fence, language, code, fence)
+    }
+
+    fn list(&self, list: ElementRef) -> String {
+        let ordered = list.value().name() == "ol";
+        let mut number = list
+            .value()
+            .attr("start")
+            .and_then(|s| s.trim().parse::<usize>().ok())
+            .unwrap_or(1);
+        let mut items = Vec::new();
+        for item in list.children().filter_map(ElementRef::wrap) {
+            if item.value().name() != "li" {
+                continue;
+            }
+            let mut blocks = Vec::new();
+            self.blocks(item, &mut blocks);
+            let body = blocks.join("\n\n");
+            let marker = if ordered { format!("{}. ", number) } else { "- ".to_string() };
+            number += 1;
+            if body.is_empty() {
+                items.push(marker.trim_end().to_string());
+            } else {
+                // Continuation lines are indented to the item's content column
+                items.push(prefix_lines(&body, &marker, &" ".repeat(marker.len())));
+            }
+        }
+        items.join("\n")
+    }
+
+    fn table(&self, table: ElementRef) -> Option<String> {
+        // Same span expansion and header handling as the `tables` extracted into ScrapeResponse
+        let table = Table::from_html(table, |cell| collapse(&self.inline_children(cell)).replace("\\\n", " "));
+        if table.headers.is_empty() && table.rows.is_empty() {
+            return None;
+        }
+        Some(match table.caption.as_deref() {
+            Some(caption) => format!("*{}*\n\n{}", caption, table.to_markdown()),
+            None => table.to_markdown(),
+        })
+    }
+}
+
+/// Language hint from `language-xxx` / `lang-xxx` classes or `data-lang`
+fn code_language(el: ElementRef) -> Option<String> {
+    el.value()
+        .attr("class")
+        .and_then(|classes| {
+            classes.split_whitespace().find_map(|c| {
+                c.strip_prefix("language-")
+                    .or_else(|| c.strip_prefix("lang-"))
+                    .or_else(|| c.strip_prefix("highlight-source-"))
+                    .map(str::to_string)
+            })
+        })
+        .or_else(|| el.value().attr("data-lang").map(str::to_string))
+        .filter(|l| !l.is_empty() && l.chars().all(|c| c.is_ascii_alphanumeric() || "+-#_.".contains(c)))
+}
+
+fn push_paragraph(inline: &mut String, out: &mut Vec<String>) {
+    let text = collapse(inline);
+    if !text.is_empty() {
+        out.push(text);
+    }
+    inline.clear();
+}
+
+/// Collapse whitespace runs (keeping hard breaks) and trim each line
+fn collapse(s: &str) -> String {
+    s.split("\\\n")
+        .map(|part| part.split_whitespace().collect::<Vec<_>>().join(" "))
+        .collect::<Vec<_>>()
+        .join("\\\n")
+        .trim_matches(|c: char| c.is_whitespace() || c == '\\')
+        .to_string()
+}
+
+fn prefix_lines(text: &str, first: &str, rest: &str) -> String {
+    text.lines()
+        .enumerate()
+        .map(|(i, line)| {
+            let prefix = if i == 0 { first } else { rest };
+            if line.is_empty() { prefix.trim_end().to_string() } else { format!("{}{}", prefix, line) }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn wrap_emphasis(inner: &str, marker: &str) -> String {
+    let trimmed = inner.trim();
+    if trimmed.is_empty() {
+        return inner.to_string();
+    }
+    // Keep surrounding spaces outside the markers so the emphasis stays valid
+    let lead = if inner.starts_with(char::is_whitespace) { " " } else { "" };
+    let trail = if inner.ends_with(char::is_whitespace) { " " } else { "" };
+    format!("{}{}{}{}{}", lead, marker, trimmed, marker, trail)
+}
+
+fn code_span(code: &str) -> String {
+    let code = code.split_whitespace().collect::<Vec<_>>().join(" ");
+    if code.is_empty() {
+        return String::new();
+    }
+    let longest_run = code.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    let ticks = "`".repeat(longest_run + 1);
+    let pad = if code.starts_with('`') || code.ends_with('`') { " " } else { "" };
+    format!("{}{}{}{}{}", ticks, pad, code, pad, ticks)
+}
+
+/// Escape characters that would otherwise start Markdown syntax
+fn escape_text(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn md(html: &str) -> String {
+        html_to_markdown(html, &Url::parse("https://docs.example.com/guide/intro").unwrap())
+    }
+
+    #[test]
+    fn test_headings_links_and_code() {
+        let out = md(r#"<article>
+            <h1>Getting <em>started</em></h1>
+            <p>Read the <a href="../api/index.html">API docs</a> or use <code>cargo add</code>.</p>
+            <h3>Install</h3>
+            <pre><code class="language-rust">fn main() {
+    println!("hi");
+}
+</code></pre>
+            <nav><a href="/">Home</a></nav>
+        </article>"#);
+        assert_eq!(
+            out,
+            "# Getting *started*\n\n\
+             Read the [API docs](https://docs.example.com/api/index.html) or use `cargo add`.\n\n\
+             ### Install\n\n\
+             ```rust\nfn main() {\n    println!(\"hi\");\n}\n```\n"
+        );
+    }
+
+    #[test]
+    fn test_lists_and_tables() {
+        let out = md(r#"<ul><li>One</li><li>Two<ol start="3"><li>Three</li><li><p>Four</p></li></ol></li></ul>
+            <table><caption>Versions</caption>
+              <thead><tr><th>Crate</th><th>Version</th></tr></thead>
+              <tbody><tr><td rowspan="2">tokio</td><td>1.0</td></tr><tr><td>1.1</td></tr><tr><td colspan="2">a|b</td></tr></tbody>
+            </table>
+            <blockquote><p>Quoted</p><p>Twice</p></blockquote>"#);
+        assert_eq!(
+            out,
+            "- One\n- Two\n\n  3. Three\n  4. Four\n\n\
+             *Versions*\n\n| Crate | Version |\n| --- | --- |\n| tokio | 1.0 |\n| tokio | 1.1 |\n| a\\|b | a\\|b |\n\n\
+             > Quoted\n>\n> Twice\n"
+        );
+    }
+
+    #[test]
+    fn test_escaping() {
+        assert_eq!(md("<p>2 * 3 = _six_ [x] <b>bold</b> `tick`</p>"), "2 \\* 3 = \\_six\\_ \\[x\\] **bold** \\`tick\\`\n");
+        assert_eq!(code_span("a `b` c"), "``a `b` c``");
+    }
+}
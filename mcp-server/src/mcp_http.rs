@@ -0,0 +1,274 @@
+//! Standard MCP over HTTP for remote agents, served from the `mcp-server` binary alongside
+//! the REST routes: Streamable HTTP at `/mcp` and the legacy HTTP+SSE transport at
+//! `/sse` (event stream) + `/message` (client posts). Both run [`McpService`], so remote
+//! clients see exactly what the stdio binary offers.
+//! The routes check `Origin` and an optional bearer token (see [`AccessPolicy`]) and are
+//! meant to be mounted outside any permissive CORS layer.
+
+use crate::stdio_service::McpService;
+use crate::AppState;
+use axum::extract::{Request, State};
+use axum::http::header::{AUTHORIZATION, ORIGIN, WWW_AUTHENTICATE};
+use axum::http::StatusCode;
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::Router;
+use futures::StreamExt;
+use rmcp::transport::sse_server::{SseServer, SseServerConfig};
+use rmcp::transport::streamable_http_server::session::local::LocalSessionManager;
+use rmcp::transport::{StreamableHttpServerConfig, StreamableHttpService};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio_util::sync::CancellationToken;
+use tracing::warn;
+use url::{Host, Url};
+
+/// Streamable HTTP endpoint: POST JSON-RPC, GET to listen for server messages, DELETE to end a session
+pub const STREAMABLE_HTTP_PATH: &str = "/mcp";
+
+/// Legacy SSE transport: the event stream announces `/message?sessionId=...` to post to
+pub const SSE_PATH: &str = "/sse";
+pub const SSE_POST_PATH: &str = "/message";
+
+/// Who may use the MCP HTTP transports. Browsers send `Origin` with cross-site requests, so
+/// refusing unknown origins keeps web pages (and DNS rebinding) from driving the server;
+/// requests without `Origin` come from non-browser clients and only need the token, if set.
+#[derive(Debug, Clone, Default)]
+pub struct AccessPolicy {
+    /// Origins allowed besides loopback ones; `*` allows any
+    allowed_origins: Vec<String>,
+    /// Required `Authorization: Bearer` token
+    bearer_token: Option<String>,
+}
+
+impl AccessPolicy {
+    /// Allow these origins (`https://app.example.com`) in addition to loopback ones
+    pub fn with_allowed_origins<I, S>(mut self, origins: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.allowed_origins.extend(
+            origins
+                .into_iter()
+                .map(|o| o.as_ref().trim().trim_end_matches('/').to_lowercase())
+                .filter(|o| !o.is_empty()),
+        );
+        self
+    }
+
+    /// Require `Authorization: Bearer <token>` on every request
+    pub fn with_bearer_token(mut self, token: impl Into<String>) -> Self {
+        self.bearer_token = Some(token.into());
+        self
+    }
+
+    /// Read `MCP_ALLOWED_ORIGINS` (comma-separated) and `MCP_AUTH_TOKEN`
+    pub fn from_env() -> Self {
+        let mut policy = Self::default()
+            .with_allowed_origins(std::env::var("MCP_ALLOWED_ORIGINS").unwrap_or_default().split(','));
+        if let Some(token) = std::env::var("MCP_AUTH_TOKEN").ok().filter(|t| !t.trim().is_empty()) {
+            policy = policy.with_bearer_token(token.trim());
+        }
+        policy
+    }
+
+    fn origin_allowed(&self, origin: &str) -> bool {
+        let origin = origin.trim_end_matches('/').to_lowercase();
+        if self.allowed_origins.iter().any(|allowed| allowed == "*" || *allowed == origin) {
+            return true;
+        }
+        // Local tools (MCP inspectors, desktop apps) run on loopback origins
+        match Url::parse(&origin).ok().as_ref().and_then(Url::host) {
+            Some(Host::Domain(domain)) => domain == "localhost",
+            Some(Host::Ipv4(ip)) => ip.is_loopback(),
+            Some(Host::Ipv6(ip)) => ip.is_loopback(),
+            None => false,
+        }
+    }
+
+    fn token_valid(&self, authorization: Option<&str>) -> bool {
+        let Some(expected) = &self.bearer_token else {
+            return true;
+        };
+        let Some(provided) = authorization.and_then(|v| v.strip_prefix("Bearer ")) else {
+            return false;
+        };
+        // Compare without short-circuiting so timing doesn't leak a matching prefix
+        provided.len() == expected.len()
+            && provided.bytes().zip(expected.bytes()).fold(0u8, |diff, (a, b)| diff | (a ^ b)) == 0
+    }
+}
+
+/// Reject disallowed origins (403) and missing or wrong bearer tokens (401)
+async fn check_access(State(access): State<Arc<AccessPolicy>>, request: Request, next: Next) -> Response {
+    if let Some(origin) = request.headers().get(ORIGIN) {
+        if !origin.to_str().map(|o| access.origin_allowed(o)).unwrap_or(false) {
+            warn!("Rejected MCP request from origin {:?}", origin);
+            return (StatusCode::FORBIDDEN, "Origin not allowed").into_response();
+        }
+    }
+    let authorization = request.headers().get(AUTHORIZATION).and_then(|v| v.to_str().ok());
+    if !access.token_valid(authorization) {
+        return (StatusCode::UNAUTHORIZED, [(WWW_AUTHENTICATE, "Bearer")], "Missing or invalid bearer token").into_response();
+    }
+    next.run(request).await
+}
+
+/// End response bodies once `ct` is cancelled: rmcp keeps event streams open until the client
+/// disconnects, which would otherwise hold up graceful shutdown
+async fn end_streams_on_cancel(State(ct): State<CancellationToken>, response: Response) -> Response {
+    let (parts, body) = response.into_parts();
+    let body = body.into_data_stream().take_until(ct.cancelled_owned());
+    Response::from_parts(parts, axum::body::Body::from_stream(body))
+}
+
+/// Routes for both MCP HTTP transports. Every client gets its own session: Streamable
+/// HTTP hands out an `Mcp-Session-Id` header on `initialize`, SSE a `sessionId` query
+/// parameter. `bind` is the address the server listens on; sessions and their event streams
+/// end when `ct` is cancelled.
+/// Every request is checked against `access` first.
+pub fn router<S>(state: Arc<AppState>, bind: SocketAddr, ct: CancellationToken, access: AccessPolicy) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    // A fresh service per session so resource subscriptions aren't shared between clients
+    let streamable = {
+        let state = Arc::clone(&state);
+        StreamableHttpService::new(
+            move || Ok(McpService::from_state(Arc::clone(&state))),
+            Arc::new(LocalSessionManager::default()),
+            StreamableHttpServerConfig::default(),
+        )
+    };
+
+    let (sse_server, sse_router) = SseServer::new(SseServerConfig {
+        bind,
+        sse_path: SSE_PATH.to_string(),
+        post_path: SSE_POST_PATH.to_string(),
+        ct: ct.clone(),
+        sse_keep_alive: None,
+    });
+    sse_server.with_service(move || McpService::from_state(Arc::clone(&state)));
+
+    // The rmcp routers match on the full request path, so mount them on exact routes rather than nesting
+    Router::new()
+        .route_service(STREAMABLE_HTTP_PATH, streamable)
+        .route_service(SSE_PATH, sse_router.clone())
+        .route_service(SSE_POST_PATH, sse_router)
+        .layer(middleware::map_response_with_state(ct, end_streams_on_cancel))
+        .layer(middleware::from_fn_with_state(Arc::new(access), check_access))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// First `data:` payload of an SSE response
+    async fn next_event(response: &mut reqwest::Response) -> String {
+        let mut buffer = String::new();
+        while let Some(chunk) = response.chunk().await.unwrap() {
+            buffer.push_str(&String::from_utf8_lossy(&chunk));
+            if buffer.contains("\n\n") {
+                if let Some(data) = buffer.lines().find_map(|l| l.strip_prefix("data:")) {
+                    return data.trim().to_string();
+                }
+            }
+        }
+        panic!("event stream ended without data: {:?}", buffer);
+    }
+
+    #[tokio::test]
+    async fn test_streamable_http_and_sse_sessions() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let state = Arc::new(AppState::new("http://localhost:8888".to_string(), reqwest::Client::new()));
+        let ct = CancellationToken::new();
+        let app: Router = router(state, addr, ct.clone(), AccessPolicy::default());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        let client = reqwest::Client::new();
+        let endpoint = format!("http://{}{}", addr, STREAMABLE_HTTP_PATH);
+        let post = |body: serde_json::Value, session: Option<&str>| {
+            let mut request = client
+                .post(&endpoint)
+                .header("Accept", "application/json, text/event-stream")
+                .json(&body);
+            if let Some(session) = session {
+                request = request.header("Mcp-Session-Id", session);
+            }
+            request.send()
+        };
+
+        let mut response = post(serde_json::json!({
+            "jsonrpc": "2.0", "id": 1, "method": "initialize",
+            "params": {"protocolVersion": "2025-03-26", "capabilities": {}, "clientInfo": {"name": "test", "version": "0"}}
+        }), None).await.unwrap();
+        assert!(response.status().is_success());
+        let session = response.headers()["mcp-session-id"].to_str().unwrap().to_string();
+        let init: serde_json::Value = serde_json::from_str(&next_event(&mut response).await).unwrap();
+        assert_eq!(init["result"]["serverInfo"]["name"], "search-scrape");
+
+        let notified = post(serde_json::json!({"jsonrpc": "2.0", "method": "notifications/initialized"}), Some(&session)).await.unwrap();
+        assert!(notified.status().is_success());
+        let mut response = post(serde_json::json!({"jsonrpc": "2.0", "id": 2, "method": "tools/list"}), Some(&session)).await.unwrap();
+        let list: serde_json::Value = serde_json::from_str(&next_event(&mut response).await).unwrap();
+        assert_eq!(list["result"]["tools"].as_array().unwrap().len(), crate::tools::list_tools().len());
+        let mut response = post(serde_json::json!({"jsonrpc": "2.0", "id": 3, "method": "prompts/get", "params": {"name": "research_topic", "arguments": {"topic": "axum"}}}), Some(&session)).await.unwrap();
+        let prompt: serde_json::Value = serde_json::from_str(&next_event(&mut response).await).unwrap();
+        assert_eq!(prompt["result"]["messages"][0]["role"], "user");
+
+        // Anything but initialize needs a known session
+        let unknown = post(serde_json::json!({"jsonrpc": "2.0", "id": 4, "method": "tools/list"}), Some("nope")).await.unwrap();
+        assert!(unknown.status().is_client_error());
+
+        let mut sse = client.get(format!("http://{}{}", addr, SSE_PATH)).send().await.unwrap();
+        assert!(sse.status().is_success());
+        let post_path = next_event(&mut sse).await;
+        assert!(post_path.starts_with("/message?sessionId="), "{}", post_path);
+
+        ct.cancel();
+    }
+
+    #[tokio::test]
+    async fn test_origin_and_token_checks() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let state = Arc::new(AppState::new("http://localhost:8888".to_string(), reqwest::Client::new()));
+        let ct = CancellationToken::new();
+        let access = AccessPolicy::default()
+            .with_allowed_origins(["https://app.example.com/"])
+            .with_bearer_token("s3cret");
+        let app: Router = router(state, addr, ct.clone(), access);
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        let client = reqwest::Client::new();
+        let initialize = |origin: Option<&str>, token: Option<&str>| {
+            let mut request = client
+                .post(format!("http://{}{}", addr, STREAMABLE_HTTP_PATH))
+                .header("Accept", "application/json, text/event-stream")
+                .json(&serde_json::json!({
+                    "jsonrpc": "2.0", "id": 1, "method": "initialize",
+                    "params": {"protocolVersion": "2025-03-26", "capabilities": {}, "clientInfo": {"name": "test", "version": "0"}}
+                }));
+            if let Some(origin) = origin {
+                request = request.header("Origin", origin);
+            }
+            if let Some(token) = token {
+                request = request.bearer_auth(token);
+            }
+            request.send()
+        };
+
+        assert_eq!(initialize(None, None).await.unwrap().status(), 401);
+        assert_eq!(initialize(None, Some("wrong")).await.unwrap().status(), 401);
+        assert_eq!(initialize(Some("https://evil.example"), Some("s3cret")).await.unwrap().status(), 403);
+        assert_eq!(initialize(Some("null"), Some("s3cret")).await.unwrap().status(), 403);
+        for origin in [None, Some("https://app.example.com"), Some("http://localhost:6274"), Some("http://127.0.0.1:3000")] {
+            let response = initialize(origin, Some("s3cret")).await.unwrap();
+            assert!(response.status().is_success(), "{:?}: {}", origin, response.status());
+        }
+        let sse = client.get(format!("http://{}{}", addr, SSE_PATH)).header("Origin", "https://evil.example").send().await.unwrap();
+        assert_eq!(sse.status(), 403);
+
+        ct.cancel();
+    }
+}
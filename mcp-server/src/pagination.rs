@@ -0,0 +1,176 @@
+use scraper::{ElementRef, Html, Selector};
+use std::collections::HashSet;
+use url::Url;
+
+/// Pages fetched (including the first) when `max_pages` isn't given
+pub const DEFAULT_MAX_PAGES: usize = 5;
+
+/// Upper bound on `max_pages`
+pub const MAX_PAGES_LIMIT: usize = 20;
+
+/// Query parameters commonly carrying the page number
+const PAGE_PARAMS: &[&str] = &["page", "p", "pg", "paged", "pagenum", "page_num"];
+
+/// Class/id/aria-label fragments marking a pagination container
+const PAGINATION_MARKERS: &[&str] = &["pagination", "pager", "paging", "page-nav", "pagenav", "page-numbers", "pagelinks"];
+
+/// Find the URL of the page following `current`, trying in order:
+/// `<link rel="next">` / `<a rel="next">`, a "next" link inside a pagination container,
+/// then a link to page N+1 of the same `?page=N` / `/page/N` sequence.
+/// Only same-site URLs not in `visited` (see [`page_key`]) are returned.
+pub fn next_page_url(html: &str, current: &Url, visited: &HashSet<String>) -> Option<Url> {
+    let document = Html::parse_document(html);
+    let accept = |href: &str| {
+        let url = current.join(href.trim()).ok()?;
+        let same_site = matches!(url.scheme(), "http" | "https") && site_host(&url) == site_host(current);
+        (same_site && !visited.contains(&page_key(&url)) && page_key(&url) != page_key(current)).then_some(url)
+    };
+    let select = |css: &str| Selector::parse(css).map(|sel| document.select(&sel).collect::<Vec<_>>()).unwrap_or_default();
+
+    // 1) Explicit rel=next
+    if let Some(url) = select(r#"link[rel~="next" i][href], a[rel~="next" i][href]"#)
+        .into_iter()
+        .find_map(|el| accept(el.value().attr("href")?))
+    {
+        return Some(url);
+    }
+
+    // 2) "Next" links in pagination navigation
+    let anchors = select("a[href]");
+    if let Some(url) = anchors
+        .iter()
+        .filter(|a| in_pagination_container(**a) || has_next_class(**a))
+        .filter(|a| looks_like_next(**a))
+        .find_map(|a| accept(a.value().attr("href")?))
+    {
+        return Some(url);
+    }
+
+    // 3) Numbered pages: a link to page N+1 of the current sequence
+    let (sequence, number) = page_number(current).unwrap_or_else(|| (sequence_key(current), 1));
+    anchors.iter().find_map(|a| {
+        let url = accept(a.value().attr("href")?)?;
+        let (next_sequence, next_number) = page_number(&url)?;
+        (next_sequence == sequence && next_number == number + 1).then_some(url)
+    })
+}
+
+/// Identity of a page for loop detection: URL without fragment or trailing slash
+pub fn page_key(url: &Url) -> String {
+    let mut url = url.clone();
+    url.set_fragment(None);
+    url.as_str().trim_end_matches('/').to_string()
+}
+
+fn site_host(url: &Url) -> String {
+    let host = url.host_str().unwrap_or_default().to_lowercase();
+    host.strip_prefix("www.").map(str::to_string).unwrap_or(host)
+}
+
+fn in_pagination_container(el: ElementRef) -> bool {
+    el.ancestors().filter_map(ElementRef::wrap).take(6).any(|a| {
+        let v = a.value();
+        ["class", "id", "aria-label", "role"].iter().any(|attr| {
+            v.attr(attr)
+                .map(|value| {
+                    let value = value.to_lowercase();
+                    PAGINATION_MARKERS.iter().any(|m| value.contains(m))
+                })
+                .unwrap_or(false)
+        })
+    })
+}
+
+fn has_next_class(el: ElementRef) -> bool {
+    el.value()
+        .attr("class")
+        .map(|c| c.split_whitespace().any(|c| matches!(c.to_lowercase().as_str(), "next" | "next-page" | "nextpostslink" | "pagination-next" | "pager-next")))
+        .unwrap_or(false)
+}
+
+fn looks_like_next(el: ElementRef) -> bool {
+    let text = el.text().collect::<String>().split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase();
+    let label = el
+        .value()
+        .attr("aria-label")
+        .or_else(|| el.value().attr("title"))
+        .unwrap_or_default()
+        .to_lowercase();
+    let is_next = |s: &str| s.starts_with("next") || s.starts_with("older") || matches!(s, ">" | ">>" | "›" | "»" | "→");
+    is_next(&text) || is_next(&label) || (text.is_empty() && has_next_class(el))
+}
+
+/// Page number carried by a URL, with the URL minus the page number identifying the sequence
+fn page_number(url: &Url) -> Option<(String, u32)> {
+    for (key, value) in url.query_pairs() {
+        if PAGE_PARAMS.contains(&key.to_lowercase().as_str()) {
+            if let Ok(n) = value.parse::<u32>() {
+                let mut seq = url.clone();
+                let rest: Vec<(String, String)> = url
+                    .query_pairs()
+                    .filter(|(k, _)| *k != key)
+                    .map(|(k, v)| (k.into_owned(), v.into_owned()))
+                    .collect();
+                seq.set_query(None);
+                if !rest.is_empty() {
+                    seq.query_pairs_mut().extend_pairs(rest);
+                }
+                return Some((sequence_key(&seq), n));
+            }
+        }
+    }
+    // WordPress-style /page/N/
+    let segments: Vec<&str> = url.path_segments()?.filter(|s| !s.is_empty()).collect();
+    let idx = segments.iter().rposition(|s| s.eq_ignore_ascii_case("page"))?;
+    let n = segments.get(idx + 1)?.parse::<u32>().ok()?;
+    let mut seq = url.clone();
+    seq.set_path(&segments[..idx].join("/"));
+    Some((sequence_key(&seq), n))
+}
+
+/// Host, path and (sorted) query of a URL, ignoring `www.`, trailing slashes and the fragment
+fn sequence_key(url: &Url) -> String {
+    let mut pairs: Vec<(String, String)> = url.query_pairs().map(|(k, v)| (k.into_owned(), v.into_owned())).collect();
+    pairs.sort();
+    let query = pairs.iter().map(|(k, v)| format!("{}={}", k, v)).collect::<Vec<_>>().join("&");
+    format!("{}{}?{}", site_host(url), url.path().trim_end_matches('/'), query)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn next(html: &str, current: &str) -> Option<String> {
+        next_page_url(html, &Url::parse(current).unwrap(), &HashSet::new()).map(|u| u.to_string())
+    }
+
+    #[test]
+    fn test_rel_next_and_pagination_nav() {
+        assert_eq!(
+            next(r#"<head><link rel="next" href="/guide/part-2"></head>"#, "https://example.com/guide/part-1").as_deref(),
+            Some("https://example.com/guide/part-2")
+        );
+        let nav = r#"<article>See <a href="/next-steps">next steps</a></article>
+            <nav class="pagination"><a href="?page=1">1</a><span>2</span><a href="/t/42?page=3">Next ›</a></nav>"#;
+        assert_eq!(next(nav, "https://forum.example.com/t/42?page=2").as_deref(), Some("https://forum.example.com/t/42?page=3"));
+        // Cross-site and already-visited links are ignored
+        assert_eq!(next(r#"<link rel="next" href="https://other.example/p2">"#, "https://example.com/p1"), None);
+        let visited = HashSet::from(["https://example.com/p2".to_string()]);
+        let current = Url::parse("https://example.com/p1").unwrap();
+        assert_eq!(next_page_url(r#"<a rel="next" href="/p2/">2</a>"#, &current, &visited), None);
+    }
+
+    #[test]
+    fn test_numbered_pages() {
+        let html = r#"<div><a href="/blog/page/3/">3</a><a href="/blog/page/2/">2</a><a href="/other/page/2/">x</a></div>"#;
+        assert_eq!(next(html, "https://example.com/blog/").as_deref(), Some("https://example.com/blog/page/2/"));
+        assert_eq!(next(html, "https://example.com/blog/page/2/").as_deref(), Some("https://example.com/blog/page/3/"));
+
+        let html = r#"<a href="list?sort=new&amp;page=2">2</a><a href="list?sort=old&amp;page=2">2</a>"#;
+        assert_eq!(
+            next(html, "https://example.com/list?sort=old").as_deref(),
+            Some("https://example.com/list?sort=old&page=2")
+        );
+        assert_eq!(next("<p>no links</p>", "https://example.com/list?page=4"), None);
+    }
+}
@@ -0,0 +1,239 @@
+use crate::types::PageBoundary;
+use anyhow::{anyhow, Result};
+use pdf_extract::{Document, Object};
+
+/// Text and metadata extracted from a PDF
+#[derive(Debug, Clone, Default)]
+pub struct PdfContent {
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub subject: Option<String>,
+    pub keywords: Option<String>,
+    /// Document creation date as RFC 3339 when parseable, otherwise the raw PDF date
+    pub created_at: Option<String>,
+    pub page_count: usize,
+    /// Normalized text of each page, in page order
+    pub pages: Vec<String>,
+}
+
+impl PdfContent {
+    /// Join pages with blank lines, recording where each page starts and ends (char offsets)
+    pub fn joined_text(&self) -> (String, Vec<PageBoundary>) {
+        let mut text = String::new();
+        let mut boundaries = Vec::with_capacity(self.pages.len());
+        let mut offset = 0;
+        for (i, page) in self.pages.iter().enumerate() {
+            if i > 0 {
+                text.push_str("\n\n");
+                offset += 2;
+            }
+            let start = offset;
+            text.push_str(page);
+            offset += page.chars().count();
+            boundaries.push(PageBoundary { page: i + 1, start_char: start, end_char: offset, url: None });
+        }
+        (text, boundaries)
+    }
+}
+
+/// True for `application/pdf` responses, or any body starting with the `%PDF-` magic
+/// (servers often send PDFs as `application/octet-stream`)
+pub fn is_pdf(content_type: &str, body: &[u8]) -> bool {
+    let mime = content_type.split(';').next().unwrap_or_default().trim().to_lowercase();
+    mime == "application/pdf" || mime == "application/x-pdf" || body.starts_with(b"%PDF-")
+}
+
+/// Parse a PDF held in memory. CPU-bound: call from `spawn_blocking`.
+pub fn extract(bytes: &[u8]) -> Result<PdfContent> {
+    let doc = Document::load_mem(bytes).map_err(|e| anyhow!("Failed to parse PDF: {}", e))?;
+    let page_count = doc.get_pages().len();
+    let pages = pdf_extract::extract_text_from_mem_by_pages(bytes)
+        .map_err(|e| anyhow!("Failed to extract PDF text: {}", e))?
+        .iter()
+        .map(|p| normalize_page(p))
+        .collect();
+
+    let info = doc
+        .trailer
+        .get(b"Info")
+        .ok()
+        .and_then(|o| match o {
+            Object::Reference(id) => doc.get_dictionary(*id).ok(),
+            Object::Dictionary(d) => Some(d),
+            _ => None,
+        });
+    let field = |key: &[u8]| {
+        info.and_then(|d| d.get(key).ok())
+            .and_then(|o| match o {
+                Object::String(bytes, _) => Some(decode_pdf_string(bytes)),
+                _ => None,
+            })
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+    };
+
+    Ok(PdfContent {
+        title: field(b"Title"),
+        author: field(b"Author"),
+        subject: field(b"Subject"),
+        keywords: field(b"Keywords"),
+        created_at: field(b"CreationDate").map(|d| parse_pdf_date(&d).unwrap_or(d)),
+        page_count,
+        pages,
+    })
+}
+
+/// Trim lines, collapse runs of spaces and keep at most one blank line between paragraphs
+fn normalize_page(text: &str) -> String {
+    let mut out = String::new();
+    let mut blank = false;
+    for line in text.lines() {
+        let line = line.split_whitespace().collect::<Vec<_>>().join(" ");
+        if line.is_empty() {
+            blank = !out.is_empty();
+            continue;
+        }
+        if !out.is_empty() {
+            out.push_str(if blank { "\n\n" } else { "\n" });
+        }
+        out.push_str(&line);
+        blank = false;
+    }
+    out
+}
+
+/// PDF text strings are UTF-16BE with a BOM, or PDFDocEncoding (Latin-1 compatible for printable text)
+fn decode_pdf_string(bytes: &[u8]) -> String {
+    if bytes.starts_with(&[0xFE, 0xFF]) {
+        let units: Vec<u16> = bytes[2..]
+            .chunks_exact(2)
+            .map(|c| u16::from_be_bytes([c[0], c[1]]))
+            .collect();
+        return String::from_utf16_lossy(&units);
+    }
+    if let Ok(s) = std::str::from_utf8(bytes) {
+        return s.to_string();
+    }
+    bytes.iter().map(|&b| b as char).collect()
+}
+
+/// `D:YYYYMMDDHHmmSS+HH'mm'` → RFC 3339 (missing trailing parts default to zero / UTC)
+fn parse_pdf_date(raw: &str) -> Option<String> {
+    let s = raw.trim().trim_start_matches("D:");
+    let digits: String = s.chars().take_while(|c| c.is_ascii_digit()).collect();
+    if digits.len() < 4 {
+        return None;
+    }
+    let part = |range: std::ops::Range<usize>, default: u32| {
+        digits.get(range).and_then(|v| v.parse::<u32>().ok()).unwrap_or(default)
+    };
+    let year = part(0..4, 0) as i32;
+    let date = chrono::NaiveDate::from_ymd_opt(year, part(4..6, 1), part(6..8, 1))?;
+    let time = chrono::NaiveTime::from_hms_opt(part(8..10, 0), part(10..12, 0), part(12..14, 0))?;
+
+    let tz = &s[digits.len()..];
+    let offset_secs = match tz.chars().next() {
+        Some(sign @ ('+' | '-')) => {
+            let tz_digits: String = tz[1..].chars().filter(|c| c.is_ascii_digit()).collect();
+            let hours = tz_digits.get(0..2).and_then(|v| v.parse::<i32>().ok()).unwrap_or(0);
+            let minutes = tz_digits.get(2..4).and_then(|v| v.parse::<i32>().ok()).unwrap_or(0);
+            let secs = hours * 3600 + minutes * 60;
+            if sign == '-' { -secs } else { secs }
+        }
+        _ => 0,
+    };
+    let offset = chrono::FixedOffset::east_opt(offset_secs)?;
+    date.and_time(time).and_local_timezone(offset).single().map(|dt| dt.to_rfc3339())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pdf_extract::content::{Content, Operation};
+    use pdf_extract::{dictionary, Stream, StringFormat};
+
+    /// Two-page PDF with an Info dictionary, built with lopdf
+    fn sample_pdf() -> Vec<u8> {
+        let mut doc = Document::with_version("1.5");
+        let pages_id = doc.new_object_id();
+        let font_id = doc.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type1",
+            "BaseFont" => "Helvetica",
+        });
+        let resources_id = doc.add_object(dictionary! {
+            "Font" => dictionary! { "F1" => font_id },
+        });
+        let mut kids = Vec::new();
+        for text in ["First page text", "Second page text"] {
+            let content = Content {
+                operations: vec![
+                    Operation::new("BT", vec![]),
+                    Operation::new("Tf", vec!["F1".into(), 24.into()]),
+                    Operation::new("Td", vec![72.into(), 700.into()]),
+                    Operation::new("Tj", vec![Object::string_literal(text)]),
+                    Operation::new("ET", vec![]),
+                ],
+            };
+            let content_id = doc.add_object(Stream::new(dictionary! {}, content.encode().unwrap()));
+            kids.push(
+                doc.add_object(dictionary! {
+                    "Type" => "Page",
+                    "Parent" => pages_id,
+                    "Contents" => content_id,
+                })
+                .into(),
+            );
+        }
+        doc.objects.insert(
+            pages_id,
+            Object::Dictionary(dictionary! {
+                "Type" => "Pages",
+                "Kids" => kids,
+                "Count" => 2,
+                "Resources" => resources_id,
+                "MediaBox" => vec![0.into(), 0.into(), 595.into(), 842.into()],
+            }),
+        );
+        let catalog_id = doc.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+        let info_id = doc.add_object(dictionary! {
+            "Title" => Object::String(b"\xFE\xFF\x00S\x00p\x00e\x00c".to_vec(), StringFormat::Hexadecimal),
+            "Author" => Object::string_literal("Jane Doe"),
+            "CreationDate" => Object::string_literal("D:20240115093000+02'00'"),
+        });
+        doc.trailer.set("Root", catalog_id);
+        doc.trailer.set("Info", info_id);
+        let mut bytes = Vec::new();
+        doc.save_to(&mut bytes).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn test_extract_pages_and_metadata() {
+        let bytes = sample_pdf();
+        assert!(is_pdf("application/octet-stream", &bytes));
+        assert!(is_pdf("application/pdf; qs=0.9", b""));
+        assert!(!is_pdf("text/html", b"<html>"));
+
+        let pdf = extract(&bytes).unwrap();
+        assert_eq!(pdf.page_count, 2);
+        assert_eq!(pdf.title.as_deref(), Some("Spec"));
+        assert_eq!(pdf.author.as_deref(), Some("Jane Doe"));
+        assert_eq!(pdf.created_at.as_deref(), Some("2024-01-15T09:30:00+02:00"));
+
+        let (text, pages) = pdf.joined_text();
+        assert_eq!(pages.len(), 2);
+        let first: String = text.chars().skip(pages[0].start_char).take(pages[0].end_char - pages[0].start_char).collect();
+        let second: String = text.chars().skip(pages[1].start_char).take(pages[1].end_char - pages[1].start_char).collect();
+        assert_eq!(first, "First page text");
+        assert_eq!(second, "Second page text");
+    }
+
+    #[test]
+    fn test_normalize_and_dates() {
+        assert_eq!(normalize_page("\n  a   b \n\n\n c\n"), "a b\n\nc");
+        assert_eq!(parse_pdf_date("D:2023").as_deref(), Some("2023-01-01T00:00:00+00:00"));
+        assert_eq!(parse_pdf_date("D:20230102030405Z").as_deref(), Some("2023-01-02T03:04:05+00:00"));
+        assert_eq!(parse_pdf_date("garbage"), None);
+    }
+}
@@ -0,0 +1,363 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio::time::Instant;
+use tracing::debug;
+
+/// Upper bound for robots.txt Crawl-delay so a hostile value can't stall a tool call
+const MAX_CRAWL_DELAY: Duration = Duration::from_secs(30);
+
+/// Idle host slots are pruned once the map grows past this size
+const MAX_TRACKED_HOSTS: usize = 1024;
+
+/// Concurrency and spacing limits applied to one host
+#[derive(Debug, Clone, Copy)]
+pub struct HostLimits {
+    pub max_concurrency: usize,
+    pub min_delay: Duration,
+}
+
+/// Scheduler configuration
+#[derive(Debug, Clone)]
+pub struct PolitenessConfig {
+    /// Cap on all outbound requests in flight
+    pub max_concurrency: usize,
+    /// Default limits for every host without an override
+    pub per_host: HostLimits,
+}
+
+impl Default for PolitenessConfig {
+    fn default() -> Self {
+        Self {
+            max_concurrency: 32,
+            per_host: HostLimits {
+                max_concurrency: 2,
+                min_delay: Duration::from_millis(250),
+            },
+        }
+    }
+}
+
+impl PolitenessConfig {
+    /// Read `OUTBOUND_MAX_CONCURRENCY`, `PER_HOST_MAX_CONCURRENCY` and `PER_HOST_MIN_DELAY_MS`
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let env_num = |name: &str| std::env::var(name).ok().and_then(|v| v.trim().parse::<u64>().ok());
+        Self {
+            max_concurrency: env_num("OUTBOUND_MAX_CONCURRENCY")
+                .map(|n| (n as usize).max(1))
+                .unwrap_or(defaults.max_concurrency),
+            per_host: HostLimits {
+                max_concurrency: env_num("PER_HOST_MAX_CONCURRENCY")
+                    .map(|n| (n as usize).max(1))
+                    .unwrap_or(defaults.per_host.max_concurrency),
+                min_delay: env_num("PER_HOST_MIN_DELAY_MS")
+                    .map(Duration::from_millis)
+                    .unwrap_or(defaults.per_host.min_delay),
+            },
+        }
+    }
+}
+
+struct HostSlot {
+    limits: HostLimits,
+    permits: Arc<Semaphore>,
+    // Earliest instant the next request to this host may start
+    next_start: tokio::sync::Mutex<Instant>,
+    queued: AtomicUsize,
+    in_flight: AtomicUsize,
+}
+
+impl HostSlot {
+    fn new(limits: HostLimits) -> Self {
+        Self {
+            limits,
+            permits: Arc::new(Semaphore::new(limits.max_concurrency)),
+            next_start: tokio::sync::Mutex::new(Instant::now()),
+            queued: AtomicUsize::new(0),
+            in_flight: AtomicUsize::new(0),
+        }
+    }
+
+    /// Safe to forget: nobody is waiting or holds a permit, and the spacing window has passed
+    /// (dropping the slot earlier would let the next request skip the host's delay)
+    fn is_idle(&self) -> bool {
+        self.queued.load(Ordering::Relaxed) == 0
+            && self.in_flight.load(Ordering::Relaxed) == 0
+            && self.permits.available_permits() == self.limits.max_concurrency
+            && self.next_start.try_lock().map(|next| *next <= Instant::now()).unwrap_or(false)
+    }
+}
+
+/// Held for the duration of one outbound request; releases the host and global slots on drop
+pub struct HostPermit {
+    slot: Arc<HostSlot>,
+    _host: OwnedSemaphorePermit,
+    _global: OwnedSemaphorePermit,
+}
+
+impl Drop for HostPermit {
+    fn drop(&mut self) {
+        self.slot.in_flight.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Per-host queue snapshot
+#[derive(Debug, Clone, Serialize)]
+pub struct HostMetrics {
+    pub host: String,
+    pub in_flight: usize,
+    pub queued: usize,
+}
+
+/// Snapshot of scheduler queueing metrics
+#[derive(Debug, Clone, Serialize)]
+pub struct SchedulerMetrics {
+    pub total_requests: u64,
+    /// Requests that had to wait for a host slot, spacing delay or global slot
+    pub delayed_requests: u64,
+    pub total_wait_ms: u64,
+    pub max_wait_ms: u64,
+    pub in_flight: usize,
+    pub queued: usize,
+    pub global_available: usize,
+    pub hosts: Vec<HostMetrics>,
+}
+
+/// Per-host politeness scheduler: bounds concurrency per host and globally,
+/// and spaces consecutive requests to the same host by a minimum delay
+/// (or the host's robots.txt Crawl-delay when larger).
+pub struct HostScheduler {
+    config: PolitenessConfig,
+    global: Arc<Semaphore>,
+    hosts: Mutex<HashMap<String, Arc<HostSlot>>>,
+    overrides: Mutex<HashMap<String, HostLimits>>,
+    total_requests: AtomicU64,
+    delayed_requests: AtomicU64,
+    total_wait_ms: AtomicU64,
+    max_wait_ms: AtomicU64,
+}
+
+impl std::fmt::Debug for HostScheduler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HostScheduler").field("config", &self.config).finish()
+    }
+}
+
+impl HostScheduler {
+    pub fn new(config: PolitenessConfig) -> Self {
+        Self {
+            global: Arc::new(Semaphore::new(config.max_concurrency)),
+            config,
+            hosts: Mutex::new(HashMap::new()),
+            overrides: Mutex::new(HashMap::new()),
+            total_requests: AtomicU64::new(0),
+            delayed_requests: AtomicU64::new(0),
+            total_wait_ms: AtomicU64::new(0),
+            max_wait_ms: AtomicU64::new(0),
+        }
+    }
+
+    pub fn from_env() -> Self {
+        Self::new(PolitenessConfig::from_env())
+    }
+
+    /// Use different limits for one host (e.g. our own SearXNG instance)
+    pub fn set_host_limits(&self, host: &str, limits: HostLimits) {
+        let host = host.to_lowercase();
+        self.overrides.lock().unwrap().insert(host.clone(), limits);
+        // Drop any existing slot so the new limits apply to subsequent requests
+        self.hosts.lock().unwrap().remove(&host);
+    }
+
+    /// The host's slot, with the caller already counted in `queued` so the slot
+    /// can't be pruned between lookup and acquiring its permit
+    fn slot_for(&self, host: &str) -> Arc<HostSlot> {
+        let mut hosts = self.hosts.lock().unwrap();
+        if let Some(slot) = hosts.get(host) {
+            slot.queued.fetch_add(1, Ordering::Relaxed);
+            return Arc::clone(slot);
+        }
+        if hosts.len() >= MAX_TRACKED_HOSTS {
+            hosts.retain(|_, slot| !slot.is_idle());
+        }
+        let limits = self
+            .overrides
+            .lock()
+            .unwrap()
+            .get(host)
+            .copied()
+            .unwrap_or(self.config.per_host);
+        let slot = Arc::new(HostSlot::new(limits));
+        slot.queued.fetch_add(1, Ordering::Relaxed);
+        hosts.insert(host.to_string(), Arc::clone(&slot));
+        slot
+    }
+
+    /// Wait for a slot to make a request to `host`.
+    /// `crawl_delay` (from robots.txt) raises the spacing delay when larger than the configured minimum.
+    pub async fn acquire(&self, host: &str, crawl_delay: Option<Duration>) -> HostPermit {
+        let host = host.to_lowercase();
+        let slot = self.slot_for(&host);
+        let started = Instant::now();
+
+        let host_permit = Arc::clone(&slot.permits)
+            .acquire_owned()
+            .await
+            .expect("host semaphore closed");
+        wait_turn(&slot, crawl_delay).await;
+
+        let global_permit = Arc::clone(&self.global)
+            .acquire_owned()
+            .await
+            .expect("global semaphore closed");
+        slot.queued.fetch_sub(1, Ordering::Relaxed);
+        slot.in_flight.fetch_add(1, Ordering::Relaxed);
+
+        let waited_ms = started.elapsed().as_millis() as u64;
+        self.total_requests.fetch_add(1, Ordering::Relaxed);
+        if waited_ms > 0 {
+            self.delayed_requests.fetch_add(1, Ordering::Relaxed);
+            self.total_wait_ms.fetch_add(waited_ms, Ordering::Relaxed);
+            self.max_wait_ms.fetch_max(waited_ms, Ordering::Relaxed);
+            debug!("Outbound request to {} waited {}ms in politeness queue", host, waited_ms);
+        }
+
+        HostPermit {
+            slot,
+            _host: host_permit,
+            _global: global_permit,
+        }
+    }
+
+    /// Wait out `host`'s spacing delay without taking a slot. For redirect hops of a request
+    /// that already holds a permit, where waiting for a second one could deadlock.
+    pub async fn pace(&self, host: &str, crawl_delay: Option<Duration>) {
+        let slot = self.slot_for(&host.to_lowercase());
+        wait_turn(&slot, crawl_delay).await;
+        slot.queued.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    pub fn metrics(&self) -> SchedulerMetrics {
+        let hosts = self.hosts.lock().unwrap();
+        let mut host_metrics: Vec<HostMetrics> = hosts
+            .iter()
+            .filter(|(_, slot)| !slot.is_idle())
+            .map(|(host, slot)| HostMetrics {
+                host: host.clone(),
+                in_flight: slot.in_flight.load(Ordering::Relaxed),
+                queued: slot.queued.load(Ordering::Relaxed),
+            })
+            .collect();
+        host_metrics.sort_by_key(|h| std::cmp::Reverse(h.in_flight + h.queued));
+
+        SchedulerMetrics {
+            total_requests: self.total_requests.load(Ordering::Relaxed),
+            delayed_requests: self.delayed_requests.load(Ordering::Relaxed),
+            total_wait_ms: self.total_wait_ms.load(Ordering::Relaxed),
+            max_wait_ms: self.max_wait_ms.load(Ordering::Relaxed),
+            in_flight: host_metrics.iter().map(|h| h.in_flight).sum(),
+            queued: host_metrics.iter().map(|h| h.queued).sum(),
+            global_available: self.global.available_permits(),
+            hosts: host_metrics,
+        }
+    }
+}
+
+/// Reserve the host's next start time, then sleep until it
+async fn wait_turn(slot: &HostSlot, crawl_delay: Option<Duration>) {
+    let delay = crawl_delay
+        .map(|d| d.min(MAX_CRAWL_DELAY))
+        .unwrap_or_default()
+        .max(slot.limits.min_delay);
+    let start_at = {
+        let mut next_start = slot.next_start.lock().await;
+        let start_at = (*next_start).max(Instant::now());
+        *next_start = start_at + delay;
+        start_at
+    };
+    tokio::time::sleep_until(start_at).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scheduler(per_host: usize, delay_ms: u64) -> Arc<HostScheduler> {
+        Arc::new(HostScheduler::new(PolitenessConfig {
+            max_concurrency: 8,
+            per_host: HostLimits {
+                max_concurrency: per_host,
+                min_delay: Duration::from_millis(delay_ms),
+            },
+        }))
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_min_delay_spaces_same_host() {
+        let sched = scheduler(4, 100);
+        let start = Instant::now();
+        let _a = sched.acquire("example.com", None).await;
+        let _b = sched.acquire("example.com", None).await;
+        let _c = sched.acquire("Example.com", None).await;
+        assert!(start.elapsed() >= Duration::from_millis(200));
+
+        // Another host isn't delayed by example.com's queue
+        let other = Instant::now();
+        let _d = sched.acquire("other.org", None).await;
+        assert!(other.elapsed() < Duration::from_millis(100));
+
+        let metrics = sched.metrics();
+        assert_eq!(metrics.total_requests, 4);
+        assert_eq!(metrics.in_flight, 4);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_crawl_delay_and_per_host_limit() {
+        let sched = scheduler(1, 0);
+        let first = sched.acquire("slow.example", Some(Duration::from_secs(2))).await;
+
+        let sched2 = Arc::clone(&sched);
+        let waiter = tokio::spawn(async move {
+            let start = Instant::now();
+            let _p = sched2.acquire("slow.example", Some(Duration::from_secs(2))).await;
+            start.elapsed()
+        });
+        tokio::task::yield_now().await;
+        assert_eq!(sched.metrics().queued, 1);
+
+        drop(first);
+        let waited = waiter.await.unwrap();
+        assert!(waited >= Duration::from_secs(2));
+        assert_eq!(sched.metrics().delayed_requests, 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_pace_waits_for_spacing_without_a_slot() {
+        let sched = scheduler(1, 0);
+        let _held = sched.acquire("slow.example", Some(Duration::from_secs(2))).await;
+        // A redirect hop to the busy host waits for its crawl delay, not for the held slot
+        let start = Instant::now();
+        sched.pace("slow.example", Some(Duration::from_secs(2))).await;
+        assert!(start.elapsed() >= Duration::from_secs(2));
+        assert_eq!(sched.metrics().queued, 0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_slots_in_use_or_spacing_are_not_idle() {
+        let sched = scheduler(1, 500);
+        // A caller that looked up the slot counts as queued before it waits for a permit
+        let slot = sched.slot_for("busy.example");
+        assert!(!slot.is_idle());
+        slot.queued.fetch_sub(1, Ordering::Relaxed);
+
+        drop(sched.acquire("busy.example", None).await);
+        // Released, but the next request must still wait out the spacing delay
+        assert!(!slot.is_idle());
+        tokio::time::advance(Duration::from_millis(600)).await;
+        assert!(slot.is_idle());
+    }
+}
@@ -0,0 +1,294 @@
+//! MCP prompts: parameterized instructions for common research workflows that walk the
+//! model through this server's own tools. Three are built in (`research_topic`,
+//! `debug_error_message`, `compare_libraries`); `PROMPTS_FILE` points at a JSON array of
+//! prompt definitions that replace built-ins of the same name or add new ones.
+//!
+//! Templates reference arguments as `{{name}}`.
+
+use anyhow::{bail, Context, Result};
+use regex::Regex;
+use rmcp::model::{ErrorData, GetPromptResult, JsonObject, Prompt, PromptArgument, PromptMessage, PromptMessageRole};
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::OnceLock;
+use tracing::{info, warn};
+
+const RESEARCH_TOPIC_TEMPLATE: &str = "Research {{topic}} using the search-scrape tools, in this order:\n\n1. Call research_history with query \"{{topic}}\" to find earlier searches and scrapes. Reuse anything relevant instead of fetching it again.\n2. Call search_web with query \"{{topic}}\" and categories \"it\". Prefer official documentation, reference pages and well-known sources.\n3. Call scrape_url with output_format \"markdown\" on the top {{max_sources}} results that history doesn't already cover.\n4. Write a concise summary of {{topic}} with inline citations [N] and a Sources list of the URLs you used. Point out where sources disagree or look outdated.";
+
+const DEBUG_ERROR_TEMPLATE: &str = "Help me debug this error:\n\n```\n{{error}}\n```\n\nContext: {{context}}\n\n1. Call research_history with the error message to check whether it was investigated before.\n2. Call search_web with the most distinctive part of the error (leave out paths, line numbers and ids) and categories \"it\". Look for issue trackers, Stack Overflow answers and official docs.\n3. Call scrape_url on the 2-3 most promising results to read the actual fix.\n4. Explain the likely cause and give the fix step by step, citing each source [N]. If the cause is ambiguous, list the alternatives and how to tell them apart.";
+
+const COMPARE_LIBRARIES_TEMPLATE: &str = "Compare these libraries for {{use_case}}: {{libraries}}\n\n1. Call research_history with \"{{libraries}}\" to reuse earlier research.\n2. For each library, call search_web with categories \"it\" to find its official documentation and repository, then call scrape_url with output_format \"markdown\" on the docs landing page or README. Use list_site_pages or crawl_site when the docs are spread over many pages.\n3. Compare them on: {{criteria}}.\n4. Present a comparison table, then a recommendation for {{use_case}} with its trade-offs. Cite a source [N] for every claim.";
+
+/// One prompt, as built in or read from `PROMPTS_FILE`
+#[derive(Debug, Clone, Deserialize)]
+pub struct PromptTemplate {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub arguments: Vec<PromptArgumentSpec>,
+    /// Text of the user message; `{{argument}}` is replaced by the argument's value
+    pub template: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PromptArgumentSpec {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub required: bool,
+    /// Used when an optional argument is omitted (empty string if unset)
+    #[serde(default)]
+    pub default: Option<String>,
+}
+
+fn arg(name: &str, description: &str, default: Option<&str>) -> PromptArgumentSpec {
+    PromptArgumentSpec {
+        name: name.to_string(),
+        description: Some(description.to_string()),
+        required: default.is_none(),
+        default: default.map(str::to_string),
+    }
+}
+
+fn placeholder_regex() -> &'static Regex {
+    static PLACEHOLDER: OnceLock<Regex> = OnceLock::new();
+    PLACEHOLDER.get_or_init(|| Regex::new(r"\{\{\s*([A-Za-z0-9_]+)\s*\}\}").unwrap())
+}
+
+impl PromptTemplate {
+    /// Names must be unique and every placeholder must be a declared argument
+    fn validate(&self) -> Result<()> {
+        if self.name.trim().is_empty() {
+            bail!("prompt without a name");
+        }
+        let mut declared = HashSet::new();
+        for argument in &self.arguments {
+            if !declared.insert(argument.name.as_str()) {
+                bail!("prompt '{}' declares argument '{}' twice", self.name, argument.name);
+            }
+        }
+        for placeholder in placeholder_regex().captures_iter(&self.template) {
+            if !declared.contains(&placeholder[1]) {
+                bail!("prompt '{}' uses undeclared argument '{{{{{}}}}}'", self.name, &placeholder[1]);
+            }
+        }
+        Ok(())
+    }
+
+    fn to_prompt(&self) -> Prompt {
+        let arguments = self
+            .arguments
+            .iter()
+            .map(|a| PromptArgument { name: a.name.clone(), description: a.description.clone(), required: Some(a.required) })
+            .collect();
+        Prompt { name: self.name.clone(), description: self.description.clone(), arguments: Some(arguments) }
+    }
+
+    /// Fill in the template. Non-string values are inserted as JSON; missing or empty
+    /// optional arguments take their default.
+    pub fn render(&self, arguments: &JsonObject) -> Result<String, ErrorData> {
+        let mut values = std::collections::HashMap::new();
+        for spec in &self.arguments {
+            let value = match arguments.get(&spec.name) {
+                Some(serde_json::Value::String(s)) => s.trim().to_string(),
+                Some(serde_json::Value::Null) | None => String::new(),
+                Some(other) => other.to_string(),
+            };
+            let value = if value.is_empty() {
+                if spec.required {
+                    return Err(ErrorData::invalid_params(
+                        format!("Prompt '{}' requires argument '{}'", self.name, spec.name),
+                        None,
+                    ));
+                }
+                spec.default.clone().unwrap_or_default()
+            } else {
+                value
+            };
+            values.insert(spec.name.as_str(), value);
+        }
+        let text = placeholder_regex().replace_all(&self.template, |caps: &regex::Captures| {
+            values.get(&caps[1]).cloned().unwrap_or_default()
+        });
+        Ok(text.into_owned())
+    }
+}
+
+/// Built-in prompts plus any loaded from `PROMPTS_FILE`, in listing order
+#[derive(Debug, Clone)]
+pub struct PromptRegistry {
+    prompts: Vec<PromptTemplate>,
+}
+
+impl Default for PromptRegistry {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+impl PromptRegistry {
+    pub fn builtin() -> Self {
+        let prompts = vec![
+            PromptTemplate {
+                name: "research_topic".to_string(),
+                description: Some("Research a topic: check history, search, scrape the best sources and summarize with citations".to_string()),
+                arguments: vec![
+                    arg("topic", "What to research, e.g. 'tokio graceful shutdown'", None),
+                    arg("max_sources", "How many search results to scrape (default 3)", Some("3")),
+                ],
+                template: RESEARCH_TOPIC_TEMPLATE.to_string(),
+            },
+            PromptTemplate {
+                name: "debug_error_message".to_string(),
+                description: Some("Find the cause and fix of an error message from issue trackers, Q&A sites and docs".to_string()),
+                arguments: vec![
+                    arg("error", "The error message or stack trace", None),
+                    arg("context", "Language, library versions, what you were doing", Some("none provided")),
+                ],
+                template: DEBUG_ERROR_TEMPLATE.to_string(),
+            },
+            PromptTemplate {
+                name: "compare_libraries".to_string(),
+                description: Some("Compare libraries from their documentation and recommend one".to_string()),
+                arguments: vec![
+                    arg("libraries", "Comma-separated library names, e.g. 'reqwest, ureq, hyper'", None),
+                    arg(
+                        "criteria",
+                        "What to compare on",
+                        Some("features, performance, API ergonomics, maintenance activity, documentation and license"),
+                    ),
+                    arg("use_case", "What the library is for", Some("general use")),
+                ],
+                template: COMPARE_LIBRARIES_TEMPLATE.to_string(),
+            },
+        ];
+        Self { prompts }
+    }
+
+    /// Built-ins overlaid with the JSON array of prompt definitions at `path`
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        let custom: Vec<PromptTemplate> =
+            serde_json::from_str(&text).with_context(|| format!("Failed to parse prompts in {}", path.display()))?;
+        let mut registry = Self::builtin();
+        let mut seen = HashSet::new();
+        for prompt in custom {
+            prompt.validate().with_context(|| format!("Invalid prompt in {}", path.display()))?;
+            if !seen.insert(prompt.name.clone()) {
+                bail!("prompt '{}' is defined twice in {}", prompt.name, path.display());
+            }
+            match registry.prompts.iter_mut().find(|p| p.name == prompt.name) {
+                Some(existing) => *existing = prompt,
+                None => registry.prompts.push(prompt),
+            }
+        }
+        Ok(registry)
+    }
+
+    /// `PROMPTS_FILE` overlaid on the built-ins, logging (not failing) when the file is unusable
+    pub fn from_env() -> Self {
+        let Some(path) = std::env::var("PROMPTS_FILE").ok().filter(|p| !p.trim().is_empty()) else {
+            return Self::builtin();
+        };
+        match Self::load(Path::new(path.trim())) {
+            Ok(registry) => {
+                info!("Loaded MCP prompts from {} ({} prompts)", path.trim(), registry.prompts.len());
+                registry
+            }
+            Err(e) => {
+                warn!("Using built-in MCP prompts only: {:#}", e);
+                Self::builtin()
+            }
+        }
+    }
+
+    pub fn list(&self) -> Vec<Prompt> {
+        self.prompts.iter().map(PromptTemplate::to_prompt).collect()
+    }
+
+    pub fn get(&self, name: &str, arguments: Option<&JsonObject>) -> Result<GetPromptResult, ErrorData> {
+        let prompt = self
+            .prompts
+            .iter()
+            .find(|p| p.name == name)
+            .ok_or_else(|| ErrorData::invalid_params(format!("Unknown prompt: {}", name), None))?;
+        let text = prompt.render(arguments.unwrap_or(&JsonObject::new()))?;
+        Ok(GetPromptResult {
+            description: prompt.description.clone(),
+            messages: vec![PromptMessage::new_text(PromptMessageRole::User, text)],
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rmcp::model::PromptMessageContent;
+
+    fn text(result: &GetPromptResult) -> &str {
+        match &result.messages[0].content {
+            PromptMessageContent::Text { text } => text,
+            other => panic!("unexpected content {:?}", other),
+        }
+    }
+
+    fn args(value: serde_json::Value) -> JsonObject {
+        value.as_object().unwrap().clone()
+    }
+
+    #[test]
+    fn test_builtin_prompts() {
+        let registry = PromptRegistry::builtin();
+        let names: Vec<_> = registry.list().into_iter().map(|p| p.name).collect();
+        assert_eq!(names, ["research_topic", "debug_error_message", "compare_libraries"]);
+        for prompt in &registry.prompts {
+            prompt.validate().unwrap();
+        }
+
+        let result = registry.get("research_topic", Some(&args(serde_json::json!({ "topic": "tokio select" })))).unwrap();
+        let rendered = text(&result);
+        assert!(rendered.starts_with("Research tokio select using"), "{}", rendered);
+        assert!(rendered.contains("on the top 3 results"), "{}", rendered);
+        assert!(!rendered.contains("{{"), "{}", rendered);
+
+        let result = registry.get("compare_libraries", Some(&args(serde_json::json!({ "libraries": "reqwest, ureq", "use_case": "a CLI" })))).unwrap();
+        assert!(text(&result).starts_with("Compare these libraries for a CLI: reqwest, ureq"));
+
+        let err = registry.get("debug_error_message", Some(&args(serde_json::json!({ "error": "  " })))).unwrap_err();
+        assert_eq!(err.code, rmcp::model::ErrorCode::INVALID_PARAMS);
+        assert!(registry.get("nope", None).is_err());
+    }
+
+    #[test]
+    fn test_load_prompts_file() {
+        let path = std::env::temp_dir().join(format!("prompts-{}.json", uuid::Uuid::new_v4()));
+        std::fs::write(&path, serde_json::json!([
+            {
+                "name": "research_topic",
+                "description": "Team variant",
+                "arguments": [{ "name": "topic", "required": true }],
+                "template": "Check our wiki first, then research {{ topic }}."
+            },
+            {
+                "name": "release_notes",
+                "arguments": [{ "name": "crate", "required": true }, { "name": "since", "default": "the last major release" }],
+                "template": "Summarize what changed in {{crate}} since {{since}}."
+            }
+        ]).to_string()).unwrap();
+        let registry = PromptRegistry::load(&path).unwrap();
+        let names: Vec<_> = registry.list().into_iter().map(|p| p.name).collect();
+        assert_eq!(names, ["research_topic", "debug_error_message", "compare_libraries", "release_notes"]);
+        let result = registry.get("research_topic", Some(&args(serde_json::json!({ "topic": "axum" })))).unwrap();
+        assert_eq!((text(&result), result.description.as_deref()), ("Check our wiki first, then research axum.", Some("Team variant")));
+        let result = registry.get("release_notes", Some(&args(serde_json::json!({ "crate": "serde" })))).unwrap();
+        assert_eq!(text(&result), "Summarize what changed in serde since the last major release.");
+
+        std::fs::write(&path, r#"[{"name": "broken", "template": "Research {{topic}}"}]"#).unwrap();
+        let err = PromptRegistry::load(&path).unwrap_err();
+        assert!(format!("{:#}", err).contains("undeclared argument '{{topic}}'"), "{:#}", err);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,274 @@
+use crate::ssrf::Cidr;
+use anyhow::{anyhow, Context, Result};
+use std::net::IpAddr;
+use tracing::info;
+use url::{Host, Url};
+
+/// Host pattern used by bypass lists and routing rules:
+/// `*` (everything), `example.com` (host and subdomains), `*.example.com` / `.example.com`
+/// (subdomains only), or an IP / CIDR matched against IP-literal hosts
+#[derive(Debug, Clone, PartialEq)]
+pub enum HostPattern {
+    Any,
+    Domain(String),
+    Subdomains(String),
+    Network(Cidr),
+}
+
+impl HostPattern {
+    pub fn parse(s: &str) -> Option<Self> {
+        let s = s.trim().trim_end_matches('.').to_lowercase();
+        if s.is_empty() {
+            return None;
+        }
+        if s == "*" {
+            return Some(Self::Any);
+        }
+        if let Some(cidr) = Cidr::parse(&s) {
+            return Some(Self::Network(cidr));
+        }
+        if let Some(suffix) = s.strip_prefix("*.").or_else(|| s.strip_prefix('.')) {
+            return Some(Self::Subdomains(suffix.to_string()));
+        }
+        Some(Self::Domain(s))
+    }
+
+    pub fn matches(&self, url: &Url) -> bool {
+        let ip = match url.host() {
+            Some(Host::Ipv4(ip)) => Some(IpAddr::V4(ip)),
+            Some(Host::Ipv6(ip)) => Some(IpAddr::V6(ip)),
+            _ => None,
+        };
+        let host = url.host_str().unwrap_or_default().trim_end_matches('.').to_lowercase();
+        match self {
+            Self::Any => true,
+            Self::Network(cidr) => ip.map(|ip| cidr.contains(&ip)).unwrap_or(false),
+            Self::Domain(domain) => host == *domain || host.ends_with(&format!(".{}", domain)),
+            Self::Subdomains(suffix) => host.ends_with(&format!(".{}", suffix)),
+        }
+    }
+}
+
+/// Where requests matching a rule go
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProxyTarget {
+    Direct,
+    Proxy(Url),
+}
+
+/// Outbound proxy configuration shared by SearXNG calls and scraping
+#[derive(Debug, Clone, Default)]
+pub struct ProxyConfig {
+    /// Proxy for hosts not matched by a rule or the bypass list
+    pub default: Option<Url>,
+    /// Hosts that always connect directly (NO_PROXY semantics)
+    pub no_proxy: Vec<HostPattern>,
+    /// Per-domain routing, first match wins; takes precedence over `no_proxy`
+    pub rules: Vec<(HostPattern, ProxyTarget)>,
+}
+
+impl ProxyConfig {
+    /// Read `OUTBOUND_PROXY` (http://, https://, socks5://, socks5h:// URL, credentials allowed),
+    /// `OUTBOUND_PROXY_USERNAME` / `OUTBOUND_PROXY_PASSWORD`, `OUTBOUND_NO_PROXY`
+    /// (comma-separated host patterns) and `OUTBOUND_PROXY_RULES`
+    /// (comma-separated `pattern=proxy-url|direct`).
+    /// Returns `None` when neither a proxy nor rules are configured, leaving reqwest's
+    /// standard `HTTP_PROXY`/`HTTPS_PROXY`/`NO_PROXY` handling in place.
+    pub fn from_env() -> Result<Option<Self>> {
+        let var = |name: &str| std::env::var(name).ok().filter(|v| !v.trim().is_empty());
+        let proxy = var("OUTBOUND_PROXY");
+        let rules = var("OUTBOUND_PROXY_RULES");
+        if proxy.is_none() && rules.is_none() {
+            return Ok(None);
+        }
+        let username = var("OUTBOUND_PROXY_USERNAME");
+        let password = var("OUTBOUND_PROXY_PASSWORD");
+
+        let mut config = Self::default();
+        if let Some(proxy) = proxy {
+            config.default = Some(parse_proxy_url(&proxy, username.as_deref(), password.as_deref())?);
+        }
+        config.no_proxy = var("OUTBOUND_NO_PROXY")
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(HostPattern::parse)
+            .collect();
+        for rule in rules.unwrap_or_default().split(',').filter(|r| !r.trim().is_empty()) {
+            let (pattern, target) = rule
+                .split_once('=')
+                .ok_or_else(|| anyhow!("Invalid OUTBOUND_PROXY_RULES entry '{}': expected pattern=proxy-url|direct", rule))?;
+            let pattern = HostPattern::parse(pattern)
+                .ok_or_else(|| anyhow!("Invalid host pattern in OUTBOUND_PROXY_RULES entry '{}'", rule))?;
+            let target = match target.trim().to_lowercase().as_str() {
+                "direct" | "none" => ProxyTarget::Direct,
+                _ => ProxyTarget::Proxy(parse_proxy_url(target, username.as_deref(), password.as_deref())?),
+            };
+            config.rules.push((pattern, target));
+        }
+
+        info!(
+            "Outbound proxy: default={}, {} bypass entries, {} routing rules",
+            config.default.as_ref().map(redacted).unwrap_or_else(|| "direct".to_string()),
+            config.no_proxy.len(),
+            config.rules.len()
+        );
+        Ok(Some(config))
+    }
+
+    /// Proxy to use for a request URL, or `None` to connect directly.
+    /// Loopback hosts (e.g. a local SearXNG) are always direct.
+    pub fn select(&self, url: &Url) -> Option<Url> {
+        if is_loopback(url) {
+            return None;
+        }
+        if let Some((_, target)) = self.rules.iter().find(|(pattern, _)| pattern.matches(url)) {
+            return match target {
+                ProxyTarget::Direct => None,
+                ProxyTarget::Proxy(proxy) => Some(proxy.clone()),
+            };
+        }
+        if self.no_proxy.iter().any(|pattern| pattern.matches(url)) {
+            return None;
+        }
+        self.default.clone()
+    }
+
+    /// Hostnames of every configured proxy (the scrape client's SSRF resolver must let these through)
+    pub fn proxy_hosts(&self) -> Vec<String> {
+        self.default
+            .iter()
+            .chain(self.rules.iter().filter_map(|(_, t)| match t {
+                ProxyTarget::Proxy(url) => Some(url),
+                ProxyTarget::Direct => None,
+            }))
+            .filter_map(|u| u.host_str().map(|h| h.trim_end_matches('.').to_lowercase()))
+            .collect()
+    }
+
+    /// Route a client's requests through this configuration
+    pub fn apply(&self, builder: reqwest::ClientBuilder) -> reqwest::ClientBuilder {
+        let config = self.clone();
+        builder.proxy(reqwest::Proxy::custom(move |url| config.select(url)))
+    }
+}
+
+/// Whether reqwest's standard proxy variables (`HTTP_PROXY`, `HTTPS_PROXY`, `ALL_PROXY`) are set
+pub fn system_proxy_configured() -> bool {
+    ["HTTP_PROXY", "HTTPS_PROXY", "ALL_PROXY", "http_proxy", "https_proxy", "all_proxy"]
+        .iter()
+        .any(|name| std::env::var(name).is_ok_and(|v| !v.trim().is_empty()))
+}
+
+fn parse_proxy_url(raw: &str, username: Option<&str>, password: Option<&str>) -> Result<Url> {
+    let mut url = Url::parse(raw.trim()).with_context(|| format!("Invalid proxy URL '{}'", raw.trim()))?;
+    if !matches!(url.scheme(), "http" | "https" | "socks5" | "socks5h") {
+        return Err(anyhow!(
+            "Unsupported proxy scheme '{}' (use http, https, socks5 or socks5h)",
+            url.scheme()
+        ));
+    }
+    if url.host_str().is_none() {
+        return Err(anyhow!("Proxy URL '{}' has no host", redacted(&url)));
+    }
+    // Separate credentials only fill in what the URL doesn't already carry
+    if url.username().is_empty() {
+        if let Some(username) = username {
+            let _ = url.set_username(username);
+            let _ = url.set_password(password);
+        }
+    }
+    Ok(url)
+}
+
+fn is_loopback(url: &Url) -> bool {
+    match url.host() {
+        Some(Host::Ipv4(ip)) => ip.is_loopback(),
+        Some(Host::Ipv6(ip)) => ip.is_loopback(),
+        Some(Host::Domain(d)) => d.eq_ignore_ascii_case("localhost") || d.to_lowercase().ends_with(".localhost"),
+        None => false,
+    }
+}
+
+/// Proxy URL with credentials removed, for logs
+fn redacted(url: &Url) -> String {
+    let mut url = url.clone();
+    let _ = url.set_username("");
+    let _ = url.set_password(None);
+    url.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(s: &str) -> Url {
+        Url::parse(s).unwrap()
+    }
+
+    #[test]
+    fn test_host_patterns() {
+        let domain = HostPattern::parse("Corp.Example").unwrap();
+        assert!(domain.matches(&url("https://corp.example/x")));
+        assert!(domain.matches(&url("https://wiki.corp.example/x")));
+        assert!(!domain.matches(&url("https://notcorp.example/x")));
+
+        let subdomains = HostPattern::parse(".corp.example").unwrap();
+        assert_eq!(subdomains, HostPattern::parse("*.corp.example").unwrap());
+        assert!(subdomains.matches(&url("https://wiki.corp.example/")));
+        assert!(!subdomains.matches(&url("https://corp.example/")));
+
+        let network = HostPattern::parse("10.0.0.0/8").unwrap();
+        assert!(network.matches(&url("http://10.1.2.3:8080/")));
+        assert!(!network.matches(&url("http://11.1.2.3/")));
+        assert!(HostPattern::parse("*").unwrap().matches(&url("https://anything.test/")));
+    }
+
+    #[test]
+    fn test_selection_order() {
+        let config = ProxyConfig {
+            default: Some(parse_proxy_url("http://proxy.corp:3128", Some("alice"), Some("p@ss")).unwrap()),
+            no_proxy: vec![HostPattern::parse("corp.example").unwrap(), HostPattern::parse("10.0.0.0/8").unwrap()],
+            rules: vec![
+                (HostPattern::parse("github.com").unwrap(), ProxyTarget::Proxy(url("socks5h://127.0.0.1:1080"))),
+                (HostPattern::parse("legacy.corp.example").unwrap(), ProxyTarget::Proxy(url("http://legacy-proxy:8080"))),
+            ],
+        };
+
+        let default = config.select(&url("https://docs.rs/")).unwrap();
+        assert_eq!(default.host_str(), Some("proxy.corp"));
+        assert_eq!(default.username(), "alice");
+        assert_eq!(default.password(), Some("p%40ss"));
+
+        assert_eq!(config.select(&url("https://api.github.com/")).unwrap().scheme(), "socks5h");
+        // Rules win over the bypass list
+        assert_eq!(config.select(&url("https://legacy.corp.example/")).unwrap().host_str(), Some("legacy-proxy"));
+        assert_eq!(config.select(&url("https://wiki.corp.example/")), None);
+        assert_eq!(config.select(&url("http://10.2.3.4/")), None);
+        assert_eq!(config.select(&url("http://localhost:8888/search")), None);
+
+        assert_eq!(config.proxy_hosts(), vec!["proxy.corp", "127.0.0.1", "legacy-proxy"]);
+        assert!(parse_proxy_url("ftp://proxy:21", None, None).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_requests_go_through_http_proxy() {
+        use axum::{http::{HeaderMap, Uri}, routing::get, Router};
+
+        // Plain-HTTP proxies receive absolute-form request targets
+        let proxy_app = Router::new().fallback(get(|uri: Uri, headers: HeaderMap| async move {
+            let auth = headers.get("proxy-authorization").and_then(|v| v.to_str().ok()).unwrap_or("-").to_string();
+            format!("{} {}", uri, auth)
+        }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, proxy_app).await });
+
+        let config = ProxyConfig {
+            default: Some(parse_proxy_url(&format!("http://{}", addr), Some("bob"), Some("secret")).unwrap()),
+            ..Default::default()
+        };
+        let client = config.apply(reqwest::Client::builder()).build().unwrap();
+        let body = client.get("http://docs.example.test/page").send().await.unwrap().text().await.unwrap();
+        assert_eq!(body, "http://docs.example.test/page Basic Ym9iOnNlY3JldA==");
+    }
+}
@@ -0,0 +1,536 @@
+//! MCP resources: every page in the scrape cache as `scrape://` resources and research
+//! history entries as `history://` resources, so clients can attach earlier results to a
+//! conversation without calling the tools again.
+//!
+//! - `scrape://content/{url}` – extracted plain text
+//! - `scrape://markdown/{url}` – the main content as CommonMark
+//! - `scrape://metadata/{url}` – everything else (title, headings, links, code blocks, ...) as JSON
+//! - `history://{id}` – one research history entry as JSON (needs `QDRANT_URL`)
+//!
+//! `{url}` is the percent-encoded page URL. Subscribers to a `scrape://` URI are notified
+//! when the page is scraped again and its cached copy replaced.
+
+use crate::error::{ErrorInfo, ErrorKind};
+use crate::history::HistoryEntry;
+use crate::types::CachedScrape;
+use crate::{scrape, AppState};
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use rmcp::model::{
+    AnnotateAble, ErrorData, ListResourcesResult, RawResource, RawResourceTemplate, ReadResourceResult,
+    Resource, ResourceContents, ResourceTemplate, ResourceUpdatedNotificationParam,
+};
+use rmcp::{Peer, RoleServer};
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast::error::RecvError;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, warn};
+
+/// Resources per `resources/list` page
+const PAGE_SIZE: usize = 100;
+
+/// History entries included in `resources/list`; older ones can still be read by URI
+const HISTORY_LIST_LIMIT: usize = 50;
+
+/// Everything but RFC 3986 unreserved characters is escaped, so `{url}` is a single path segment
+const URL_SEGMENT: &AsciiSet = &NON_ALPHANUMERIC.remove(b'-').remove(b'.').remove(b'_').remove(b'~');
+
+/// Representation of a cached page served under `scrape://{variant}/{url}`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScrapeVariant {
+    Content,
+    Markdown,
+    Metadata,
+}
+
+impl ScrapeVariant {
+    pub const ALL: [Self; 3] = [Self::Content, Self::Markdown, Self::Metadata];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Content => "content",
+            Self::Markdown => "markdown",
+            Self::Metadata => "metadata",
+        }
+    }
+
+    pub fn mime_type(self) -> &'static str {
+        match self {
+            Self::Content => "text/plain",
+            Self::Markdown => "text/markdown",
+            Self::Metadata => "application/json",
+        }
+    }
+
+    fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|v| v.name() == name)
+    }
+}
+
+/// A parsed resource URI
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResourceUri {
+    Scrape { variant: ScrapeVariant, url: String },
+    History { id: String },
+}
+
+impl ResourceUri {
+    pub fn parse(uri: &str) -> Option<Self> {
+        if let Some(rest) = uri.strip_prefix("scrape://") {
+            let (variant, encoded) = rest.split_once('/')?;
+            let url = percent_decode_str(encoded).decode_utf8().ok()?.into_owned();
+            if url.is_empty() {
+                return None;
+            }
+            return Some(Self::Scrape { variant: ScrapeVariant::parse(variant)?, url });
+        }
+        let id = uri.strip_prefix("history://")?;
+        (!id.is_empty() && !id.contains('/')).then(|| Self::History { id: id.to_string() })
+    }
+}
+
+impl std::fmt::Display for ResourceUri {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Scrape { variant, url } => write!(f, "scrape://{}/{}", variant.name(), utf8_percent_encode(url, URL_SEGMENT)),
+            Self::History { id } => write!(f, "history://{}", id),
+        }
+    }
+}
+
+/// Cached pages (three variants each, ordered by URL) followed by the most recent history
+/// entries. `cursor` is the offset returned as `next_cursor` by the previous page.
+pub async fn list_resources(state: &Arc<AppState>, cursor: Option<&str>) -> Result<ListResourcesResult, ErrorData> {
+    let offset = match cursor {
+        Some(cursor) => cursor
+            .parse::<usize>()
+            .map_err(|_| ErrorData::invalid_params(format!("Invalid cursor: {}", cursor), None))?,
+        None => 0,
+    };
+
+    // Only the in-memory cache can be enumerated; disk-cached pages are still readable by URI.
+    // Entries are shared, so this copies URLs and pointers, not page contents.
+    let mut pages: Vec<_> = state.scrape_cache.iter().collect();
+    pages.sort_by(|a, b| a.0.cmp(&b.0));
+    let variants = ScrapeVariant::ALL.len();
+    let scrape_total = pages.len() * variants;
+    let mut resources: Vec<Resource> = (offset..scrape_total.min(offset + PAGE_SIZE))
+        .map(|index| {
+            let (url, entry) = &pages[index / variants];
+            scrape_resource(url, entry, ScrapeVariant::ALL[index % variants])
+        })
+        .collect();
+
+    // History entries follow the pages; until a page reaches them there's a next page anyway
+    let mut total = scrape_total;
+    if let Some(memory) = state.memory.as_ref().filter(|_| offset + PAGE_SIZE >= scrape_total) {
+        match memory.recent_entries(HISTORY_LIST_LIMIT).await {
+            Ok(entries) => {
+                total += entries.len();
+                let skip = offset.saturating_sub(scrape_total);
+                let take = PAGE_SIZE - resources.len();
+                resources.extend(entries.iter().skip(skip).take(take).map(history_resource));
+            }
+            Err(e) => warn!("Failed to list research history resources: {:#}", e),
+        }
+    }
+
+    let next_cursor = (offset + PAGE_SIZE < total).then(|| (offset + PAGE_SIZE).to_string());
+    Ok(ListResourcesResult { resources, next_cursor })
+}
+
+fn scrape_resource(url: &str, entry: &CachedScrape, variant: ScrapeVariant) -> Resource {
+    let page = &entry.response;
+    let title = if page.title.is_empty() { url } else { page.title.as_str() };
+    let uri = ResourceUri::Scrape { variant, url: url.to_string() }.to_string();
+    let mut resource = RawResource::new(uri, format!("{} ({})", title, variant.name()));
+    resource.description = Some(format!(
+        "{} of {} ({} words, scraped {})",
+        variant.name(),
+        url,
+        page.word_count,
+        entry.fetched_at.to_rfc3339()
+    ));
+    resource.mime_type = Some(variant.mime_type().to_string());
+    resource.no_annotation()
+}
+
+fn history_resource(entry: &HistoryEntry) -> Resource {
+    let uri = ResourceUri::History { id: entry.id.clone() }.to_string();
+    let mut resource = RawResource::new(uri, entry.summary.clone());
+    resource.description = Some(format!("Research history ({}) from {}", entry.topic, entry.timestamp.to_rfc3339()));
+    resource.mime_type = Some("application/json".to_string());
+    resource.no_annotation()
+}
+
+/// URI templates for the `scrape://` variants and `history://` entries
+pub fn resource_templates() -> Vec<ResourceTemplate> {
+    let mut templates: Vec<ResourceTemplate> = ScrapeVariant::ALL
+        .into_iter()
+        .map(|variant| {
+            RawResourceTemplate {
+                uri_template: format!("scrape://{}/{{url}}", variant.name()),
+                name: format!("Scraped page ({})", variant.name()),
+                description: Some(format!(
+                    "{} of a previously scraped page; url is the percent-encoded page URL",
+                    variant.name()
+                )),
+                mime_type: Some(variant.mime_type().to_string()),
+            }
+            .no_annotation()
+        })
+        .collect();
+    templates.push(
+        RawResourceTemplate {
+            uri_template: "history://{id}".to_string(),
+            name: "Research history entry".to_string(),
+            description: Some("A logged search or scrape, as returned by research_history".to_string()),
+            mime_type: Some("application/json".to_string()),
+        }
+        .no_annotation(),
+    );
+    templates
+}
+
+/// Contents of a resource. Never fetches: pages that aren't cached are `RESOURCE_NOT_FOUND`.
+pub async fn read_resource(state: &Arc<AppState>, uri: &str) -> Result<ReadResourceResult, ErrorData> {
+    let not_found = |message: String| ErrorData::resource_not_found(message, Some(serde_json::json!({ "uri": uri })));
+    let parsed = ResourceUri::parse(uri).ok_or_else(|| not_found(format!("Unknown resource URI: {}", uri)))?;
+    let (text, mime_type) = match parsed {
+        ResourceUri::Scrape { variant, url } => {
+            let entry = scrape::cached_scrape(state, &url)
+                .await
+                .ok_or_else(|| not_found(format!("{} has not been scraped (or has expired from the cache); call scrape_url first", url)))?;
+            let text = match variant {
+                ScrapeVariant::Content => entry.response.clean_content.clone(),
+                ScrapeVariant::Markdown => scrape::markdown_output(state, &entry.response, usize::MAX).await,
+                ScrapeVariant::Metadata => {
+                    let mut metadata = serde_json::to_value(&entry.response).map_err(internal_error)?;
+                    if let Some(fields) = metadata.as_object_mut() {
+                        // The text lives in the content/markdown variants
+                        fields.remove("content");
+                        fields.remove("clean_content");
+                        fields.insert("fetched_at".to_string(), serde_json::json!(entry.fetched_at));
+                    }
+                    serde_json::to_string_pretty(&metadata).map_err(internal_error)?
+                }
+            };
+            (text, variant.mime_type())
+        }
+        ResourceUri::History { id } => {
+            let memory = state.memory.as_ref().ok_or_else(|| {
+                ErrorInfo::new(ErrorKind::NotConfigured, "Research history is not enabled. Set QDRANT_URL to enable it.")
+                    .to_error_data()
+            })?;
+            // Entry ids are UUIDs; anything else can't exist
+            if uuid::Uuid::parse_str(&id).is_err() {
+                return Err(not_found(format!("No research history entry {}", id)));
+            }
+            let entry = memory
+                .get_entry(&id)
+                .await
+                .map_err(|e| ErrorInfo::from_error(&e).to_error_data())?
+                .ok_or_else(|| not_found(format!("No research history entry {}", id)))?;
+            (serde_json::to_string_pretty(&entry).map_err(internal_error)?, "application/json")
+        }
+    };
+    Ok(ReadResourceResult {
+        contents: vec![ResourceContents::TextResourceContents {
+            uri: uri.to_string(),
+            mime_type: Some(mime_type.to_string()),
+            text,
+        }],
+    })
+}
+
+fn internal_error(e: serde_json::Error) -> ErrorData {
+    ErrorData::internal_error(e.to_string(), None)
+}
+
+/// Resource URIs one client session has subscribed to. The first subscription starts a task
+/// that forwards `AppState::resource_updates` to the client as `notifications/resources/updated`;
+/// it ends when the last handle of the session is dropped or its transport is closed.
+#[derive(Debug, Clone, Default)]
+pub struct Subscriptions {
+    inner: Arc<Mutex<SubscriptionState>>,
+    /// Stops the watcher once every clone held by the session is gone
+    session: Arc<SessionGuard>,
+}
+
+#[derive(Debug, Default)]
+struct SessionGuard(CancellationToken);
+
+impl SessionGuard {
+    fn ended(&self) -> CancellationToken {
+        self.0.clone()
+    }
+}
+
+impl Drop for SessionGuard {
+    fn drop(&mut self) {
+        self.0.cancel();
+    }
+}
+
+#[derive(Debug, Default)]
+struct SubscriptionState {
+    uris: HashSet<String>,
+    watching: bool,
+}
+
+impl Subscriptions {
+    /// Any `scrape://` URI may be subscribed to, including pages that haven't been scraped yet.
+    /// History entries never change once logged, so `history://` subscriptions are refused.
+    pub fn subscribe(&self, state: &AppState, uri: &str, peer: Peer<RoleServer>) -> Result<(), ErrorData> {
+        match ResourceUri::parse(uri) {
+            Some(ResourceUri::Scrape { .. }) => {}
+            Some(ResourceUri::History { .. }) => {
+                return Err(ErrorData::invalid_params(
+                    format!("{} can't be subscribed to: history entries never change", uri),
+                    None,
+                ));
+            }
+            None => return Err(ErrorData::invalid_params(format!("Unknown resource URI: {}", uri), None)),
+        }
+        let start_watching = {
+            let mut inner = self.inner.lock().unwrap();
+            inner.uris.insert(uri.to_string());
+            !std::mem::replace(&mut inner.watching, true)
+        };
+        if start_watching {
+            self.watch(state.resource_updates.subscribe(), peer);
+        }
+        Ok(())
+    }
+
+    pub fn unsubscribe(&self, uri: &str) {
+        self.inner.lock().unwrap().uris.remove(uri);
+    }
+
+    fn watch(&self, mut updates: tokio::sync::broadcast::Receiver<String>, peer: Peer<RoleServer>) {
+        // Holding only the state (not the guard) lets the session end while this task runs
+        let subscriptions = Arc::clone(&self.inner);
+        let session_ended = self.session.ended();
+        tokio::spawn(async move {
+            loop {
+                let url = tokio::select! {
+                    _ = session_ended.cancelled() => break,
+                    update = updates.recv() => match update {
+                        Ok(url) => url,
+                        // Some updates were dropped; later ones are still delivered
+                        Err(RecvError::Lagged(_)) => continue,
+                        Err(RecvError::Closed) => break,
+                    },
+                };
+                if peer.is_transport_closed() {
+                    break;
+                }
+                let uris = affected(&subscriptions.lock().unwrap(), &url);
+                for uri in uris {
+                    if let Err(e) = peer.notify_resource_updated(ResourceUpdatedNotificationParam { uri }).await {
+                        debug!("Stopped resource notifications: {}", e);
+                        return;
+                    }
+                }
+            }
+        });
+    }
+}
+
+/// Subscribed URIs that change when `url` is re-scraped
+fn affected(state: &SubscriptionState, url: &str) -> Vec<String> {
+    ScrapeVariant::ALL
+        .into_iter()
+        .map(|variant| ResourceUri::Scrape { variant, url: url.to_string() }.to_string())
+        .filter(|uri| state.uris.contains(uri))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stdio_service::McpService;
+    use rmcp::ServiceExt;
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    async fn page_server() -> std::net::SocketAddr {
+        use axum::{response::Html, routing::get, Router};
+
+        let page = "<html><head><title>Guide</title></head><body><article><h1>Guide</h1>\
+            <p>Some words about the guide that are long enough to count as content.</p>\
+            <pre><code class=\"language-rust\">fn main() {}</code></pre></article></body></html>";
+        let app = Router::new().route("/guide", get(move || async move { Html(page) }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        addr
+    }
+
+    fn test_state() -> Arc<AppState> {
+        let state = AppState::new("http://localhost:8888".to_string(), reqwest::Client::new())
+            .with_ssrf_policy(crate::ssrf::SsrfPolicy::disabled());
+        Arc::new(state)
+    }
+
+    /// Newline-delimited JSON-RPC over an in-memory pipe, as a stdio client would speak it
+    struct JsonRpcClient {
+        lines: tokio::io::Lines<BufReader<tokio::io::ReadHalf<tokio::io::DuplexStream>>>,
+        write: tokio::io::WriteHalf<tokio::io::DuplexStream>,
+    }
+
+    impl JsonRpcClient {
+        fn new(stream: tokio::io::DuplexStream) -> Self {
+            let (read, write) = tokio::io::split(stream);
+            Self { lines: BufReader::new(read).lines(), write }
+        }
+
+        async fn send(&mut self, message: serde_json::Value) {
+            self.write.write_all(format!("{}\n", message).as_bytes()).await.unwrap();
+        }
+
+        async fn recv(&mut self) -> serde_json::Value {
+            serde_json::from_str(&self.lines.next_line().await.unwrap().unwrap()).unwrap()
+        }
+    }
+
+    #[test]
+    fn test_resource_uris() {
+        let uri = ResourceUri::Scrape { variant: ScrapeVariant::Markdown, url: "https://docs.rs/a/b?x=1&y=2".to_string() };
+        let text = uri.to_string();
+        assert_eq!(text, "scrape://markdown/https%3A%2F%2Fdocs.rs%2Fa%2Fb%3Fx%3D1%26y%3D2");
+        assert_eq!(ResourceUri::parse(&text), Some(uri));
+        assert_eq!(
+            ResourceUri::parse("history://0b5c6f0e-1d2b-4f5e-9a51-3c2c1f1e8d10"),
+            Some(ResourceUri::History { id: "0b5c6f0e-1d2b-4f5e-9a51-3c2c1f1e8d10".to_string() })
+        );
+        assert_eq!(ResourceUri::parse("scrape://summary/https%3A%2F%2Fa.test"), None);
+        assert_eq!(ResourceUri::parse("scrape://content/"), None);
+        assert_eq!(ResourceUri::parse("file:///etc/passwd"), None);
+    }
+
+    #[tokio::test]
+    async fn test_list_and_read_scraped_pages() {
+        let addr = page_server().await;
+        let state = test_state();
+        let url = format!("http://{}/guide", addr);
+        scrape::scrape_url(&state, &url).await.unwrap();
+        state.scrape_cache.run_pending_tasks().await;
+
+        let listed = list_resources(&state, None).await.unwrap();
+        let uris: Vec<_> = listed.resources.iter().map(|r| r.uri.clone()).collect();
+        let uri = |variant| ResourceUri::Scrape { variant, url: url.clone() }.to_string();
+        assert_eq!(uris, ScrapeVariant::ALL.map(uri).to_vec());
+        assert_eq!(listed.resources[0].name, "Guide (content)");
+        assert!(listed.next_cursor.is_none());
+
+        let read = |variant| {
+            let state = Arc::clone(&state);
+            let uri = uri(variant);
+            async move {
+                match read_resource(&state, &uri).await.unwrap().contents.remove(0) {
+                    ResourceContents::TextResourceContents { text, mime_type, .. } => (text, mime_type.unwrap()),
+                    other => panic!("unexpected contents {:?}", other),
+                }
+            }
+        };
+        let (content, mime) = read(ScrapeVariant::Content).await;
+        assert!(content.contains("long enough to count as content"), "{}", content);
+        assert_eq!(mime, "text/plain");
+        let (markdown, _) = read(ScrapeVariant::Markdown).await;
+        assert!(markdown.starts_with("# Guide") && markdown.contains("```\nfn main() {}\n```"), "{}", markdown);
+        let (metadata, mime) = read(ScrapeVariant::Metadata).await;
+        let metadata: serde_json::Value = serde_json::from_str(&metadata).unwrap();
+        assert_eq!((metadata["title"].as_str(), mime.as_str()), (Some("Guide"), "application/json"));
+        assert!(metadata.get("clean_content").is_none() && metadata.get("fetched_at").is_some());
+
+        let missing = ResourceUri::Scrape { variant: ScrapeVariant::Content, url: "https://never.test/".to_string() };
+        let err = read_resource(&state, &missing.to_string()).await.unwrap_err();
+        assert_eq!(err.code, rmcp::model::ErrorCode::RESOURCE_NOT_FOUND);
+        let err = read_resource(&state, "history://0b5c6f0e-1d2b-4f5e-9a51-3c2c1f1e8d10").await.unwrap_err();
+        assert_eq!(err.data.unwrap()["code"], "not_configured");
+        assert!(list_resources(&state, Some("soon")).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_list_pages_through_cursor() {
+        let addr = page_server().await;
+        let state = test_state();
+        let url = format!("http://{}/guide", addr);
+        scrape::scrape_url(&state, &url).await.unwrap();
+        let entry = state.scrape_cache.get(&url).await.unwrap();
+        for i in 0..39 {
+            state.scrape_cache.insert(format!("https://docs.test/{:02}", i), Arc::clone(&entry)).await;
+        }
+        state.scrape_cache.run_pending_tasks().await;
+
+        let first = list_resources(&state, None).await.unwrap();
+        assert_eq!(first.resources.len(), PAGE_SIZE);
+        assert_eq!(first.resources[0].uri, ResourceUri::Scrape { variant: ScrapeVariant::Content, url }.to_string());
+        let second = list_resources(&state, first.next_cursor.as_deref()).await.unwrap();
+        assert_eq!(second.resources.len(), 40 * ScrapeVariant::ALL.len() - PAGE_SIZE);
+        let last = ResourceUri::Scrape { variant: ScrapeVariant::Metadata, url: "https://docs.test/38".to_string() };
+        assert_eq!(second.resources.last().unwrap().uri, last.to_string());
+        assert!(second.next_cursor.is_none());
+        assert!(list_resources(&state, Some("1000")).await.unwrap().resources.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_subscribers_are_notified_of_rescrapes() {
+        let addr = page_server().await;
+        let state = test_state();
+        let url = format!("http://{}/guide", addr);
+        let (client, server) = tokio::io::duplex(64 * 1024);
+        let session_state = Arc::clone(&state);
+        tokio::spawn(async move {
+            let service = McpService::from_state(session_state).serve(tokio::io::split(server)).await.unwrap();
+            service.waiting().await
+        });
+        let mut client = JsonRpcClient::new(client);
+
+        client.send(serde_json::json!({
+            "jsonrpc": "2.0", "id": 1, "method": "initialize",
+            "params": {"protocolVersion": "2025-03-26", "capabilities": {}, "clientInfo": {"name": "test", "version": "0"}}
+        })).await;
+        let init = client.recv().await;
+        assert_eq!(init["result"]["capabilities"]["resources"]["subscribe"], true);
+        client.send(serde_json::json!({"jsonrpc": "2.0", "method": "notifications/initialized"})).await;
+
+        let uri = ResourceUri::Scrape { variant: ScrapeVariant::Markdown, url: url.clone() }.to_string();
+        client.send(serde_json::json!({"jsonrpc": "2.0", "id": 2, "method": "resources/subscribe", "params": {"uri": uri}})).await;
+        assert!(client.recv().await["result"].is_object());
+
+        // Scraping the page replaces its cached copy and notifies the subscriber
+        client.send(serde_json::json!({"jsonrpc": "2.0", "id": 3, "method": "tools/call", "params": {"name": "scrape_url", "arguments": {"url": url}}})).await;
+        let mut notified = false;
+        let mut answered = false;
+        while !(notified && answered) {
+            let message = client.recv().await;
+            if message["method"] == "notifications/resources/updated" {
+                assert_eq!(message["params"]["uri"], uri.as_str());
+                notified = true;
+            } else {
+                assert_eq!(message["id"], 3);
+                answered = true;
+            }
+        }
+
+        client.send(serde_json::json!({"jsonrpc": "2.0", "id": 4, "method": "resources/templates/list"})).await;
+        assert_eq!(client.recv().await["result"]["resourceTemplates"].as_array().unwrap().len(), 4);
+
+        // History entries never change, so there's nothing to subscribe to
+        let history = "history://0b5c6f0e-1d2b-4f5e-9a51-3c2c1f1e8d10";
+        client.send(serde_json::json!({"jsonrpc": "2.0", "id": 5, "method": "resources/subscribe", "params": {"uri": history}})).await;
+        assert_eq!(client.recv().await["error"]["code"], -32602);
+
+        // Disconnecting ends the session's watcher
+        assert_eq!(state.resource_updates.receiver_count(), 1);
+        drop(client);
+        tokio::time::timeout(std::time::Duration::from_secs(5), async {
+            while state.resource_updates.receiver_count() > 0 {
+                tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("watcher still running after the client disconnected");
+    }
+}
@@ -0,0 +1,476 @@
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+use url::Url;
+
+/// Default product token matched against `User-agent` lines in robots.txt
+const DEFAULT_ROBOTS_USER_AGENT: &str = "search-scrape";
+
+/// RFC 9309 asks crawlers to parse at least 500 KiB; anything beyond is ignored
+const MAX_ROBOTS_BYTES: usize = 500 * 1024;
+
+/// How long a fetched robots.txt is reused
+const ROBOTS_CACHE_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// How long an unreachable robots.txt (network failure, 5xx) blocks the origin before it is retried
+const UNAVAILABLE_CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// How robots.txt rules are applied to scrapes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RobotsMode {
+    /// Refuse to scrape disallowed URLs
+    Enforce,
+    /// Scrape anyway but flag the response with a `disallowed_by_robots` warning
+    Warn,
+    /// Never fetch robots.txt
+    Off,
+}
+
+impl RobotsMode {
+    /// Read `ROBOTS_TXT_MODE` (enforce | warn | off), defaulting to enforce
+    pub fn from_env() -> Self {
+        match std::env::var("ROBOTS_TXT_MODE")
+            .unwrap_or_default()
+            .trim()
+            .to_lowercase()
+            .as_str()
+        {
+            "warn" => RobotsMode::Warn,
+            "off" | "false" | "0" | "disabled" => RobotsMode::Off,
+            _ => RobotsMode::Enforce,
+        }
+    }
+}
+
+/// Error returned when robots.txt forbids fetching a URL
+#[derive(Debug, thiserror::Error)]
+#[error("disallowed_by_robots: {url} is disallowed by robots.txt for user-agent '{user_agent}'")]
+pub struct RobotsDisallowed {
+    pub url: String,
+    pub user_agent: String,
+}
+
+#[derive(Debug, Clone)]
+struct Rule {
+    allow: bool,
+    pattern: String,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Group {
+    agents: Vec<String>,
+    rules: Vec<Rule>,
+    crawl_delay: Option<f64>,
+}
+
+/// Parsed robots.txt file
+#[derive(Debug, Clone, Default)]
+pub struct RobotsTxt {
+    groups: Vec<Group>,
+    sitemaps: Vec<String>,
+    /// Stand-in for a robots.txt that couldn't be fetched; cached only briefly
+    unavailable: bool,
+}
+
+impl RobotsTxt {
+    /// A robots.txt that allows everything (missing file, 4xx)
+    pub fn allow_all() -> Self {
+        Self::default()
+    }
+
+    /// Disallow-all for an unreachable robots.txt (network failure, 5xx), per RFC 9309
+    pub fn unavailable() -> Self {
+        Self { unavailable: true, ..Self::disallow_all() }
+    }
+
+    /// A robots.txt that disallows everything
+    pub fn disallow_all() -> Self {
+        Self {
+            groups: vec![Group {
+                agents: vec!["*".to_string()],
+                rules: vec![Rule { allow: false, pattern: "/".to_string() }],
+                crawl_delay: None,
+            }],
+            sitemaps: Vec::new(),
+            unavailable: false,
+        }
+    }
+
+    /// Parse robots.txt content into user-agent groups
+    pub fn parse(body: &str) -> Self {
+        let mut groups: Vec<Group> = Vec::new();
+        let mut sitemaps = Vec::new();
+        let mut current: Option<Group> = None;
+        // True while we're still reading consecutive User-agent lines of one group
+        let mut collecting_agents = false;
+
+        for raw_line in body.lines() {
+            let line = raw_line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let Some((key, value)) = line.split_once(':') else { continue };
+            let key = key.trim().to_lowercase();
+            let value = value.trim();
+
+            match key.as_str() {
+                "user-agent" => {
+                    if !collecting_agents {
+                        if let Some(group) = current.take() {
+                            groups.push(group);
+                        }
+                        current = Some(Group::default());
+                        collecting_agents = true;
+                    }
+                    if let Some(group) = current.as_mut() {
+                        group.agents.push(value.to_lowercase());
+                    }
+                }
+                "allow" | "disallow" => {
+                    collecting_agents = false;
+                    if let Some(group) = current.as_mut() {
+                        // An empty Disallow means "allow everything" and carries no rule
+                        if !value.is_empty() {
+                            group.rules.push(Rule {
+                                allow: key == "allow",
+                                pattern: value.to_string(),
+                            });
+                        }
+                    }
+                }
+                "crawl-delay" => {
+                    collecting_agents = false;
+                    if let Some(group) = current.as_mut() {
+                        if let Ok(delay) = value.parse::<f64>() {
+                            if delay.is_finite() && delay >= 0.0 {
+                                group.crawl_delay = Some(delay);
+                            }
+                        }
+                    }
+                }
+                "sitemap" => {
+                    if !value.is_empty() {
+                        sitemaps.push(value.to_string());
+                    }
+                }
+                _ => {
+                    collecting_agents = false;
+                }
+            }
+        }
+
+        if let Some(group) = current.take() {
+            groups.push(group);
+        }
+
+        Self { groups, sitemaps, unavailable: false }
+    }
+
+    /// Groups that apply to the given product token; falls back to `*`
+    fn groups_for(&self, user_agent: &str) -> Vec<&Group> {
+        let ua = user_agent.to_lowercase();
+        let specific: Vec<&Group> = self
+            .groups
+            .iter()
+            .filter(|g| g.agents.iter().any(|a| a != "*" && ua.starts_with(a.as_str())))
+            .collect();
+        if !specific.is_empty() {
+            return specific;
+        }
+        self.groups
+            .iter()
+            .filter(|g| g.agents.iter().any(|a| a == "*"))
+            .collect()
+    }
+
+    /// Check whether a path (including query string) may be fetched.
+    /// The longest matching rule wins; on a tie Allow beats Disallow.
+    pub fn is_allowed(&self, user_agent: &str, path: &str) -> bool {
+        let path = if path.is_empty() { "/" } else { path };
+        let mut best: Option<(usize, bool)> = None;
+
+        for group in self.groups_for(user_agent) {
+            for rule in &group.rules {
+                if !pattern_matches(&rule.pattern, path) {
+                    continue;
+                }
+                let len = rule.pattern.len();
+                best = match best {
+                    Some((best_len, best_allow)) if best_len > len || (best_len == len && best_allow) => {
+                        Some((best_len, best_allow))
+                    }
+                    _ => Some((len, rule.allow)),
+                };
+            }
+        }
+
+        best.map(|(_, allow)| allow).unwrap_or(true)
+    }
+
+    /// Crawl-delay declared for the matching group, if any
+    pub fn crawl_delay(&self, user_agent: &str) -> Option<Duration> {
+        self.groups_for(user_agent)
+            .iter()
+            .filter_map(|g| g.crawl_delay)
+            .reduce(f64::max)
+            .map(Duration::from_secs_f64)
+    }
+
+    /// `Sitemap:` URLs declared anywhere in the file
+    pub fn sitemaps(&self) -> &[String] {
+        &self.sitemaps
+    }
+}
+
+/// Match a robots.txt path pattern supporting `*` wildcards and a trailing `$` anchor
+fn pattern_matches(pattern: &str, path: &str) -> bool {
+    let (pattern, anchored) = match pattern.strip_suffix('$') {
+        Some(p) => (p, true),
+        None => (pattern, false),
+    };
+    let pat: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = path.chars().collect();
+
+    // Iterative wildcard matching with backtracking to the last `*`
+    let (mut p, mut t) = (0usize, 0usize);
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        if p < pat.len() && pat[p] == '*' {
+            star = Some((p, t));
+            p += 1;
+        } else if p < pat.len() && pat[p] == text[t] {
+            p += 1;
+            t += 1;
+        } else if p == pat.len() && !anchored {
+            // Unanchored patterns are prefixes
+            return true;
+        } else if let Some((sp, st)) = star {
+            p = sp + 1;
+            t = st + 1;
+            star = Some((sp, st + 1));
+        } else {
+            return false;
+        }
+    }
+    while p < pat.len() && pat[p] == '*' {
+        p += 1;
+    }
+    p == pat.len()
+}
+
+/// Fetches robots.txt per origin and caches the parsed result
+pub struct RobotsChecker {
+    client: reqwest::Client,
+    user_agent: String,
+    mode: RobotsMode,
+    cache: moka::future::Cache<String, Arc<RobotsTxt>>, // key: scheme://host:port
+}
+
+impl std::fmt::Debug for RobotsChecker {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RobotsChecker")
+            .field("user_agent", &self.user_agent)
+            .field("mode", &self.mode)
+            .finish()
+    }
+}
+
+/// Expires stand-ins for unreachable robots.txt files after `UNAVAILABLE_CACHE_TTL`
+struct UnavailableExpiry;
+
+impl moka::Expiry<String, Arc<RobotsTxt>> for UnavailableExpiry {
+    fn expire_after_create(&self, _key: &String, value: &Arc<RobotsTxt>, _created_at: std::time::Instant) -> Option<Duration> {
+        value.unavailable.then_some(UNAVAILABLE_CACHE_TTL)
+    }
+}
+
+impl RobotsChecker {
+    pub fn new(client: reqwest::Client, user_agent: impl Into<String>, mode: RobotsMode) -> Self {
+        Self {
+            client,
+            user_agent: user_agent.into(),
+            mode,
+            cache: moka::future::Cache::builder()
+                .max_capacity(10_000)
+                .time_to_live(ROBOTS_CACHE_TTL)
+                .expire_after(UnavailableExpiry)
+                .build(),
+        }
+    }
+
+    /// Build from `ROBOTS_TXT_MODE` and `ROBOTS_USER_AGENT`
+    pub fn from_env(client: reqwest::Client) -> Self {
+        let user_agent = std::env::var("ROBOTS_USER_AGENT")
+            .ok()
+            .filter(|s| !s.trim().is_empty())
+            .unwrap_or_else(|| DEFAULT_ROBOTS_USER_AGENT.to_string());
+        Self::new(client, user_agent, RobotsMode::from_env())
+    }
+
+    pub fn mode(&self) -> RobotsMode {
+        self.mode
+    }
+
+    pub fn user_agent(&self) -> &str {
+        &self.user_agent
+    }
+
+    /// Whether robots.txt permits fetching `url`. Always true when mode is Off.
+    pub async fn is_allowed(&self, url: &Url) -> bool {
+        if self.mode == RobotsMode::Off {
+            return true;
+        }
+        let robots = self.robots_for(url).await;
+        let mut path = url.path().to_string();
+        if let Some(q) = url.query() {
+            path.push('?');
+            path.push_str(q);
+        }
+        let allowed = robots.is_allowed(&self.user_agent, &path);
+        if !allowed {
+            info!("robots.txt disallows {} for user-agent '{}'", url, self.user_agent);
+        }
+        allowed
+    }
+
+    /// Crawl-delay for the URL's host, if robots.txt declares one
+    pub async fn crawl_delay(&self, url: &Url) -> Option<Duration> {
+        if self.mode == RobotsMode::Off {
+            return None;
+        }
+        self.robots_for(url).await.crawl_delay(&self.user_agent)
+    }
+
+    /// Get the (cached) robots.txt for the URL's origin
+    pub async fn robots_for(&self, url: &Url) -> Arc<RobotsTxt> {
+        let origin = url.origin().ascii_serialization();
+        self.cache
+            .get_with(origin.clone(), async move { Arc::new(self.fetch(&origin).await) })
+            .await
+    }
+
+    async fn fetch(&self, origin: &str) -> RobotsTxt {
+        let robots_url = format!("{}/robots.txt", origin);
+        debug!("Fetching {}", robots_url);
+
+        let response = match self
+            .client
+            .get(&robots_url)
+            .header("User-Agent", format!("Mozilla/5.0 (compatible; {})", self.user_agent))
+            .timeout(Duration::from_secs(10))
+            .send()
+            .await
+        {
+            Ok(r) => r,
+            Err(e) => {
+                warn!("Failed to fetch {}: {}. Assuming disallow-all.", robots_url, e);
+                return RobotsTxt::unavailable();
+            }
+        };
+
+        let status = response.status();
+        if status.is_server_error() {
+            warn!("{} returned {}. Assuming disallow-all.", robots_url, status);
+            return RobotsTxt::unavailable();
+        }
+        if !status.is_success() {
+            debug!("{} returned {}. Assuming allow-all.", robots_url, status);
+            return RobotsTxt::allow_all();
+        }
+
+        // Stop reading at the cap instead of downloading whatever the host streams; robots.txt
+        // is plain text whatever its Content-Type says
+        match crate::body::read_limited(response, &robots_url, "text/plain", MAX_ROBOTS_BYTES).await {
+            Ok(body) => RobotsTxt::parse(&String::from_utf8_lossy(&body.bytes)),
+            Err(e) => {
+                warn!("Failed to read {}: {}. Assuming disallow-all.", robots_url, e);
+                RobotsTxt::unavailable()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_group_selection_and_precedence() {
+        let robots = RobotsTxt::parse(
+            "User-agent: *\nDisallow: /private\nAllow: /private/public\n\n\
+             User-agent: search-scrape\nUser-agent: otherbot\nDisallow: /no-scrape\n",
+        );
+        // Generic group
+        assert!(!robots.is_allowed("somebot", "/private/data"));
+        assert!(robots.is_allowed("somebot", "/private/public/page"));
+        assert!(robots.is_allowed("somebot", "/no-scrape"));
+        // Specific group replaces the generic one
+        assert!(!robots.is_allowed("search-scrape", "/no-scrape/x"));
+        assert!(robots.is_allowed("search-scrape", "/private/data"));
+        assert!(!robots.is_allowed("OtherBot", "/no-scrape"));
+    }
+
+    #[test]
+    fn test_wildcards_and_anchor() {
+        let robots = RobotsTxt::parse(
+            "User-agent: *\nDisallow: /*.pdf$\nDisallow: /search*q=\nAllow: /search/about\n",
+        );
+        assert!(!robots.is_allowed("bot", "/docs/spec.pdf"));
+        assert!(robots.is_allowed("bot", "/docs/spec.pdf.html"));
+        assert!(!robots.is_allowed("bot", "/search?q=rust"));
+        assert!(robots.is_allowed("bot", "/search/about"));
+        assert!(robots.is_allowed("bot", "/"));
+    }
+
+    #[test]
+    fn test_allow_wins_ties_and_empty_disallow() {
+        let robots = RobotsTxt::parse("User-agent: *\nDisallow: /page\nAllow: /page\n");
+        assert!(robots.is_allowed("bot", "/page"));
+
+        let robots = RobotsTxt::parse("User-agent: *\nDisallow:\n");
+        assert!(robots.is_allowed("bot", "/anything"));
+
+        assert!(!RobotsTxt::disallow_all().is_allowed("bot", "/"));
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_robots_disallows_briefly() {
+        let client = reqwest::Client::builder().timeout(Duration::from_millis(200)).build().unwrap();
+        let checker = RobotsChecker::new(client, "bot", RobotsMode::Enforce);
+        // Nothing listens on port 9 of the loopback interface
+        let url = Url::parse("http://127.0.0.1:9/page").unwrap();
+        assert!(!checker.is_allowed(&url).await);
+        assert!(checker.robots_for(&url).await.unavailable);
+        assert!(!RobotsTxt::disallow_all().unavailable);
+    }
+
+    #[tokio::test]
+    async fn test_endless_robots_is_read_up_to_the_cap() {
+        use axum::{body::Body, routing::get, Router};
+
+        // Rules followed by a body that never ends
+        let app = Router::new().route("/robots.txt", get(|| async {
+            let rules = futures::stream::once(async { Ok::<_, std::io::Error>("User-agent: *\nDisallow: /private\n".to_string()) });
+            let padding = futures::stream::repeat_with(|| Ok("# padding\n".repeat(1000)));
+            Body::from_stream(futures::StreamExt::chain(rules, padding))
+        }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let checker = RobotsChecker::new(reqwest::Client::new(), "bot", RobotsMode::Enforce);
+        let url = Url::parse(&format!("http://{}/private/page", addr)).unwrap();
+        let started = std::time::Instant::now();
+        assert!(!checker.is_allowed(&url).await);
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_crawl_delay_and_sitemaps() {
+        let robots = RobotsTxt::parse(
+            "Sitemap: https://example.com/sitemap.xml\nUser-agent: *\nCrawl-delay: 2.5\nDisallow: /tmp\n",
+        );
+        assert_eq!(robots.crawl_delay("bot"), Some(Duration::from_millis(2500)));
+        assert_eq!(robots.sitemaps(), &["https://example.com/sitemap.xml".to_string()]);
+    }
+}
@@ -1,3 +1,9 @@
+use crate::markdown;
+use crate::structured_data;
+use crate::robots::{RobotsChecker, RobotsDisallowed, RobotsMode};
+use crate::error::FetchError;
+use crate::politeness::HostScheduler;
+use crate::ssrf::SsrfPolicy;
 use crate::types::*;
 use anyhow::{anyhow, Result};
 use chrono::Utc;
@@ -8,6 +14,7 @@ use reqwest::Client;
 use scraper::{Html, Selector};
 use select::{document::Document as SelectDoc, predicate::{Name as SelName, Attr as SelAttr, Predicate}};
 use std::collections::HashSet;
+use std::sync::Arc;
 use tracing::{info, warn};
 use url::Url;
 use whatlang::{detect, Lang};
@@ -22,20 +29,92 @@ const USER_AGENTS: &[&str] = &[
     "Mozilla/5.0 (X11; Ubuntu; Linux x86_64; rv:89.0) Gecko/20100101 Firefox/89.0",
 ];
 
+/// Redirects followed by the scraper itself (see `ssrf::redirect_policy`)
+const MAX_REDIRECTS: usize = 10;
+
+/// Result of a (possibly conditional) scrape
+#[derive(Debug)]
+pub enum ScrapeOutcome {
+    /// Newly fetched and extracted page, with validators for future revalidation
+    Fresh(Box<ScrapeResponse>, Validators),
+    /// Server answered 304; the cached response is still current
+    NotModified(Validators),
+}
+
 /// Enhanced Rust-native web scraper
 pub struct RustScraper {
     client: Client,
+    page_client: Option<Client>,
+    robots: Option<Arc<RobotsChecker>>,
+    outbound: Option<Arc<HostScheduler>>,
+    ssrf: Option<Arc<SsrfPolicy>>,
+    max_download_bytes: usize,
 }
 
 impl RustScraper {
+    /// Standalone scraper with its own client. Servers should prefer `with_client`
+    /// so connections are pooled across scrapes.
     pub fn new() -> Self {
-        let client = Client::builder()
+        let mut builder = Client::builder()
             .timeout(std::time::Duration::from_secs(30))
-            .redirect(reqwest::redirect::Policy::limited(10))
-            .build()
-            .expect("Failed to create HTTP client");
+            .redirect(reqwest::redirect::Policy::limited(10));
+        match crate::proxy::ProxyConfig::from_env() {
+            Ok(Some(proxy)) => builder = proxy.apply(builder),
+            Ok(None) => {}
+            Err(e) => warn!("Ignoring invalid proxy configuration: {:#}", e),
+        }
+        let client = builder.build().expect("Failed to create HTTP client");
+
+        Self::with_client(client)
+    }
 
-        Self { client }
+    /// Build on a shared client (see `build_http_client`)
+    pub fn with_client(client: Client) -> Self {
+        Self {
+            client,
+            page_client: None,
+            robots: None,
+            outbound: None,
+            ssrf: None,
+            max_download_bytes: crate::body::max_download_bytes(),
+        }
+    }
+
+    /// Underlying HTTP client (shared with the fallback scraper)
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// Consult robots.txt through the given checker before every scrape
+    pub fn with_robots(mut self, robots: Arc<RobotsChecker>) -> Self {
+        self.robots = Some(robots);
+        self
+    }
+
+    /// Client for page requests that doesn't follow redirects (see `build_page_client`), so
+    /// every hop is checked against the SSRF policy and robots.txt before it is requested.
+    /// Without one, page requests use the shared client and its redirect policy.
+    pub fn with_page_client(mut self, client: Client) -> Self {
+        self.page_client = Some(client);
+        self
+    }
+
+    /// Space redirect hops by the target host's delay (and robots.txt Crawl-delay)
+    pub fn with_outbound(mut self, outbound: Arc<HostScheduler>) -> Self {
+        self.outbound = Some(outbound);
+        self
+    }
+
+    /// Check redirects the client hands back unfollowed against this policy before following them
+    pub fn with_ssrf(mut self, policy: Arc<SsrfPolicy>) -> Self {
+        self.ssrf = Some(policy);
+        self
+    }
+
+    /// Cap on downloaded body size; longer bodies are cut off with a `size_limit_exceeded` warning
+    pub fn with_max_download_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_download_bytes = max_bytes.max(1);
+        self
     }
 
     /// Get a random User-Agent string
@@ -45,35 +124,141 @@ impl RustScraper {
         USER_AGENTS[index]
     }
 
+    /// Refuse `url` when robots.txt disallows it (enforce mode) or flag it with a
+    /// `disallowed_by_robots` warning (warn mode)
+    async fn check_robots(&self, url: &Url, warnings: &mut Vec<String>) -> Result<()> {
+        let Some(robots) = &self.robots else {
+            return Ok(());
+        };
+        if robots.is_allowed(url).await {
+            return Ok(());
+        }
+        if robots.mode() == RobotsMode::Enforce {
+            return Err(RobotsDisallowed {
+                url: url.to_string(),
+                user_agent: robots.user_agent().to_string(),
+            }
+            .into());
+        }
+        if !warnings.iter().any(|w| w == "disallowed_by_robots") {
+            warnings.push("disallowed_by_robots".to_string());
+        }
+        Ok(())
+    }
+
     /// Scrape a URL with enhanced content extraction
     pub async fn scrape_url(&self, url: &str) -> Result<ScrapeResponse> {
+        match self.scrape_url_conditional(url, None).await? {
+            ScrapeOutcome::Fresh(response, _) => Ok(*response),
+            ScrapeOutcome::NotModified(_) => Err(anyhow!("Unexpected 304 Not Modified for unconditional request")),
+        }
+    }
+
+    /// Scrape a URL, sending `If-None-Match`/`If-Modified-Since` when validators are given.
+    /// Returns `NotModified` on a 304 so the caller can reuse its cached response.
+    pub async fn scrape_url_conditional(&self, url: &str, validators: Option<&Validators>) -> Result<ScrapeOutcome> {
         info!("Scraping URL with Rust-native scraper: {}", url);
 
         // Validate URL
-        let parsed_url = Url::parse(url)
-            .map_err(|e| anyhow!("Invalid URL '{}': {}", url, e))?;
+        let parsed_url = Url::parse(url).map_err(|e| FetchError::invalid_url(url, e))?;
 
         if parsed_url.scheme() != "http" && parsed_url.scheme() != "https" {
-            return Err(anyhow!("URL must use HTTP or HTTPS protocol"));
-        }
-
-        // Make HTTP request with random User-Agent
-        let user_agent = self.get_random_user_agent();
-        let response = self
-            .client
-            .get(url)
-            .header("User-Agent", user_agent)
-            .header("Accept", "text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8")
-            .header("Accept-Language", "en-US,en;q=0.5")
-            // Rely on reqwest automatic decompression; remove manual Accept-Encoding to avoid serving compressed body as text
-            .header("DNT", "1")
-            .header("Connection", "keep-alive")
-            .header("Upgrade-Insecure-Requests", "1")
-            .send()
-            .await
-            .map_err(|e| anyhow!("Failed to fetch URL: {}", e))?;
+            return Err(FetchError::invalid_url(url, "URL must use HTTP or HTTPS protocol").into());
+        }
+
+        let mut warnings = Vec::new();
+
+        // robots.txt compliance
+        self.check_robots(&parsed_url, &mut warnings).await?;
+
+        // Make HTTP request with random User-Agent, naming the robots.txt product token while
+        // its rules are applied so site operators can address them to us
+        let user_agent = match &self.robots {
+            Some(robots) if robots.mode() != RobotsMode::Off => {
+                format!("{} (compatible; {})", self.get_random_user_agent(), robots.user_agent())
+            }
+            _ => self.get_random_user_agent().to_string(),
+        };
+        let client = self.page_client.as_ref().unwrap_or(&self.client);
+        let mut target = parsed_url.clone();
+        let mut redirects = 0;
+        let response = loop {
+            let mut request = client.get(target.as_str());
+            if let Some(v) = validators {
+                if let Some(etag) = &v.etag {
+                    request = request.header("If-None-Match", etag);
+                }
+                if let Some(last_modified) = &v.last_modified {
+                    request = request.header("If-Modified-Since", last_modified);
+                }
+            }
+            let response = request
+                .header("User-Agent", &user_agent)
+                .header("Accept", "text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8")
+                .header("Accept-Language", "en-US,en;q=0.5")
+                // Rely on reqwest automatic decompression; remove manual Accept-Encoding to avoid serving compressed body as text
+                .header("DNT", "1")
+                .header("Upgrade-Insecure-Requests", "1")
+                .send()
+                .await
+                .map_err(|e| FetchError::from_reqwest(url, e))?;
+
+            // The page client hands back every redirect (the shared client only those going
+            // through a proxy); check the target against the SSRF policy and robots.txt and
+            // wait for its host's turn before following it
+            let location = response
+                .headers()
+                .get("location")
+                .and_then(|v| v.to_str().ok())
+                .filter(|_| matches!(response.status().as_u16(), 301 | 302 | 303 | 307 | 308));
+            let Some(location) = location else {
+                break response;
+            };
+            let next = target.join(location).map_err(|e| FetchError::invalid_url(location, e))?;
+            redirects += 1;
+            if redirects > MAX_REDIRECTS {
+                return Err(FetchError::UpstreamUnavailable { target: url.to_string(), reason: "too many redirects".to_string() }.into());
+            }
+            if let Some(policy) = &self.ssrf {
+                policy.check_url(&next).await?;
+            }
+            self.check_robots(&next, &mut warnings).await?;
+            if let Some(outbound) = &self.outbound {
+                let crawl_delay = match &self.robots {
+                    Some(robots) => robots.crawl_delay(&next).await,
+                    None => None,
+                };
+                outbound.pace(next.host_str().unwrap_or_default(), crawl_delay).await;
+            }
+            target = next;
+        };
+
+        let header_str = |name: &str| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(|s| s.to_string())
+        };
+        let response_validators = Validators {
+            etag: header_str("etag"),
+            last_modified: header_str("last-modified"),
+        };
+
+        if response.status() == reqwest::StatusCode::NOT_MODIFIED {
+            info!("Not modified since last fetch: {}", url);
+            // A 304 may omit validators; keep the ones we sent
+            let previous = validators.cloned().unwrap_or_default();
+            return Ok(ScrapeOutcome::NotModified(Validators {
+                etag: response_validators.etag.or(previous.etag),
+                last_modified: response_validators.last_modified.or(previous.last_modified),
+            }));
+        }
 
         let status_code = response.status().as_u16();
+        if status_code >= 400 {
+            return Err(FetchError::from_status(url, status_code).into());
+        }
         let content_type = response
             .headers()
             .get("content-type")
@@ -81,28 +266,67 @@ impl RustScraper {
             .unwrap_or("text/html")
             .to_string();
 
-        // Get response body
-        let html = response
-            .text()
-            .await
-            .map_err(|e| anyhow!("Failed to read response body: {}", e))?;
+        // Stream the body up to the download limit; binary types are refused without downloading
+        let body = crate::body::read_limited(response, url, &content_type, self.max_download_bytes).await?;
+        if body.truncated {
+            warnings.push("size_limit_exceeded".to_string());
+        }
+
+        // PDFs get their own pipeline instead of being parsed as HTML
+        if crate::pdf::is_pdf(&content_type, &body.bytes) {
+            if body.truncated {
+                return Err(crate::body::BodyTooLarge { url: url.to_string(), limit_bytes: self.max_download_bytes }.into());
+            }
+            let result = self
+                .build_pdf_response(url, &parsed_url, body.bytes, status_code, content_type, warnings)
+                .await?;
+            return Ok(ScrapeOutcome::Fresh(Box::new(result), response_validators));
+        }
+
+        // Sniff the charset (BOM, header, <meta>, statistical) and transcode to UTF-8 before parsing
+        let (html, charset) = crate::charset::decode(&body.bytes, &content_type, parsed_url.host_str());
+        let encoding = Some(charset.encoding.name().to_string());
+
+        // RSS/Atom/JSON Feed documents become a list of entries instead of being parsed as HTML
+        if crate::feed::is_feed(&content_type, &html) {
+            match crate::feed::parse(&html, &parsed_url) {
+                Ok(feed) => {
+                    let mut result = self.build_feed_response(url, &parsed_url, html, feed, status_code, content_type);
+                    result.warnings = warnings;
+                    result.encoding = encoding;
+                    return Ok(ScrapeOutcome::Fresh(Box::new(result), response_validators));
+                }
+                Err(e) => {
+                    warn!("{} looks like a feed but couldn't be parsed: {}", url, e);
+                    warnings.push("feed_parse_failed".to_string());
+                }
+            }
+        }
 
         // Parse HTML
     let document = Html::parse_document(&html);
         
         // Extract basic metadata
-    let title = self.extract_title(&document);
+    let mut title = self.extract_title(&document);
     let meta_description = self.extract_meta_description(&document);
     let meta_keywords = self.extract_meta_keywords(&document);
         let language = self.detect_language(&document, &html);
     let canonical_url = self.extract_canonical(&document, &parsed_url);
-    let site_name = self.extract_site_name(&document);
     let (og_title, og_description, og_image) = self.extract_open_graph(&document, &parsed_url);
-    let author = self.extract_author(&document);
-    let published_at = self.extract_published_time(&document);
+
+        // schema.org data (JSON-LD, Microdata, RDFa) fills whatever the meta tags leave out
+        let structured_data = structured_data::extract(&document, &parsed_url);
+        let main_entity = structured_data.as_ref().map(structured_data::main_entity).unwrap_or_default();
+        if let (true, Some(headline)) = (title == "No Title", main_entity.headline) {
+            title = headline;
+        }
+    let site_name = self.extract_site_name(&document).or(main_entity.publisher);
+    let author = self.extract_author(&document).or(main_entity.author);
+    let published_at = self.extract_published_time(&document).or(main_entity.date_published);
+    let modified_at = self.extract_modified_time(&document).or(main_entity.date_modified);
 
         // Extract code blocks BEFORE html2text conversion (Priority 1 fix)
-        let code_blocks = self.extract_code_blocks(&document);
+        let mut code_blocks = self.extract_code_blocks(&document);
 
         // Extract readable content using readability
         let clean_content = self.extract_clean_content(&html, &parsed_url);
@@ -110,10 +334,13 @@ impl RustScraper {
     let reading_time_minutes = Some(((word_count as f64 / 200.0).ceil() as u32).max(1));
 
         // Extract structured data
-        let headings = self.extract_headings(&document);
+        let mut headings = self.extract_headings(&document);
         // Smart link extraction: prefer content links over all document links
         let links = self.extract_content_links(&document, &parsed_url);
         let images = self.extract_images(&document, &parsed_url);
+        let tables = self.extract_tables(&document);
+        let feeds = crate::feed::discover(&document, &parsed_url);
+        self.locate_spans(&clean_content, &mut headings, &mut code_blocks);
 
         // Calculate extraction quality score (Priority 1 fix)
         let extraction_score = self.calculate_extraction_score(
@@ -126,9 +353,6 @@ impl RustScraper {
         // Extract domain from URL (Priority 2 enhancement)
         let domain = parsed_url.host_str().map(|h| h.to_string());
 
-        // Initialize warnings
-        let warnings = Vec::new();
-        
         let result = ScrapeResponse {
             url: url.to_string(),
             title,
@@ -148,6 +372,7 @@ impl RustScraper {
             site_name,
             author,
             published_at,
+            modified_at,
             og_title,
             og_description,
             og_image,
@@ -160,10 +385,153 @@ impl RustScraper {
             extraction_score: Some(extraction_score),
             warnings,
             domain,
+            encoding,
+            page_count: None,
+            pages: Vec::new(),
+            structured_data,
+            tables,
+            feed: None,
+            feeds,
         };
 
         info!("Successfully scraped: {} ({} words, score: {:.2})", result.title, result.word_count, extraction_score);
-        Ok(result)
+        Ok(ScrapeOutcome::Fresh(Box::new(result), response_validators))
+    }
+
+    /// Build a response from a parsed feed: entries rendered as `clean_content`, entry links as `links`
+    fn build_feed_response(
+        &self,
+        url: &str,
+        parsed_url: &Url,
+        body: String,
+        feed: Feed,
+        status_code: u16,
+        content_type: String,
+    ) -> ScrapeResponse {
+        let (clean_content, headings) = crate::feed::render_entries(&feed);
+        let word_count = self.count_words(&clean_content);
+        let links = feed
+            .entries
+            .iter()
+            .filter_map(|e| Some(Link { url: e.link.clone()?, text: e.title.clone() }))
+            .collect();
+        let published_at = feed.updated.clone().or_else(|| feed.entries.iter().find_map(|e| e.published.clone()));
+        let extraction_score = self.calculate_extraction_score(word_count, &published_at, &[], &headings);
+        let title = if feed.title.is_empty() { "Untitled Feed".to_string() } else { feed.title.clone() };
+
+        info!("Parsed {} feed: {} ({} entries)", feed.format, title, feed.entries.len());
+        ScrapeResponse {
+            url: url.to_string(),
+            title,
+            content: body,
+            language: self.detect_text_language(&clean_content),
+            clean_content,
+            meta_description: feed.description.clone().unwrap_or_default(),
+            meta_keywords: String::new(),
+            headings,
+            links,
+            images: Vec::new(),
+            timestamp: Utc::now().to_rfc3339(),
+            status_code,
+            content_type,
+            word_count,
+            canonical_url: feed.link.clone(),
+            site_name: (!feed.title.is_empty()).then(|| feed.title.clone()),
+            author: None,
+            published_at,
+            modified_at: None,
+            og_title: None,
+            og_description: None,
+            og_image: None,
+            reading_time_minutes: Some(((word_count as f64 / 200.0).ceil() as u32).max(1)),
+            code_blocks: Vec::new(),
+            truncated: false,
+            actual_chars: 0,
+            max_chars_limit: None,
+            extraction_score: Some(extraction_score),
+            warnings: Vec::new(),
+            domain: parsed_url.host_str().map(|h| h.to_string()),
+            encoding: None,
+            page_count: None,
+            pages: Vec::new(),
+            structured_data: None,
+            tables: Vec::new(),
+            feed: Some(feed),
+            feeds: Vec::new(),
+        }
+    }
+
+    /// Build a response from a PDF body: per-page text, Info metadata and page boundaries
+    async fn build_pdf_response(
+        &self,
+        url: &str,
+        parsed_url: &Url,
+        body: Vec<u8>,
+        status_code: u16,
+        content_type: String,
+        mut warnings: Vec<String>,
+    ) -> Result<ScrapeResponse> {
+        // Text extraction is CPU-bound and may panic on malformed files
+        let pdf = tokio::task::spawn_blocking(move || crate::pdf::extract(&body))
+            .await
+            .map_err(|e| anyhow!("PDF extraction failed: {}", e))??;
+        let (clean_content, pages) = pdf.joined_text();
+        let word_count = self.count_words(&clean_content);
+        if word_count == 0 {
+            // Scanned documents have no text layer
+            warnings.push("pdf_no_text".to_string());
+        }
+
+        let title = pdf.title.clone().unwrap_or_else(|| {
+            parsed_url
+                .path_segments()
+                .and_then(|mut s| s.next_back())
+                .filter(|s| !s.is_empty())
+                .map(|s| percent_encoding::percent_decode_str(s).decode_utf8_lossy().to_string())
+                .unwrap_or_else(|| "No Title".to_string())
+        });
+        let extraction_score = self.calculate_extraction_score(word_count, &pdf.created_at, &[], &[]);
+
+        info!("Extracted PDF: {} ({} pages, {} words)", title, pdf.page_count, word_count);
+        Ok(ScrapeResponse {
+            url: url.to_string(),
+            title,
+            content: clean_content.clone(),
+            language: self.detect_text_language(&clean_content),
+            clean_content,
+            meta_description: pdf.subject.unwrap_or_default(),
+            meta_keywords: pdf.keywords.unwrap_or_default(),
+            headings: Vec::new(),
+            links: Vec::new(),
+            images: Vec::new(),
+            timestamp: Utc::now().to_rfc3339(),
+            status_code,
+            content_type,
+            word_count,
+            canonical_url: None,
+            site_name: None,
+            author: pdf.author,
+            published_at: pdf.created_at,
+            modified_at: None,
+            og_title: None,
+            og_description: None,
+            og_image: None,
+            reading_time_minutes: Some(((word_count as f64 / 200.0).ceil() as u32).max(1)),
+            code_blocks: Vec::new(),
+            truncated: false,
+            actual_chars: 0,
+            max_chars_limit: None,
+            extraction_score: Some(extraction_score),
+            warnings,
+            domain: parsed_url.host_str().map(|h| h.to_string()),
+            encoding: None,
+            page_count: Some(pdf.page_count),
+            pages,
+            structured_data: None,
+            tables: Vec::new(),
+            feed: None,
+            feeds: Vec::new(),
+        })
     }
 
     /// Extract page title with fallback to h1
@@ -281,6 +649,16 @@ impl RustScraper {
         None
     }
 
+    /// Extract last-modified time
+    fn extract_modified_time(&self, document: &Html) -> Option<String> {
+        if let Ok(sel) = Selector::parse("meta[property=\"article:modified_time\"]") {
+            if let Some(el) = document.select(&sel).next() {
+                if let Some(content) = el.value().attr("content") { return Some(content.trim().to_string()); }
+            }
+        }
+        None
+    }
+
     /// Detect language from HTML attributes and content
     fn detect_language(&self, document: &Html, html: &str) -> String {
         // Try HTML lang attribute
@@ -302,7 +680,12 @@ impl RustScraper {
         }
 
         // Use whatlang for content-based detection
-        if let Some(info) = detect(html) {
+        self.detect_text_language(html)
+    }
+
+    /// Content-based language detection (ISO 639-1 where common)
+    fn detect_text_language(&self, text: &str) -> String {
+        if let Some(info) = detect(text) {
             match info.lang() {
                 Lang::Eng => "en".to_string(),
                 Lang::Spa => "es".to_string(),
@@ -374,6 +757,50 @@ impl RustScraper {
         final_text
     }
 
+    /// Render the main content as CommonMark. The content root is chosen the same way as
+    /// for `clean_content`: mdBook-style containers, then the better of readability and the
+    /// heuristic selectors, then the whole body.
+    pub fn extract_markdown(&self, html: &str, base_url: &Url) -> String {
+        let pre = self.preprocess_html(html);
+        let fragment = self.main_content_html(&pre, base_url);
+        markdown::html_to_markdown(&fragment, base_url).trim().to_string()
+    }
+
+    /// HTML of the main-content element of a preprocessed document
+    fn main_content_html(&self, pre: &str, base_url: &Url) -> String {
+        let document = Html::parse_document(pre);
+        for sel_str in ["div#content", "main", "article"] {
+            if let Some(el) = Selector::parse(sel_str).ok().and_then(|sel| document.select(&sel).next()) {
+                if self.count_words(&el.text().collect::<Vec<_>>().join(" ")) > 50 {
+                    return el.html();
+                }
+            }
+        }
+
+        let readability = extractor::extract(&mut pre.as_bytes(), base_url)
+            .map(|product| {
+                let words = self.count_words(&html2text::from_read(product.content.as_bytes(), 80));
+                (product.content, words)
+            })
+            .unwrap_or_default();
+        let heuristic = self
+            .heuristic_main_element(&document)
+            .map(|(el, text)| (el.html(), self.count_words(&text)))
+            .unwrap_or_default();
+
+        if heuristic.1 > readability.1.saturating_add(20) || (readability.1 == 0 && heuristic.1 > 0) {
+            heuristic.0
+        } else if readability.1 > 0 {
+            readability.0
+        } else {
+            Selector::parse("body")
+                .ok()
+                .and_then(|sel| document.select(&sel).next())
+                .map(|body| body.html())
+                .unwrap_or_else(|| pre.to_string())
+        }
+    }
+
     /// Extract content from mdBook-like structures (#content, main, article) using select crate
     fn extract_mdbook_like(&self, html: &str) -> Option<String> {
         let doc = SelectDoc::from(html);
@@ -552,7 +979,13 @@ impl RustScraper {
     /// Heuristic extraction from common main/article containers; returns cleaned text
     fn heuristic_main_extraction(&self, html: &str) -> String {
         let document = Html::parse_document(html);
+        self.heuristic_main_element(&document)
+            .map(|(_, text)| text)
+            .unwrap_or_default()
+    }
 
+    /// Best main/article container by word count, with its cleaned text
+    fn heuristic_main_element<'a>(&self, document: &'a Html) -> Option<(scraper::ElementRef<'a>, String)> {
         // Candidate selectors in priority order
         let selectors = [
             "article",
@@ -569,7 +1002,7 @@ impl RustScraper {
             ".article",
         ];
 
-        let mut best_text = String::new();
+        let mut best = None;
         let mut best_words = 0usize;
 
         for sel_str in selectors.iter() {
@@ -581,13 +1014,13 @@ impl RustScraper {
                     let wc = self.count_words(&text);
                     if wc > best_words {
                         best_words = wc;
-                        best_text = text;
+                        best = Some((el, text));
                     }
                 }
             }
         }
 
-        best_text
+        best
     }
 
     /// Count words in text
@@ -599,24 +1032,17 @@ impl RustScraper {
     fn extract_headings(&self, document: &Html) -> Vec<Heading> {
         let mut headings = Vec::new();
         
-        for level in 1..=6 {
-            let sel: &str = match level {
-                1 => "h1",
-                2 => "h2",
-                3 => "h3",
-                4 => "h4",
-                5 => "h5",
-                _ => "h6",
-            };
-            if let Ok(selector) = Selector::parse(sel) {
-                for element in document.select(&selector) {
-                    let text = element.text().collect::<String>().trim().to_string();
-                    if !text.is_empty() {
-                        headings.push(Heading {
-                            level: sel.to_string(),
-                            text,
-                        });
-                    }
+        // Document order, so headings line up with the content they introduce
+        if let Ok(selector) = Selector::parse("h1, h2, h3, h4, h5, h6") {
+            for element in document.select(&selector) {
+                let text = element.text().collect::<String>().trim().to_string();
+                if !text.is_empty() {
+                    headings.push(Heading {
+                        level: element.value().name().to_string(),
+                        text,
+                        start_char: None,
+                        end_char: None,
+                    });
                 }
             }
         }
@@ -624,6 +1050,30 @@ impl RustScraper {
         headings
     }
 
+    /// Fill in where each heading and code block appears in `clean_content`.
+    /// Matching ignores whitespace (extraction reflows text); headings must be a whole line
+    /// or follow a `#` marker, code blocks must start and end on word boundaries. Items are
+    /// searched in document order so repeated headings map to successive occurrences; items
+    /// not found after the previous match (e.g. navigation headings outside the main
+    /// content) keep `None`.
+    fn locate_spans(&self, clean_content: &str, headings: &mut [Heading], code_blocks: &mut [CodeBlock]) {
+        let locator = SpanLocator::new(clean_content);
+        let mut cursor = 0;
+        for heading in headings.iter_mut() {
+            if let Some((start, end)) = locator.find_heading(&heading.text, cursor) {
+                (heading.start_char, heading.end_char) = (Some(start), Some(end));
+                cursor = cursor.max(end);
+            }
+        }
+        let mut cursor = 0;
+        for block in code_blocks.iter_mut() {
+            if let Some((start, end)) = locator.find_code(&block.code, cursor) {
+                (block.start_char, block.end_char) = (Some(start), Some(end));
+                cursor = cursor.max(end);
+            }
+        }
+    }
+
     /// Extract links with absolute URLs (all document links)
     fn extract_links(&self, document: &Html, base_url: &Url) -> Vec<Link> {
         self.extract_links_from_selector(document, base_url, "a[href]")
@@ -729,6 +1179,27 @@ impl RustScraper {
         images
     }
 
+    /// Extract data tables (see `Table::from_html`). Layout tables (nested tables,
+    /// role=presentation, a single column) and header-only tables are skipped.
+    fn extract_tables(&self, document: &Html) -> Vec<Table> {
+        const MAX_TABLES: usize = 50;
+
+        let Ok(table_sel) = Selector::parse("table") else {
+            return Vec::new();
+        };
+        let cell_text = |el: scraper::ElementRef| el.text().collect::<Vec<_>>().join(" ").split_whitespace().collect::<Vec<_>>().join(" ");
+        document
+            .select(&table_sel)
+            .filter(|table| {
+                !matches!(table.value().attr("role"), Some("presentation" | "none"))
+                    && table.select(&table_sel).next().is_none()
+            })
+            .map(|table| Table::from_html(table, cell_text))
+            .filter(|table| !table.rows.is_empty() && table.columns() >= 2)
+            .take(MAX_TABLES)
+            .collect()
+    }
+
     /// Extract code blocks with language hints (Priority 1 fix)
     fn extract_code_blocks(&self, document: &Html) -> Vec<CodeBlock> {
         let mut code_blocks = Vec::new();
@@ -765,7 +1236,7 @@ impl RustScraper {
                 code_blocks.push(CodeBlock {
                     language,
                     code,
-                    start_char: None,  // Could be enhanced with position tracking
+                    start_char: None,
                     end_char: None,
                 });
             }
@@ -839,6 +1310,87 @@ impl Default for RustScraper {
     }
 }
 
+/// Whitespace-insensitive substring search that reports char offsets in the original text
+struct SpanLocator {
+    /// The original text
+    chars: Vec<char>,
+    /// The text with all whitespace removed
+    compact: String,
+    /// For each char of `compact`: (byte offset in `compact`, char offset in the original)
+    positions: Vec<(usize, usize)>,
+}
+
+impl SpanLocator {
+    fn new(text: &str) -> Self {
+        let chars: Vec<char> = text.chars().collect();
+        let mut compact = String::with_capacity(text.len());
+        let mut positions = Vec::new();
+        for (char_idx, &c) in chars.iter().enumerate() {
+            if !c.is_whitespace() {
+                positions.push((compact.len(), char_idx));
+                compact.push(c);
+            }
+        }
+        Self { chars, compact, positions }
+    }
+
+    /// A heading: a whole line, or text right after a Markdown `#` marker (`clean_content`
+    /// flattens lines, but html2text's heading markers survive) that ends at a word boundary
+    fn find_heading(&self, needle: &str, from: usize) -> Option<(usize, usize)> {
+        self.find(needle, from, |start, end| {
+            (self.line_start(start) && self.line_end(end)) || (self.after_heading_marker(start) && self.word_end(end))
+        })
+    }
+
+    /// A code block, starting and ending on word boundaries
+    fn find_code(&self, needle: &str, from: usize) -> Option<(usize, usize)> {
+        self.find(needle, from, |start, end| self.word_start(start) && self.word_end(end))
+    }
+
+    /// Span of the first match starting at or after char offset `from` that `accept`s
+    fn find(&self, needle: &str, from: usize, accept: impl Fn(usize, usize) -> bool) -> Option<(usize, usize)> {
+        let needle: String = needle.chars().filter(|c| !c.is_whitespace()).collect();
+        let first_char = needle.chars().next()?;
+        let from_idx = self.positions.partition_point(|&(_, orig)| orig < from);
+        let mut search = self.positions.get(from_idx).map(|&(b, _)| b).unwrap_or(self.compact.len());
+        while let Some(found) = self.compact[search..].find(&needle) {
+            let byte = search + found;
+            let first = self.positions.partition_point(|&(b, _)| b < byte);
+            let last = first + needle.chars().count() - 1;
+            let (start, end) = (self.positions[first].1, self.positions[last].1 + 1);
+            if accept(start, end) {
+                return Some((start, end));
+            }
+            search = byte + first_char.len_utf8();
+        }
+        None
+    }
+
+    fn line_start(&self, start: usize) -> bool {
+        self.chars[..start].iter().rev().take_while(|&&c| c != '\n').all(|c| c.is_whitespace())
+    }
+
+    fn line_end(&self, end: usize) -> bool {
+        self.chars[end..].iter().take_while(|&&c| c != '\n').all(|c| c.is_whitespace())
+    }
+
+    fn word_start(&self, start: usize) -> bool {
+        start == 0 || self.chars[start - 1].is_whitespace()
+    }
+
+    fn word_end(&self, end: usize) -> bool {
+        self.chars.get(end).is_none_or(|c| c.is_whitespace())
+    }
+
+    /// `start` follows `#`s (themselves at a word start) and whitespace
+    fn after_heading_marker(&self, start: usize) -> bool {
+        let before = &self.chars[..start];
+        let spaces = before.iter().rev().take_while(|c| c.is_whitespace()).count();
+        let hashes = before[..start - spaces].iter().rev().take_while(|&&c| c == '#').count();
+        spaces > 0 && hashes > 0 && self.word_start(start - spaces - hashes)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -869,6 +1421,119 @@ mod tests {
         assert_eq!(cleaned, "This is some text");
     }
     
+    #[test]
+    fn test_extract_tables() {
+        let scraper = RustScraper::new();
+        let document = Html::parse_document(
+            r#"<table><caption>Support matrix</caption>
+                 <thead><tr><th rowspan="2">Platform</th><th colspan="2">Version</th></tr>
+                        <tr><th>1.x</th><th>2.x</th></tr></thead>
+                 <tbody><tr><td rowspan="2">Linux</td><td>yes</td><td>yes</td></tr>
+                        <tr><td>no</td><td>yes, "beta"</td></tr>
+                        <tr><td>macOS</td><td colspan="2">planned</td></tr></tbody>
+               </table>
+               <table role="presentation"><tr><td>layout</td><td>only</td></tr></table>"#,
+        );
+        let tables = scraper.extract_tables(&document);
+        assert_eq!(tables.len(), 1);
+        let table = &tables[0];
+        assert_eq!(table.caption.as_deref(), Some("Support matrix"));
+        assert_eq!(table.headers, vec!["Platform", "Version / 1.x", "Version / 2.x"]);
+        assert_eq!(table.rows, vec![
+            vec!["Linux", "yes", "yes"],
+            vec!["Linux", "no", "yes, \"beta\""],
+            vec!["macOS", "planned", "planned"],
+        ]);
+        assert_eq!(
+            table.to_csv().lines().nth(2),
+            Some(r#"Linux,no,"yes, ""beta""""#)
+        );
+        assert!(table.to_markdown().starts_with("| Platform | Version / 1.x | Version / 2.x |\n| --- | --- | --- |\n| Linux |"));
+    }
+
+    #[tokio::test]
+    async fn test_redirect_hops_are_checked_against_robots() {
+        use axum::{http::HeaderMap, response::{Html as HtmlBody, Redirect}, routing::get, Router};
+
+        let page = |body: String| HtmlBody(format!(
+            "<html><head><title>Page</title></head><body><article><p>{}</p></article></body></html>",
+            format!("{} with enough words to count as the main content of the page. ", body).repeat(5)
+        ));
+        let app = Router::new()
+            .route("/robots.txt", get(|| async { "User-agent: search-scrape\nDisallow: /private\n" }))
+            .route("/start", get(|| async { Redirect::temporary("/private/page") }))
+            .route("/private/page", get(move || async move { page("Private page".to_string()) }))
+            .route("/agent", get(move |headers: HeaderMap| async move {
+                page(headers["user-agent"].to_str().unwrap().to_string())
+            }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let scraper = |mode| {
+            let page_client = crate::build_page_client(Arc::new(SsrfPolicy::disabled())).unwrap();
+            RustScraper::with_client(reqwest::Client::new())
+                .with_page_client(page_client)
+                .with_robots(Arc::new(RobotsChecker::new(reqwest::Client::new(), "search-scrape", mode)))
+        };
+        let start = format!("http://{}/start", addr);
+
+        let err = scraper(RobotsMode::Enforce).scrape_url(&start).await.unwrap_err();
+        let disallowed = err.downcast_ref::<RobotsDisallowed>().expect("redirect target should be refused");
+        assert_eq!(disallowed.url, format!("http://{}/private/page", addr));
+
+        let warned = scraper(RobotsMode::Warn).scrape_url(&start).await.unwrap();
+        assert!(warned.clean_content.contains("Private page"));
+        assert_eq!(warned.warnings, vec!["disallowed_by_robots".to_string()]);
+
+        // The User-Agent names the token the robots.txt rules were matched for
+        let agent = scraper(RobotsMode::Enforce).scrape_url(&format!("http://{}/agent", addr)).await.unwrap();
+        assert!(agent.clean_content.contains("(compatible; search-scrape)"), "{}", agent.clean_content);
+    }
+
+    #[test]
+    fn test_locate_spans() {
+        let scraper = RustScraper::new();
+        let clean = "# Intro\n\nSome text.\n\n## Usage\n\n    let x =\n        1;\n\n## Usage\n\nAgain.";
+        let document = Html::parse_document(
+            "<h2>Usage</h2><h1>Intro</h1><h2>Usage</h2><pre><code class='language-rust'>let x = 1;</code></pre>",
+        );
+        let mut headings = scraper.extract_headings(&document);
+        assert_eq!(headings.iter().map(|h| h.text.as_str()).collect::<Vec<_>>(), vec!["Usage", "Intro", "Usage"]);
+        let mut code_blocks = scraper.extract_code_blocks(&document);
+        scraper.locate_spans(clean, &mut headings, &mut code_blocks);
+
+        let span = |start: Option<usize>, end: Option<usize>| clean.chars().skip(start.unwrap()).take(end.unwrap() - start.unwrap()).collect::<String>();
+        assert_eq!(headings[0].start_char, Some(24));
+        // Out of order in the extracted text: nothing after the previous heading
+        assert_eq!((headings[1].start_char, headings[1].end_char), (None, None));
+        // Repeated headings map to successive occurrences
+        assert_eq!(headings[2].start_char, Some(58));
+        assert_eq!(span(headings[2].start_char, headings[2].end_char), "Usage");
+        assert_eq!(span(code_blocks[0].start_char, code_blocks[0].end_char), "let x =\n        1;");
+    }
+
+    #[test]
+    fn test_locate_spans_matches_whole_lines() {
+        let scraper = RustScraper::new();
+        let clean = "Notes on the API below.\n\n# API\n\nCall it.\n\n# Notes\n\nFirst.\n\n# Notes\n\nSecond.";
+        // "Sidebar" only exists in navigation; the body mentions "API" and "Notes" in running text
+        let document = Html::parse_document("<h3>Sidebar</h3><h1>API</h1><h1>Notes</h1><h1>Notes</h1><h3>API</h3>");
+        let mut headings = scraper.extract_headings(&document);
+        scraper.locate_spans(clean, &mut headings, &mut []);
+
+        let starts: Vec<_> = headings.iter().map(|h| h.start_char).collect();
+        assert_eq!(starts, vec![None, Some(27), Some(44), Some(61), None]);
+        assert_eq!(headings[1].end_char, Some(30));
+
+        // Flattened onto one line, headings are found by their `#` markers
+        let flat = "Notes on the API below. # API Call it. ## Notes First. ## Notes Second. The API is stable.";
+        let mut headings = scraper.extract_headings(&document);
+        scraper.locate_spans(flat, &mut headings, &mut []);
+        let starts: Vec<_> = headings.iter().map(|h| h.start_char).collect();
+        assert_eq!(starts, vec![None, Some(26), Some(42), Some(58), None]);
+    }
+
     #[test]
     fn test_word_count() {
         let scraper = RustScraper::new();
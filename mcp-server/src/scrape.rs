@@ -4,57 +4,160 @@ use anyhow::{anyhow, Result};
 use backoff::future::retry;
 use backoff::ExponentialBackoffBuilder;
 use std::sync::Arc;
-use tracing::info;
+use tracing::{debug, info, warn};
 use select::predicate::Predicate;
-use crate::rust_scraper::RustScraper;
+use crate::error::{backoff_error, FetchError};
+use crate::rust_scraper::ScrapeOutcome;
+use crate::pagination;
+
+/// Default freshness window for cached scrapes (`SCRAPE_CACHE_MAX_AGE_SECS`)
+const DEFAULT_MAX_AGE_SECS: u64 = 60 * 30;
+
+/// Disk cache namespace for scrape results
+const SCRAPE_NAMESPACE: &str = "scrape";
+
+#[derive(Debug, Clone, Default)]
+pub struct ScrapeOptions {
+    /// Serve a cached copy younger than this without revalidating; older copies are
+    /// revalidated with If-None-Match/If-Modified-Since. `Some(0)` always revalidates.
+    pub max_age: Option<std::time::Duration>,
+    /// Bypass the cache entirely and refetch
+    pub force_refresh: bool,
+}
+
+fn default_max_age() -> std::time::Duration {
+    let secs = std::env::var("SCRAPE_CACHE_MAX_AGE_SECS")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(DEFAULT_MAX_AGE_SECS);
+    std::time::Duration::from_secs(secs)
+}
+
+/// Memory cache first, then the disk cache (promoting hits back into memory)
+async fn get_cached(state: &Arc<AppState>, url: &str) -> Option<Arc<CachedScrape>> {
+    if let Some(entry) = state.scrape_cache.get(url).await {
+        return Some(entry);
+    }
+    let entry = Arc::new(state.disk_cache.as_ref()?.get::<CachedScrape>(SCRAPE_NAMESPACE, url).await?);
+    debug!("scrape disk cache hit for {}", url);
+    state.scrape_cache.insert(url.to_string(), Arc::clone(&entry)).await;
+    Some(entry)
+}
+
+/// Cached scrape of `url` (memory, then disk) without fetching anything
+pub async fn cached_scrape(state: &Arc<AppState>, url: &str) -> Option<Arc<CachedScrape>> {
+    get_cached(state, url).await
+}
+
+async fn store_cached(state: &Arc<AppState>, url: &str, entry: CachedScrape) {
+    if let Some(disk) = &state.disk_cache {
+        disk.put(SCRAPE_NAMESPACE, url, &entry).await;
+    }
+    state.scrape_cache.insert(url.to_string(), Arc::new(entry)).await;
+}
+
+async fn invalidate_cached(state: &Arc<AppState>, url: &str) {
+    state.scrape_cache.invalidate(url).await;
+    if let Some(disk) = &state.disk_cache {
+        disk.remove(SCRAPE_NAMESPACE, url).await;
+    }
+}
 
 pub async fn scrape_url(state: &Arc<AppState>, url: &str) -> Result<ScrapeResponse> {
+    scrape_url_with_options(state, url, ScrapeOptions::default()).await
+}
+
+pub async fn scrape_url_with_options(
+    state: &Arc<AppState>,
+    url: &str,
+    options: ScrapeOptions,
+) -> Result<ScrapeResponse> {
     info!("Scraping URL: {}", url);
     
     // Validate URL
     if !url.starts_with("http://") && !url.starts_with("https://") {
-        return Err(anyhow!("Invalid URL: must start with http:// or https://"));
+        return Err(FetchError::invalid_url(url, "must start with http:// or https://").into());
     }
+    let parsed_url = url::Url::parse(url).map_err(|e| FetchError::invalid_url(url, e))?;
+
+    // SSRF guard: refuse loopback/private/link-local/metadata targets before touching cache or network
+    state.ssrf.check_url(&parsed_url).await?;
 
     // Check cache
-    if let Some(cached) = state.scrape_cache.get(url).await {
-        if cached.word_count == 0 || cached.clean_content.trim().is_empty() {
+    let mut cached = None;
+    if options.force_refresh {
+        invalidate_cached(state, url).await;
+    } else if let Some(entry) = get_cached(state, url).await {
+        if entry.response.word_count == 0 || entry.response.clean_content.trim().is_empty() {
             // Invalidate poor/empty cache entries and recompute
-            state.scrape_cache.invalidate(url).await;
+            invalidate_cached(state, url).await;
         } else {
-            return Ok(cached);
+            let max_age = options.max_age.unwrap_or_else(default_max_age);
+            let age = (chrono::Utc::now() - entry.fetched_at).to_std().unwrap_or_default();
+            if age <= max_age {
+                debug!("scrape cache hit for {} (age {}s)", url, age.as_secs());
+                return Ok(entry.response.clone());
+            }
+            if !entry.validators.is_empty() {
+                cached = Some(entry);
+            }
         }
     }
 
-    // Concurrency control
-    let _permit = state.outbound_limit.acquire().await.expect("semaphore closed");
+    // Per-host politeness: concurrency limit + spacing (honors robots.txt Crawl-delay)
+    let host = parsed_url.host_str().unwrap_or_default().to_string();
+    let crawl_delay = state.robots.crawl_delay(&parsed_url).await;
+    let _permit = state.outbound.acquire(&host, crawl_delay).await;
 
     // Only use Rust-native scraper with retries
-    let rust_scraper = RustScraper::new();
+    let rust_scraper = &state.scraper;
     let url_owned = url.to_string();
-    let mut result = retry(
+    let validators = cached.as_ref().map(|c| c.validators.clone());
+    let outcome = retry(
         ExponentialBackoffBuilder::new()
             .with_initial_interval(std::time::Duration::from_millis(200))
             .with_max_interval(std::time::Duration::from_secs(2))
             .with_max_elapsed_time(Some(std::time::Duration::from_secs(6)))
             .build(),
         || async {
-            match rust_scraper.scrape_url(&url_owned).await {
+            match rust_scraper.scrape_url_conditional(&url_owned, validators.as_ref()).await {
                 Ok(r) => Ok(r),
-                Err(e) => {
-                    // Treat network/temporary HTML parse errors as transient
-                    Err(backoff::Error::transient(anyhow!("{}", e)))
-                }
+                // Only timeouts, network failures and 5xx/429 responses may clear up on retry;
+                // a 404, a policy refusal or an unusable body won't
+                Err(e) => Err(backoff_error(e)),
             }
         },
     ).await?;
-    if result.word_count == 0 || result.clean_content.trim().is_empty() {
+
+    let (mut result, validators) = match (outcome, cached) {
+        (ScrapeOutcome::NotModified(validators), Some(entry)) => {
+            // Revalidated: reuse the cached extraction and restart its freshness window
+            store_cached(state, url, CachedScrape {
+                response: entry.response.clone(),
+                validators,
+                fetched_at: chrono::Utc::now(),
+            }).await;
+            return Ok(entry.response.clone());
+        }
+        (ScrapeOutcome::NotModified(_), None) => {
+            return Err(anyhow!("Received 304 Not Modified without a cached copy of {}", url));
+        }
+        (ScrapeOutcome::Fresh(result, validators), _) => (*result, validators),
+    };
+    // The HTML fallback can't do better on a PDF without a text layer
+    if result.page_count.is_none() && (result.word_count == 0 || result.clean_content.trim().is_empty()) {
         info!("Rust-native scraper returned empty content, using fallback for {}", url);
         result = scrape_url_fallback(state, &url_owned).await?;
     } else {
         info!("Rust-native scraper succeeded for {}", url);
     }
-    state.scrape_cache.insert(url.to_string(), result.clone()).await;
+    store_cached(state, url, CachedScrape {
+        response: result.clone(),
+        validators,
+        fetched_at: chrono::Utc::now(),
+    }).await;
+    // Notify `scrape://` resource subscribers; an error only means nobody is listening
+    let _ = state.resource_updates.send(url.to_string());
     
     // Auto-log to history if memory is enabled (Phase 1)
     if let Some(memory) = &state.memory {
@@ -85,20 +188,209 @@ pub async fn scrape_url(state: &Arc<AppState>, url: &str) -> Result<ScrapeRespon
     Ok(result)
 }
 
+/// Scrape `url` and, when it is part of a paginated article or thread, follow `rel="next"`,
+/// pagination navigation and `?page=N` links for up to `max_pages` pages in total.
+/// Pages are merged into one response: `clean_content` is joined with blank lines, heading and
+/// code block offsets are shifted accordingly, links/images are deduplicated, and `pages`
+/// records each page's URL and char range. A single-page result is returned unchanged.
+pub async fn scrape_url_paginated(
+    state: &Arc<AppState>,
+    url: &str,
+    options: ScrapeOptions,
+    max_pages: usize,
+) -> Result<ScrapeResponse> {
+    let max_pages = max_pages.clamp(1, pagination::MAX_PAGES_LIMIT);
+    let mut merged = scrape_url_with_options(state, url, options.clone()).await?;
+    // PDFs are already paged, and non-HTML has no pagination links
+    if merged.page_count.is_some() || !merged.content_type.contains("html") {
+        return Ok(merged);
+    }
+
+    let mut current = url::Url::parse(&merged.url)?;
+    let mut current_html = merged.content.clone();
+    let mut visited = std::collections::HashSet::from([pagination::page_key(&current)]);
+    let mut pages = vec![PageBoundary {
+        page: 1,
+        start_char: 0,
+        end_char: merged.clean_content.chars().count(),
+        url: Some(merged.url.clone()),
+    }];
+
+    while let Some(next) = pagination::next_page_url(&current_html, &current, &visited) {
+        if pages.len() >= max_pages {
+            merged.warnings.push("pagination_limit_reached".to_string());
+            break;
+        }
+        visited.insert(pagination::page_key(&next));
+        let page = match scrape_url_with_options(state, next.as_str(), options.clone()).await {
+            Ok(page) => page,
+            Err(e) => {
+                warn!("Stopped following pagination at {}: {}", next, e);
+                merged.warnings.push("pagination_incomplete".to_string());
+                break;
+            }
+        };
+        info!("Stitching page {} of {}: {}", pages.len() + 1, url, next);
+        pages.push(append_page(&mut merged, &page, pages.len() + 1));
+        current_html = page.content;
+        current = next;
+    }
+
+    if pages.len() > 1 {
+        merged.page_count = Some(pages.len());
+        merged.pages = pages;
+        merged.reading_time_minutes = Some(((merged.word_count as f64 / 200.0).ceil() as u32).max(1));
+    }
+    Ok(merged)
+}
+
+/// Append one page to a stitched response, returning its boundary
+fn append_page(merged: &mut ScrapeResponse, page: &ScrapeResponse, number: usize) -> PageBoundary {
+    merged.clean_content.push_str("\n\n");
+    let offset = merged.clean_content.chars().count();
+    merged.clean_content.push_str(&page.clean_content);
+    let shift = |start: Option<usize>, end: Option<usize>| (start.map(|s| s + offset), end.map(|e| e + offset));
+
+    merged.headings.extend(page.headings.iter().cloned().map(|mut h| {
+        (h.start_char, h.end_char) = shift(h.start_char, h.end_char);
+        h
+    }));
+    merged.code_blocks.extend(page.code_blocks.iter().cloned().map(|mut c| {
+        (c.start_char, c.end_char) = shift(c.start_char, c.end_char);
+        c
+    }));
+    for link in &page.links {
+        if !merged.links.iter().any(|l| l.url == link.url) {
+            merged.links.push(link.clone());
+        }
+    }
+    for image in &page.images {
+        if !merged.images.iter().any(|i| i.src == image.src) {
+            merged.images.push(image.clone());
+        }
+    }
+    merged.tables.extend(page.tables.iter().cloned());
+    for warning in &page.warnings {
+        if !merged.warnings.contains(warning) {
+            merged.warnings.push(warning.clone());
+        }
+    }
+    merged.word_count += page.word_count;
+
+    PageBoundary {
+        page: number,
+        start_char: offset,
+        end_char: merged.clean_content.chars().count(),
+        url: Some(page.url.clone()),
+    }
+}
+
+/// Text-mode rendering of `content.tables` (`table_format` "markdown" or "csv"),
+/// or an empty string when the page has none
+pub fn render_tables(content: &ScrapeResponse, table_format: &str) -> String {
+    const MAX_TEXT_TABLES: usize = 10;
+    let mut out = content
+        .tables
+        .iter()
+        .take(MAX_TEXT_TABLES)
+        .enumerate()
+        .map(|(i, table)| {
+            let label = match &table.caption {
+                Some(caption) => format!("Table {}: {} ({} rows)", i + 1, caption, table.rows.len()),
+                None => format!("Table {} ({} rows)", i + 1, table.rows.len()),
+            };
+            if table_format == "csv" {
+                format!("{}\n```csv\n{}\n```", label, table.to_csv())
+            } else {
+                format!("{}\n{}", label, table.to_markdown())
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    if content.tables.len() > MAX_TEXT_TABLES {
+        out.push_str(&format!(
+            "\n\n(Showing {} of {} tables; use output_format='json' for all)",
+            MAX_TEXT_TABLES,
+            content.tables.len()
+        ));
+    }
+    out
+}
+
+/// `output_format: "markdown"` rendering of a scrape: CommonMark of the main content
+/// (plain text for PDFs), cut at a block boundary when longer than `max_chars`.
+/// Stitched pages are rendered from their cached HTML and separated by rules.
+pub async fn markdown_output(state: &Arc<AppState>, content: &ScrapeResponse, max_chars: usize) -> String {
+    let markdown = if content.feed.is_some() {
+        // Feed entries are already rendered as `## title` sections
+        content.clean_content.clone()
+    } else if content.page_count.is_none() {
+        match url::Url::parse(&content.url) {
+            Ok(base) => state.scraper.extract_markdown(&content.content, &base),
+            Err(_) => content.clean_content.clone(),
+        }
+    } else if content.pages.iter().all(|p| p.url.is_some()) {
+        let mut parts = Vec::with_capacity(content.pages.len());
+        for page in &content.pages {
+            let page_url = page.url.as_deref().unwrap_or_default();
+            let cached = get_cached(state, page_url).await;
+            parts.push(match (cached, url::Url::parse(page_url)) {
+                (Some(entry), Ok(base)) => state.scraper.extract_markdown(&entry.response.content, &base),
+                // Evicted from the cache: fall back to the page's slice of clean_content
+                _ => content
+                    .clean_content
+                    .chars()
+                    .skip(page.start_char)
+                    .take(page.end_char - page.start_char)
+                    .collect(),
+            });
+        }
+        parts.join("\n\n---\n\n")
+    } else {
+        content.clean_content.clone()
+    };
+    let total = markdown.chars().count();
+    let body = if total > max_chars {
+        let cut: String = markdown.chars().take(max_chars).collect();
+        // Prefer ending on a blank line so code fences and tables aren't left half-open
+        let cut = match cut.rfind("\n\n") {
+            Some(i) if i > cut.len() / 2 => cut[..i].to_string(),
+            _ => cut,
+        };
+        let shown = cut.chars().count();
+        format!(
+            "{}\n\n[Content truncated: {}/{} chars shown. Increase max_chars parameter to see more]",
+            cut, shown, total
+        )
+    } else {
+        markdown
+    };
+    let title = if content.title.is_empty() || body.starts_with("# ") {
+        String::new()
+    } else {
+        format!("# {}\n\n", content.title)
+    };
+    format!("{}Source: <{}>\n\n{}", title, content.url, body)
+}
+
 // Fallback scraper using direct HTTP request (legacy simple mode) -- optional; keeping for troubleshooting
 pub async fn scrape_url_fallback(state: &Arc<AppState>, url: &str) -> Result<ScrapeResponse> {
     info!("Using fallback scraper for: {}", url);
     
-    // Make direct HTTP request
+    // Make direct HTTP request through the scraper's SSRF-guarded client
     let response = state
-        .http_client
+        .scraper
+        .client()
         .get(url)
         .header("User-Agent", "Mozilla/5.0 (compatible; MCP-Server/1.0)")
         .send()
         .await
-        .map_err(|e| anyhow!("Failed to fetch URL: {}", e))?;
+        .map_err(|e| FetchError::from_reqwest(url, e))?;
     
     let status_code = response.status().as_u16();
+    if status_code >= 400 {
+        return Err(FetchError::from_status(url, status_code).into());
+    }
     let content_type = response
         .headers()
         .get("content-type")
@@ -106,10 +398,13 @@ pub async fn scrape_url_fallback(state: &Arc<AppState>, url: &str) -> Result<Scr
         .unwrap_or("text/html")
         .to_string();
     
-    let html = response
-        .text()
-        .await
-        .map_err(|e| anyhow!("Failed to read response body: {}", e))?;
+    let body = crate::body::read_limited(response, url, &content_type, crate::body::max_download_bytes()).await?;
+    let host = url::Url::parse(url).ok().and_then(|u| u.host_str().map(|h| h.to_string()));
+    let (html, charset) = crate::charset::decode(&body.bytes, &content_type, host.as_deref());
+    let mut warnings = vec!["fallback_scraper_used".to_string()];
+    if body.truncated {
+        warnings.push("size_limit_exceeded".to_string());
+    }
     
     let document = select::document::Document::from(html.as_str());
     
@@ -152,6 +447,8 @@ pub async fn scrape_url_fallback(state: &Arc<AppState>, url: &str) -> Result<Scr
         .map(|n| Heading {
             level: n.name().unwrap_or("h1").to_string(),
             text: n.text(),
+            start_char: None,
+            end_char: None,
         })
         .collect();
     
@@ -195,6 +492,7 @@ pub async fn scrape_url_fallback(state: &Arc<AppState>, url: &str) -> Result<Scr
         site_name: None,
         author: None,
         published_at: None,
+        modified_at: None,
         og_title: None,
         og_description: None,
         og_image: None,
@@ -205,8 +503,15 @@ pub async fn scrape_url_fallback(state: &Arc<AppState>, url: &str) -> Result<Scr
         actual_chars: 0,
         max_chars_limit: None,
         extraction_score: Some(0.3), // Lower score for fallback
-        warnings: vec!["fallback_scraper_used".to_string()],
-        domain: url::Url::parse(url).ok().and_then(|u| u.host_str().map(|h| h.to_string())),
+        warnings,
+        domain: host,
+        encoding: Some(charset.encoding.name().to_string()),
+        page_count: None,
+        pages: Vec::new(),
+        structured_data: None,
+        tables: Vec::new(),
+        feed: None,
+        feeds: Vec::new(),
     };
     
     info!("Fallback scraper extracted {} words", result.word_count);
@@ -217,7 +522,146 @@ pub async fn scrape_url_fallback(state: &Arc<AppState>, url: &str) -> Result<Scr
 mod tests {
     use super::*;
     use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_cache_revalidation_with_etag() {
+        use axum::{http::{HeaderMap, StatusCode}, response::IntoResponse, routing::get, Router};
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        static HITS: AtomicUsize = AtomicUsize::new(0);
+        static NOT_MODIFIED: AtomicUsize = AtomicUsize::new(0);
+        let page = "<html><head><title>Cached</title></head><body><article><h1>Cached page</h1><p>"
+            .to_string() + &"Revalidation keeps this article fresh without downloading it again. ".repeat(20)
+            + "</p></article></body></html>";
+        let app = Router::new().route("/doc", get(move |headers: HeaderMap| {
+            let page = page.clone();
+            async move {
+                HITS.fetch_add(1, Ordering::SeqCst);
+                if headers.get("if-none-match").and_then(|v| v.to_str().ok()) == Some("\"v1\"") {
+                    NOT_MODIFIED.fetch_add(1, Ordering::SeqCst);
+                    return (StatusCode::NOT_MODIFIED, [("etag", "\"v1\"")], String::new()).into_response();
+                }
+                ([("etag", "\"v1\""), ("content-type", "text/html")], page).into_response()
+            }
+        }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let state = AppState::new("http://localhost:8888".to_string(), reqwest::Client::new())
+            .with_ssrf_policy(crate::ssrf::SsrfPolicy::disabled());
+        let state = Arc::new(state);
+        let url = format!("http://{}/doc", addr);
+
+        let first = scrape_url(&state, &url).await.unwrap();
+        // Fresh enough: served from cache without a request
+        let cached = scrape_url(&state, &url).await.unwrap();
+        assert_eq!(HITS.load(Ordering::SeqCst), 1);
+        assert_eq!(cached.timestamp, first.timestamp);
+
+        // max_age=0 forces a conditional request that comes back 304
+        let options = ScrapeOptions { max_age: Some(std::time::Duration::ZERO), force_refresh: false };
+        let revalidated = scrape_url_with_options(&state, &url, options).await.unwrap();
+        assert_eq!(HITS.load(Ordering::SeqCst), 2);
+        assert_eq!(NOT_MODIFIED.load(Ordering::SeqCst), 1);
+        assert_eq!(revalidated.timestamp, first.timestamp);
+
+        // force_refresh skips validators and downloads again
+        let options = ScrapeOptions { max_age: None, force_refresh: true };
+        scrape_url_with_options(&state, &url, options).await.unwrap();
+        assert_eq!(HITS.load(Ordering::SeqCst), 3);
+        assert_eq!(NOT_MODIFIED.load(Ordering::SeqCst), 1);
+    }
     
+    #[tokio::test]
+    async fn test_retries_only_transient_statuses() {
+        use crate::error::{ErrorInfo, ErrorKind};
+        use axum::{http::StatusCode, routing::get, Router};
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        static MISSING_HITS: AtomicUsize = AtomicUsize::new(0);
+        static FLAKY_HITS: AtomicUsize = AtomicUsize::new(0);
+        let page = "<html><head><title>Recovered</title></head><body><article><p>".to_string()
+            + &"The second attempt returns the article. ".repeat(20)
+            + "</p></article></body></html>";
+        let app = Router::new()
+            .route("/missing", get(|| async {
+                MISSING_HITS.fetch_add(1, Ordering::SeqCst);
+                (StatusCode::NOT_FOUND, "not here")
+            }))
+            .route("/flaky", get(move || {
+                let page = page.clone();
+                async move {
+                    if FLAKY_HITS.fetch_add(1, Ordering::SeqCst) == 0 {
+                        (StatusCode::SERVICE_UNAVAILABLE, [("content-type", "text/html")], "try later".to_string())
+                    } else {
+                        (StatusCode::OK, [("content-type", "text/html")], page)
+                    }
+                }
+            }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let state = AppState::new("http://localhost:8888".to_string(), reqwest::Client::new())
+            .with_ssrf_policy(crate::ssrf::SsrfPolicy::disabled());
+        let state = Arc::new(state);
+
+        let err = scrape_url(&state, &format!("http://{}/missing", addr)).await.unwrap_err();
+        let info = ErrorInfo::from_error(&err);
+        assert_eq!((info.code, info.http_status, info.retryable), (ErrorKind::HttpStatus, Some(404), false));
+        assert_eq!(MISSING_HITS.load(Ordering::SeqCst), 1);
+
+        let page = scrape_url(&state, &format!("http://{}/flaky", addr)).await.unwrap();
+        assert_eq!(page.title, "Recovered");
+        assert_eq!(FLAKY_HITS.load(Ordering::SeqCst), 2);
+
+        let err = scrape_url(&state, "http://exa mple.com/").await.unwrap_err();
+        assert_eq!(ErrorInfo::from_error(&err).code, ErrorKind::InvalidUrl);
+    }
+
+    #[tokio::test]
+    async fn test_follow_pagination() {
+        use axum::{extract::Query, routing::get, Router};
+        use std::collections::HashMap;
+
+        let app = Router::new().route("/thread", get(|Query(q): Query<HashMap<String, String>>| async move {
+            let n: usize = q.get("page").and_then(|p| p.parse().ok()).unwrap_or(1);
+            let next = if n < 4 {
+                format!(r#"<nav class="pagination"><a href="/thread?page={}">Next »</a></nav>"#, n + 1)
+            } else {
+                String::new()
+            };
+            let body = format!(
+                "<html><head><title>Thread</title></head><body><article><h2>Part {}</h2><p>{}</p></article>{}</body></html>",
+                n,
+                format!("Reply number {} continues the discussion with more detail. ", n).repeat(15),
+                next
+            );
+            ([("content-type", "text/html")], body)
+        }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let state = AppState::new("http://localhost:8888".to_string(), reqwest::Client::new())
+            .with_ssrf_policy(crate::ssrf::SsrfPolicy::disabled());
+        let state = Arc::new(state);
+        let url = format!("http://{}/thread", addr);
+
+        let merged = scrape_url_paginated(&state, &url, ScrapeOptions::default(), 3).await.unwrap();
+        assert_eq!(merged.page_count, Some(3));
+        assert_eq!(merged.pages[2].url.as_deref(), Some(format!("http://{}/thread?page=3", addr).as_str()));
+        assert!(merged.warnings.contains(&"pagination_limit_reached".to_string()));
+
+        let slice = |start: usize, end: usize| merged.clean_content.chars().skip(start).take(end - start).collect::<String>();
+        let second = &merged.pages[1];
+        assert!(slice(second.start_char, second.end_char).contains("Reply number 2"));
+        let heading = merged.headings.iter().find(|h| h.text == "Part 2").unwrap();
+        assert_eq!(slice(heading.start_char.unwrap(), heading.end_char.unwrap()), "Part 2");
+        assert!(heading.start_char.unwrap() >= second.start_char);
+    }
+
     #[tokio::test]
     async fn test_scrape_url_fallback() {
         let state = Arc::new(AppState::new(
@@ -1,13 +1,17 @@
 use crate::types::*;
 use crate::AppState;
 use crate::query_rewriter::{QueryRewriter, QueryRewriteResult};
-use anyhow::{anyhow, Result};
+use crate::error::{backoff_error, FetchError};
+use anyhow::Result;
 use backoff::future::retry;
 use backoff::ExponentialBackoffBuilder;
 use std::collections::HashMap;
 use std::sync::Arc;
 use tracing::{debug, info, warn};
 
+/// Disk cache namespace for search results
+pub(crate) const SEARCH_NAMESPACE: &str = "search";
+
 #[derive(Debug, Default, Clone)]
 pub struct SearchParamOverrides {
     pub engines: Option<String>,       // comma-separated list
@@ -103,8 +107,24 @@ pub async fn search_web_with_params(
         };
         return Ok((cached, cached_extras));
     }
+    if let Some(disk) = &state.disk_cache {
+        if let Some(cached) = disk.get::<Vec<SearchResult>>(SEARCH_NAMESPACE, &cache_key).await {
+            debug!("search disk cache hit for query");
+            state.search_cache.insert(cache_key, cached.clone()).await;
+            let cached_extras = SearchExtras {
+                query_rewrite: Some(rewrite_result),
+                duplicate_warning,
+                ..Default::default()
+            };
+            return Ok((cached, cached_extras));
+        }
+    }
 
-    let _permit = state.outbound_limit.acquire().await.expect("semaphore closed");
+    let searxng_host = url::Url::parse(&state.searxng_url)
+        .ok()
+        .and_then(|u| u.host_str().map(|h| h.to_string()))
+        .unwrap_or_default();
+    let _permit = state.outbound.acquire(&searxng_host, None).await;
     let mut params: HashMap<String, String> = HashMap::new();
     let engines = std::env::var("SEARXNG_ENGINES").unwrap_or_else(|_| "duckduckgo,google,bing".to_string());
     
@@ -147,22 +167,28 @@ pub async fn search_web_with_params(
                 .header("Accept", "application/json")
                 .send()
                 .await
-                .map_err(|e| backoff::Error::transient(anyhow!("Failed to send request to SearXNG: {}", e)))?;
+                .map_err(|e| backoff_error(FetchError::from_reqwest(&search_url_owned, e)))?;
             if !resp.status().is_success() {
                 let status = resp.status();
                 let text = resp.text().await.unwrap_or_else(|_| "".into());
-                let err = anyhow!("SearXNG request failed with status {}: {}", status, text);
-                // 5xx transient, others permanent
-                if status.is_server_error() {
-                    return Err(backoff::Error::transient(err));
+                warn!("SearXNG request failed with status {}: {}", status, text);
+                // A failing SearXNG is an outage on our side, not a problem with the query
+                let err = if status.is_server_error() {
+                    FetchError::UpstreamUnavailable { target: "SearXNG".to_string(), reason: format!("HTTP {}", status) }
                 } else {
-                    return Err(backoff::Error::permanent(err));
-                }
-            }
-            match resp.json::<SearxngResponse>().await {
-                Ok(parsed) => Ok(parsed),
-                Err(e) => Err(backoff::Error::transient(anyhow!("Failed to parse SearXNG response: {}", e))),
+                    FetchError::HttpStatus { url: search_url_owned.clone(), status: status.as_u16() }
+                };
+                return Err(backoff_error(err.into()));
             }
+            resp.json::<SearxngResponse>().await.map_err(|e| {
+                backoff_error(
+                    FetchError::UpstreamUnavailable {
+                        target: "SearXNG".to_string(),
+                        reason: format!("unparseable response: {}", e),
+                    }
+                    .into(),
+                )
+            })
         },
     )
     .await?;
@@ -217,6 +243,9 @@ pub async fn search_web_with_params(
     
     debug!("Converted {} results", results.len());
     // Fill cache with composite key
+    if let Some(disk) = &state.disk_cache {
+        disk.put(SEARCH_NAMESPACE, &cache_key, &results).await;
+    }
     state.search_cache.insert(cache_key, results.clone()).await;
     
     // Auto-log to history if memory is enabled (Phase 1)
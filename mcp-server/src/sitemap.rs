@@ -0,0 +1,417 @@
+use crate::body::{read_limited, BodyTooLarge};
+use crate::error::FetchError;
+use crate::types::*;
+use crate::AppState;
+use anyhow::{anyhow, Result};
+use chrono::NaiveDate;
+use flate2::read::GzDecoder;
+use std::collections::{HashSet, VecDeque};
+use std::io::Read;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+use url::Url;
+
+/// URLs returned when `limit` isn't given
+pub const DEFAULT_LIMIT: usize = 500;
+
+/// Upper bound on `limit`
+pub const MAX_LIMIT: usize = 5000;
+
+/// Sitemap files read per call, nested ones included
+const MAX_SITEMAPS: usize = 50;
+
+/// Decompressed size cap for a gzipped sitemap (the protocol's own limit is 50 MB)
+const MAX_SITEMAP_BYTES: u64 = 50 * 1024 * 1024;
+
+const FETCH_TIMEOUT: Duration = Duration::from_secs(20);
+
+#[derive(Debug, Clone)]
+pub struct SitemapOptions {
+    /// Keep URLs whose path starts with this prefix (or, when it is an absolute URL, URLs starting with it)
+    pub path_prefix: Option<String>,
+    /// Keep URLs whose `lastmod` is on or after this date; URLs without `lastmod` are dropped
+    pub modified_since: Option<NaiveDate>,
+    /// Maximum URLs returned
+    pub limit: usize,
+}
+
+impl Default for SitemapOptions {
+    fn default() -> Self {
+        Self { path_prefix: None, modified_since: None, limit: DEFAULT_LIMIT }
+    }
+}
+
+impl SitemapOptions {
+    /// Options from tool arguments; `modified_since` must be a date such as `2024-01-31`
+    pub fn new(path_prefix: Option<&str>, modified_since: Option<&str>, limit: usize) -> Result<Self> {
+        let modified_since = match modified_since.map(str::trim) {
+            Some(s) if !s.is_empty() => Some(
+                lastmod_date(s).ok_or_else(|| anyhow!("Invalid modified_since '{}': expected a date like 2024-01-31", s))?,
+            ),
+            _ => None,
+        };
+        Ok(Self {
+            path_prefix: path_prefix.map(str::trim).filter(|s| !s.is_empty()).map(str::to_string),
+            modified_since,
+            limit,
+        })
+    }
+
+    fn matches(&self, entry: &SitemapUrl) -> bool {
+        if let Some(prefix) = &self.path_prefix {
+            let matched = if prefix.starts_with("http://") || prefix.starts_with("https://") {
+                entry.url.starts_with(prefix.as_str())
+            } else {
+                Url::parse(&entry.url).map(|u| u.path().starts_with(prefix.as_str())).unwrap_or(false)
+            };
+            if !matched {
+                return false;
+            }
+        }
+        match self.modified_since {
+            Some(since) => entry.lastmod.as_deref().and_then(lastmod_date).map(|d| d >= since).unwrap_or(false),
+            None => true,
+        }
+    }
+}
+
+/// A parsed sitemap file
+#[derive(Debug, PartialEq)]
+pub enum Sitemap {
+    Urls(Vec<SitemapUrl>),
+    /// A sitemap index: locations of further sitemaps
+    Index(Vec<String>),
+}
+
+/// List a site's pages from its sitemaps. `url` is either a sitemap itself (`.xml` / `.xml.gz`)
+/// or any page of the site, in which case sitemaps are discovered from robots.txt `Sitemap:`
+/// lines, falling back to `/sitemap.xml`. Sitemap indexes are followed.
+pub async fn list_site_pages(state: &Arc<AppState>, url: &str, options: SitemapOptions) -> Result<SitemapResponse> {
+    let site = Url::parse(url).map_err(|e| FetchError::invalid_url(url, e))?;
+    if !matches!(site.scheme(), "http" | "https") {
+        return Err(FetchError::invalid_url(url, "must start with http:// or https://").into());
+    }
+    let limit = options.limit.clamp(1, MAX_LIMIT);
+
+    let mut queue: VecDeque<String> = if is_sitemap_url(&site) {
+        VecDeque::from([site.to_string()])
+    } else {
+        let declared = state.robots.robots_for(&site).await.sitemaps().to_vec();
+        if declared.is_empty() {
+            debug!("No Sitemap: lines in robots.txt for {}; trying /sitemap.xml", site.origin().ascii_serialization());
+            VecDeque::from([format!("{}/sitemap.xml", site.origin().ascii_serialization())])
+        } else {
+            declared.into()
+        }
+    };
+    info!("Listing pages of {} from {} sitemap(s)", site, queue.len());
+
+    let mut response = SitemapResponse {
+        site: site.origin().ascii_serialization(),
+        sitemaps: Vec::new(),
+        urls: Vec::new(),
+        total_matched: 0,
+        truncated: false,
+        errors: Vec::new(),
+    };
+    let mut seen_sitemaps: HashSet<String> = queue.iter().cloned().collect();
+    let mut seen_urls = HashSet::new();
+    let mut last_error = None;
+
+    while let Some(sitemap_url) = queue.pop_front() {
+        if response.sitemaps.len() + response.errors.len() >= MAX_SITEMAPS {
+            warn!("Sitemap limit ({}) reached for {}", MAX_SITEMAPS, site);
+            response.truncated = true;
+            break;
+        }
+        let sitemap = match fetch_sitemap(state, &sitemap_url).await.and_then(|text| parse_sitemap(&text)) {
+            Ok(sitemap) => sitemap,
+            Err(e) => {
+                warn!("Failed to read sitemap {}: {}", sitemap_url, e);
+                response.errors.push(format!("{}: {}", sitemap_url, e));
+                last_error = Some(e);
+                continue;
+            }
+        };
+        response.sitemaps.push(sitemap_url.clone());
+
+        match sitemap {
+            Sitemap::Index(children) => {
+                let base = Url::parse(&sitemap_url).ok();
+                for child in children {
+                    let child = base.as_ref().and_then(|b| b.join(&child).ok()).map(|u| u.to_string()).unwrap_or(child);
+                    if seen_sitemaps.insert(child.clone()) {
+                        queue.push_back(child);
+                    }
+                }
+            }
+            Sitemap::Urls(entries) => {
+                for entry in entries {
+                    if !options.matches(&entry) || !seen_urls.insert(entry.url.clone()) {
+                        continue;
+                    }
+                    response.total_matched += 1;
+                    if response.urls.len() < limit {
+                        response.urls.push(entry);
+                    }
+                }
+            }
+        }
+    }
+
+    if response.sitemaps.is_empty() {
+        // Keep the last failure's category (e.g. a 404 for /sitemap.xml) for the caller
+        let error = last_error.unwrap_or_else(|| anyhow!("no sitemap listed"));
+        return Err(error.context(format!("No sitemap found for {}", response.site)));
+    }
+    response.truncated |= response.total_matched > response.urls.len();
+    info!(
+        "Read {} sitemap(s) for {}: {} matching URLs, {} returned",
+        response.sitemaps.len(),
+        response.site,
+        response.total_matched,
+        response.urls.len()
+    );
+    Ok(response)
+}
+
+/// Parse an XML `<urlset>` / `<sitemapindex>` or a plain-text sitemap (one URL per line)
+pub fn parse_sitemap(text: &str) -> Result<Sitemap> {
+    let text = text.trim_start_matches('\u{feff}').trim_start();
+    if !text.starts_with('<') {
+        let urls = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| Url::parse(line).map(|u| matches!(u.scheme(), "http" | "https")).unwrap_or(false))
+            .map(|line| SitemapUrl { url: line.to_string(), lastmod: None, priority: None, changefreq: None })
+            .collect::<Vec<_>>();
+        if urls.is_empty() {
+            return Err(anyhow!("not an XML or text sitemap"));
+        }
+        return Ok(Sitemap::Urls(urls));
+    }
+
+    let options = roxmltree::ParsingOptions { allow_dtd: true, ..Default::default() };
+    let document = roxmltree::Document::parse_with_options(text, options).map_err(|e| anyhow!("invalid XML: {}", e))?;
+    let root = document.root_element();
+    let children = |name: &'static str| root.children().filter(move |n| n.is_element() && n.tag_name().name() == name);
+    match root.tag_name().name() {
+        "sitemapindex" => Ok(Sitemap::Index(children("sitemap").filter_map(|n| child_text(n, "loc")).collect())),
+        "urlset" => Ok(Sitemap::Urls(
+            children("url")
+                .filter_map(|n| {
+                    Some(SitemapUrl {
+                        url: child_text(n, "loc")?,
+                        lastmod: child_text(n, "lastmod"),
+                        priority: child_text(n, "priority").and_then(|p| p.parse().ok()),
+                        changefreq: child_text(n, "changefreq"),
+                    })
+                })
+                .collect(),
+        )),
+        other => Err(anyhow!("unexpected root element <{}>", other)),
+    }
+}
+
+fn child_text(node: roxmltree::Node, name: &str) -> Option<String> {
+    node.children()
+        .find(|c| c.is_element() && c.tag_name().name() == name)
+        .and_then(|c| c.text())
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty())
+}
+
+/// Date part of a W3C datetime (`2024`, `2024-05`, `2024-05-01` or `2024-05-01T12:00:00+00:00`)
+fn lastmod_date(value: &str) -> Option<NaiveDate> {
+    let value = value.trim();
+    let date = value.get(..10).unwrap_or(value);
+    match date.len() {
+        10 => NaiveDate::parse_from_str(date, "%Y-%m-%d").ok(),
+        7 => NaiveDate::parse_from_str(&format!("{}-01", date), "%Y-%m-%d").ok(),
+        4 => NaiveDate::parse_from_str(&format!("{}-01-01", date), "%Y-%m-%d").ok(),
+        _ => None,
+    }
+}
+
+fn is_sitemap_url(url: &Url) -> bool {
+    let path = url.path().to_lowercase();
+    path.ends_with(".xml") || path.ends_with(".xml.gz")
+}
+
+/// Download a sitemap through the SSRF-guarded scrape client, decompressing `.gz` files
+async fn fetch_sitemap(state: &Arc<AppState>, url: &str) -> Result<String> {
+    let parsed = Url::parse(url).map_err(|e| FetchError::invalid_url(url, e))?;
+    state.ssrf.check_url(&parsed).await?;
+
+    let host = parsed.host_str().unwrap_or_default().to_string();
+    let crawl_delay = state.robots.crawl_delay(&parsed).await;
+    let _permit = state.outbound.acquire(&host, crawl_delay).await;
+
+    debug!("Fetching sitemap {}", url);
+    let response = state
+        .scraper
+        .client()
+        .get(url)
+        .header("User-Agent", format!("Mozilla/5.0 (compatible; {})", state.robots.user_agent()))
+        .header("Accept", "application/xml,text/xml;q=0.9,text/plain;q=0.8,*/*;q=0.5")
+        .timeout(FETCH_TIMEOUT)
+        .send()
+        .await
+        .map_err(|e| FetchError::from_reqwest(url, e))?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(FetchError::from_status(url, status.as_u16()).into());
+    }
+
+    let content_type = response
+        .headers()
+        .get("content-type")
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("application/xml")
+        .to_lowercase();
+    // `.xml.gz` files are served as gzip archives, which read_limited would refuse as binary
+    let gzipped = content_type.contains("gzip") || parsed.path().to_lowercase().ends_with(".gz");
+    let read_as = if gzipped { "application/xml" } else { content_type.as_str() };
+    let max_bytes = crate::body::max_download_bytes();
+    let body = read_limited(response, url, read_as, max_bytes).await?;
+    if body.truncated {
+        return Err(BodyTooLarge { url: url.to_string(), limit_bytes: max_bytes }.into());
+    }
+
+    if body.bytes.starts_with(&[0x1f, 0x8b]) {
+        let mut xml = Vec::new();
+        GzDecoder::new(body.bytes.as_slice())
+            .take(MAX_SITEMAP_BYTES)
+            .read_to_end(&mut xml)
+            .map_err(|e| anyhow!("invalid gzip data: {}", e))?;
+        Ok(String::from_utf8_lossy(&xml).into_owned())
+    } else {
+        Ok(String::from_utf8_lossy(&body.bytes).into_owned())
+    }
+}
+
+/// Plain-text rendering for the MCP tools: one URL per line with its sitemap metadata
+pub fn render_text(result: &SitemapResponse) -> String {
+    let mut out = format!(
+        "**Pages of {}** ({} of {} matching URLs, from {} sitemap(s))\n\n",
+        result.site,
+        result.urls.len(),
+        result.total_matched,
+        result.sitemaps.len()
+    );
+    for entry in &result.urls {
+        let mut meta = Vec::new();
+        if let Some(lastmod) = &entry.lastmod {
+            meta.push(format!("lastmod {}", lastmod));
+        }
+        if let Some(priority) = entry.priority {
+            meta.push(format!("priority {}", priority));
+        }
+        if meta.is_empty() {
+            out.push_str(&format!("- {}\n", entry.url));
+        } else {
+            out.push_str(&format!("- {} ({})\n", entry.url, meta.join(", ")));
+        }
+    }
+    if result.truncated {
+        out.push_str("\n⚠️ Results truncated: raise `limit` or narrow with `path_prefix` / `modified_since`.\n");
+    }
+    if !result.errors.is_empty() {
+        out.push_str("\n**Unreadable sitemaps:**\n");
+        for error in &result.errors {
+            out.push_str(&format!("- {}\n", error));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_sitemap_formats() {
+        let urlset = r#"<?xml version="1.0" encoding="UTF-8"?>
+            <urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+              <url><loc> https://example.com/docs/intro </loc><lastmod>2024-03-01T10:00:00+00:00</lastmod><priority>0.8</priority></url>
+              <url><loc>https://example.com/blog/</loc><changefreq>daily</changefreq></url>
+              <url><lastmod>2024-01-01</lastmod></url>
+            </urlset>"#;
+        let Sitemap::Urls(urls) = parse_sitemap(urlset).unwrap() else { panic!("expected a urlset") };
+        assert_eq!(urls.len(), 2);
+        assert_eq!(urls[0].url, "https://example.com/docs/intro");
+        assert_eq!(urls[0].priority, Some(0.8));
+        assert_eq!(urls[1].changefreq.as_deref(), Some("daily"));
+
+        let index = r#"<sitemapindex xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+            <sitemap><loc>https://example.com/sitemap-docs.xml.gz</loc></sitemap></sitemapindex>"#;
+        assert_eq!(parse_sitemap(index).unwrap(), Sitemap::Index(vec!["https://example.com/sitemap-docs.xml.gz".to_string()]));
+
+        let Sitemap::Urls(urls) = parse_sitemap("https://example.com/a\n\nhttps://example.com/b\n").unwrap() else {
+            panic!("expected a text sitemap")
+        };
+        assert_eq!(urls.len(), 2);
+        assert!(parse_sitemap("<html><body>Not found</body></html>").is_err());
+
+        assert_eq!(lastmod_date("2024-03-01T10:00:00Z"), NaiveDate::from_ymd_opt(2024, 3, 1));
+        assert_eq!(lastmod_date("2024-03"), NaiveDate::from_ymd_opt(2024, 3, 1));
+        assert_eq!(lastmod_date("yesterday"), None);
+    }
+
+    #[tokio::test]
+    async fn test_list_site_pages_discovery_and_filters() {
+        use axum::{routing::get, Router};
+        use flate2::{write::GzEncoder, Compression};
+        use std::io::Write;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let base = format!("http://{}", addr);
+
+        let docs = format!(
+            r#"<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+              <url><loc>{0}/docs/new</loc><lastmod>2024-06-01</lastmod></url>
+              <url><loc>{0}/docs/old</loc><lastmod>2022-01-01</lastmod></url>
+              <url><loc>{0}/docs/undated</loc></url>
+            </urlset>"#,
+            base
+        );
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::fast());
+        encoder.write_all(docs.as_bytes()).unwrap();
+        let docs_gz = encoder.finish().unwrap();
+        let index = r#"<sitemapindex><sitemap><loc>/sitemaps/docs.xml.gz</loc></sitemap>
+            <sitemap><loc>/sitemaps/blog.xml</loc></sitemap><sitemap><loc>/sitemaps/missing.xml</loc></sitemap></sitemapindex>"#;
+        let blog = format!("<urlset><url><loc>{0}/blog/post</loc><lastmod>2024-07-01</lastmod></url></urlset>", base);
+        let robots = format!("User-agent: *\nAllow: /\nSitemap: {}/sitemap_index.xml\n", base);
+
+        let app = Router::new()
+            .route("/robots.txt", get(move || async move { robots }))
+            .route("/sitemap_index.xml", get(move || async move { ([("content-type", "application/xml")], index) }))
+            .route("/sitemaps/docs.xml.gz", get(move || async move { ([("content-type", "application/x-gzip")], docs_gz) }))
+            .route("/sitemaps/blog.xml", get(move || async move { ([("content-type", "text/xml")], blog) }));
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let state = AppState::new("http://localhost:8888".to_string(), reqwest::Client::new())
+            .with_ssrf_policy(crate::ssrf::SsrfPolicy::disabled());
+        let state = Arc::new(state);
+
+        let all = list_site_pages(&state, &format!("{}/docs/new", base), SitemapOptions::default()).await.unwrap();
+        assert_eq!(all.sitemaps.len(), 3);
+        assert_eq!(all.urls.len(), 4);
+        assert_eq!(all.errors.len(), 1);
+        assert!(!all.truncated);
+
+        let options = SitemapOptions {
+            path_prefix: Some("/docs/".to_string()),
+            modified_since: NaiveDate::from_ymd_opt(2024, 1, 1),
+            ..Default::default()
+        };
+        let recent = list_site_pages(&state, &base, options).await.unwrap();
+        let urls: Vec<&str> = recent.urls.iter().map(|u| u.url.as_str()).collect();
+        assert_eq!(urls, vec![format!("{}/docs/new", base)]);
+
+        let limited = list_site_pages(&state, &base, SitemapOptions { limit: 2, ..Default::default() }).await.unwrap();
+        assert_eq!((limited.urls.len(), limited.total_matched, limited.truncated), (2, 4, true));
+    }
+}
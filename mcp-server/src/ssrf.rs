@@ -0,0 +1,413 @@
+use crate::proxy::ProxyConfig;
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::Arc;
+use tracing::warn;
+use url::{Host, Url};
+
+/// Hostnames that always point at internal infrastructure
+const BLOCKED_HOSTNAMES: &[&str] = &[
+    "localhost",
+    "metadata",
+    "metadata.google.internal",
+    "metadata.goog",
+    "instance-data",
+    "instance-data.ec2.internal",
+];
+
+/// Error returned when a scrape target is not a public address
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum SsrfError {
+    #[error("blocked_by_ssrf_policy: {host} resolves to non-public address {ip}")]
+    BlockedAddress { host: String, ip: IpAddr },
+    #[error("blocked_by_ssrf_policy: host '{host}' is not allowed")]
+    BlockedHost { host: String },
+}
+
+/// IP network in CIDR notation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cidr {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl Cidr {
+    pub fn parse(s: &str) -> Option<Self> {
+        let (addr, prefix) = match s.split_once('/') {
+            Some((a, p)) => (a.trim().parse::<IpAddr>().ok()?, Some(p.trim().parse::<u8>().ok()?)),
+            None => (s.trim().parse::<IpAddr>().ok()?, None),
+        };
+        let max = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix = prefix.unwrap_or(max);
+        (prefix <= max).then_some(Self { addr, prefix })
+    }
+
+    pub fn contains(&self, ip: &IpAddr) -> bool {
+        match (self.addr, ip) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                let mask = u32::MAX.checked_shl(32 - self.prefix as u32).unwrap_or(0);
+                u32::from(net) & mask == u32::from(*ip) & mask
+            }
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                let mask = u128::MAX.checked_shl(128 - self.prefix as u32).unwrap_or(0);
+                u128::from(net) & mask == u128::from(*ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Whether an IPv4 address is loopback, private, link-local or otherwise not publicly routable
+fn is_non_public_v4(ip: &Ipv4Addr) -> bool {
+    let [a, b, c, _] = ip.octets();
+    ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local() // 169.254.0.0/16, includes cloud metadata 169.254.169.254
+        || ip.is_broadcast()
+        || ip.is_multicast()
+        || ip.is_documentation()
+        || a == 0
+        || (a == 100 && (64..128).contains(&b)) // 100.64.0.0/10 CGNAT (Alibaba metadata lives here)
+        || (a == 192 && b == 0 && c == 0) // 192.0.0.0/24 IETF protocol assignments
+        || (a == 198 && (b == 18 || b == 19)) // 198.18.0.0/15 benchmarking
+        || a >= 240 // 240.0.0.0/4 reserved
+}
+
+/// Whether an IPv6 address is loopback, unique-local, link-local or maps to a non-public IPv4 address
+fn is_non_public_v6(ip: &Ipv6Addr) -> bool {
+    if let Some(v4) = ip.to_ipv4_mapped() {
+        return is_non_public_v4(&v4);
+    }
+    let segments = ip.segments();
+    // 64:ff9b::/96 NAT64 embeds an IPv4 address in the low 32 bits
+    if segments[..6] == [0x64, 0xff9b, 0, 0, 0, 0] {
+        let v4 = Ipv4Addr::from(u128::from(*ip) as u32);
+        return is_non_public_v4(&v4);
+    }
+    ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_multicast()
+        || (segments[0] & 0xfe00) == 0xfc00 // fc00::/7 unique local (incl. AWS fd00:ec2::254)
+        || (segments[0] & 0xffc0) == 0xfe80 // fe80::/10 link-local
+        || (segments[0] & 0xffc0) == 0xfec0 // fec0::/10 deprecated site-local
+        || (segments[0] == 0x2001 && segments[1] == 0x0db8) // documentation
+}
+
+pub fn is_non_public(ip: &IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => is_non_public_v4(v4),
+        IpAddr::V6(v6) => is_non_public_v6(v6),
+    }
+}
+
+/// Policy deciding which scrape targets are reachable
+#[derive(Debug, Clone)]
+pub struct SsrfPolicy {
+    enabled: bool,
+    allowed_hosts: Vec<String>,
+    allowed_networks: Vec<Cidr>,
+    /// Fixed addresses `check_url` uses instead of DNS for these hostnames (tests only)
+    resolved_hosts: Vec<(String, Vec<IpAddr>)>,
+}
+
+impl Default for SsrfPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            allowed_hosts: Vec::new(),
+            allowed_networks: Vec::new(),
+            resolved_hosts: Vec::new(),
+        }
+    }
+}
+
+impl SsrfPolicy {
+    /// Policy that allows every address (for trusted, intranet-only deployments)
+    pub fn disabled() -> Self {
+        Self { enabled: false, ..Self::default() }
+    }
+
+    /// Allow hosts (`wiki.corp`, `*.corp.example`) and networks (`10.20.0.0/16`, `192.168.1.5`)
+    pub fn with_allowlist<I, S>(mut self, entries: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        for entry in entries {
+            let entry = entry.as_ref().trim();
+            if entry.is_empty() {
+                continue;
+            }
+            match Cidr::parse(entry) {
+                Some(cidr) => self.allowed_networks.push(cidr),
+                None => self.allowed_hosts.push(entry.trim_end_matches('.').to_lowercase()),
+            }
+        }
+        self
+    }
+
+    /// Resolve `host` to `ips` in `check_url` without asking DNS
+    #[cfg(test)]
+    fn with_resolved_host(mut self, host: &str, ips: Vec<IpAddr>) -> Self {
+        self.resolved_hosts.push((host.to_lowercase(), ips));
+        self
+    }
+
+    /// Read `SSRF_PROTECTION` (on | off) and `SSRF_ALLOWLIST` (comma-separated hosts/CIDRs)
+    pub fn from_env() -> Self {
+        let enabled = !matches!(
+            std::env::var("SSRF_PROTECTION").unwrap_or_default().trim().to_lowercase().as_str(),
+            "off" | "false" | "0" | "disabled"
+        );
+        if !enabled {
+            warn!("SSRF_PROTECTION is off: scrape_url can reach private and internal addresses");
+            return Self::disabled();
+        }
+        let allowlist = std::env::var("SSRF_ALLOWLIST").unwrap_or_default();
+        Self::default().with_allowlist(allowlist.split(','))
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn host_allowlisted(&self, host: &str) -> bool {
+        let host = host.trim_end_matches('.').to_lowercase();
+        self.allowed_hosts.iter().any(|allowed| match allowed.strip_prefix("*.") {
+            Some(suffix) => host == suffix || host.ends_with(&format!(".{}", suffix)),
+            None => host == *allowed,
+        })
+    }
+
+    /// Check a resolved address for `host`
+    pub fn check_ip(&self, host: &str, ip: &IpAddr) -> Result<(), SsrfError> {
+        if !self.enabled
+            || self.host_allowlisted(host)
+            || self.allowed_networks.iter().any(|net| net.contains(ip))
+            || !is_non_public(ip)
+        {
+            return Ok(());
+        }
+        Err(SsrfError::BlockedAddress { host: host.to_string(), ip: *ip })
+    }
+
+    /// Check a hostname before resolution (blocks `localhost`, metadata names, etc.)
+    pub fn check_hostname(&self, host: &str) -> Result<(), SsrfError> {
+        if !self.enabled || self.host_allowlisted(host) {
+            return Ok(());
+        }
+        let lower = host.trim_end_matches('.').to_lowercase();
+        if BLOCKED_HOSTNAMES.contains(&lower.as_str()) || lower.ends_with(".localhost") || lower.ends_with(".internal") {
+            return Err(SsrfError::BlockedHost { host: host.to_string() });
+        }
+        Ok(())
+    }
+
+    /// Check a URL without DNS resolution: IP-literal hosts and blocked hostnames
+    pub fn check_url_host(&self, url: &Url) -> Result<(), SsrfError> {
+        match url.host() {
+            Some(Host::Ipv4(ip)) => self.check_ip(&ip.to_string(), &IpAddr::V4(ip)),
+            Some(Host::Ipv6(ip)) => self.check_ip(&ip.to_string(), &IpAddr::V6(ip)),
+            Some(Host::Domain(domain)) => {
+                // Catch decimal/octal IPv4 forms the URL parser left as domains
+                if let Ok(ip) = domain.parse::<IpAddr>() {
+                    return self.check_ip(domain, &ip);
+                }
+                self.check_hostname(domain)
+            }
+            None => Err(SsrfError::BlockedHost { host: String::new() }),
+        }
+    }
+
+    /// Whether checking `url` needs DNS resolution: a domain that isn't allowlisted
+    fn needs_resolution(&self, url: &Url) -> bool {
+        self.enabled
+            && matches!(url.host(), Some(Host::Domain(domain)) if domain.parse::<IpAddr>().is_err() && !self.host_allowlisted(domain))
+    }
+
+    /// Pre-flight check: host checks plus resolving the name and checking every address.
+    /// Connection-time checks in `GuardedResolver` still apply (DNS rebinding).
+    pub async fn check_url(&self, url: &Url) -> Result<(), SsrfError> {
+        if !self.enabled {
+            return Ok(());
+        }
+        self.check_url_host(url)?;
+        if let Some(Host::Domain(domain)) = url.host() {
+            if let Some((_, ips)) = self.resolved_hosts.iter().find(|(host, _)| host == domain) {
+                return ips.iter().try_for_each(|ip| self.check_ip(domain, ip));
+            }
+            let port = url.port_or_known_default().unwrap_or(80);
+            // Resolution failures surface later as ordinary fetch errors
+            if let Ok(addrs) = tokio::net::lookup_host((domain, port)).await {
+                for addr in addrs {
+                    self.check_ip(domain, &addr.ip())?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// DNS resolver that refuses to hand non-public addresses to the connector.
+/// Every connection (initial request, redirects, pooled reconnects) resolves through it,
+/// so a hostname that rebinds to an internal address after the pre-flight check is still blocked.
+pub struct GuardedResolver {
+    policy: Arc<SsrfPolicy>,
+    exempt_hosts: Arc<Vec<String>>,
+}
+
+impl GuardedResolver {
+    pub fn new(policy: Arc<SsrfPolicy>) -> Self {
+        Self { policy, exempt_hosts: Arc::new(Vec::new()) }
+    }
+
+    /// Resolve these hosts without checks: configured outbound proxies commonly live on
+    /// private addresses. Scrape targets are still checked by `SsrfPolicy::check_url`.
+    pub fn with_exempt_hosts(mut self, hosts: Vec<String>) -> Self {
+        self.exempt_hosts = Arc::new(hosts);
+        self
+    }
+}
+
+impl Resolve for GuardedResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let policy = Arc::clone(&self.policy);
+        let exempt_hosts = Arc::clone(&self.exempt_hosts);
+        Box::pin(async move {
+            let host = name.as_str().to_string();
+            let exempt = exempt_hosts.iter().any(|h| h.eq_ignore_ascii_case(host.trim_end_matches('.')));
+            if !exempt {
+                policy.check_hostname(&host)?;
+            }
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host.as_str(), 0)).await?.collect();
+            for addr in addrs.iter().filter(|_| !exempt) {
+                policy.check_ip(&host, &addr.ip())?;
+            }
+            let addrs: Addrs = Box::new(addrs.into_iter());
+            Ok(addrs)
+        })
+    }
+}
+
+/// Redirect policy that re-checks IP-literal and blocked hostnames on every hop
+/// (IP literals never reach the resolver).
+/// Hops that go through a proxy are resolved by the proxy, never by `GuardedResolver`, so
+/// they are not followed: the redirect response is returned and the caller must run
+/// `SsrfPolicy::check_url` on its `Location` before following it (see `RustScraper`).
+/// Without a `proxy` configuration, any standard `HTTP_PROXY`-style variable counts as proxying every hop.
+pub fn redirect_policy(policy: Arc<SsrfPolicy>, proxy: Option<ProxyConfig>) -> reqwest::redirect::Policy {
+    let system_proxy = proxy.is_none() && crate::proxy::system_proxy_configured();
+    reqwest::redirect::Policy::custom(move |attempt| {
+        if attempt.previous().len() >= 10 {
+            return attempt.error("too many redirects");
+        }
+        if let Err(e) = policy.check_url_host(attempt.url()) {
+            return attempt.error(e);
+        }
+        let proxied = proxy.as_ref().map(|p| p.select(attempt.url()).is_some()).unwrap_or(system_proxy);
+        if proxied && policy.needs_resolution(attempt.url()) {
+            return attempt.stop();
+        }
+        attempt.follow()
+    })
+}
+
+/// Find an `SsrfError` in an error's source chain (reqwest wraps resolver/redirect errors)
+pub fn find_ssrf_error(err: &(dyn std::error::Error + 'static)) -> Option<SsrfError> {
+    let mut current: Option<&(dyn std::error::Error + 'static)> = Some(err);
+    while let Some(e) = current {
+        if let Some(ssrf) = e.downcast_ref::<SsrfError>() {
+            return Some(ssrf.clone());
+        }
+        current = e.source();
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_non_public_addresses() {
+        for ip in [
+            "127.0.0.1", "10.1.2.3", "172.16.0.1", "192.168.1.1", "169.254.169.254",
+            "100.100.100.200", "0.0.0.0", "::1", "fe80::1", "fd00:ec2::254", "::ffff:127.0.0.1",
+            "64:ff9b::a9fe:a9fe",
+        ] {
+            assert!(is_non_public(&ip.parse().unwrap()), "{} should be blocked", ip);
+        }
+        for ip in ["93.184.216.34", "1.1.1.1", "2606:4700:4700::1111"] {
+            assert!(!is_non_public(&ip.parse().unwrap()), "{} should be allowed", ip);
+        }
+    }
+
+    #[test]
+    fn test_url_host_checks() {
+        let policy = SsrfPolicy::default();
+        for url in [
+            "http://169.254.169.254/latest/meta-data/",
+            "http://localhost:6333/collections",
+            "http://[::1]:8080/",
+            "http://metadata.google.internal/computeMetadata/v1/",
+            "http://2130706433/", // 127.0.0.1 in decimal
+        ] {
+            assert!(policy.check_url_host(&Url::parse(url).unwrap()).is_err(), "{} should be blocked", url);
+        }
+        assert!(policy.check_url_host(&Url::parse("https://example.com/").unwrap()).is_ok());
+    }
+
+    #[test]
+    fn test_allowlist() {
+        let policy = SsrfPolicy::default().with_allowlist(["*.corp.example", "10.20.0.0/16", "qdrant"]);
+        assert!(policy.check_ip("wiki.corp.example", &"10.0.0.5".parse().unwrap()).is_ok());
+        assert!(policy.check_ip("anything", &"10.20.3.4".parse().unwrap()).is_ok());
+        assert!(policy.check_ip("anything", &"10.21.3.4".parse().unwrap()).is_err());
+        assert!(policy.check_hostname("qdrant").is_ok());
+        assert!(SsrfPolicy::disabled().check_url_host(&Url::parse("http://127.0.0.1/").unwrap()).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_proxied_redirect_to_private_name_is_blocked() {
+        use axum::{extract::Request, http::header::LOCATION, response::IntoResponse, Router};
+        use std::sync::Mutex;
+
+        // Plain HTTP proxy: redirects the scrape target to the internal name, serves anything else
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let seen = Arc::clone(&requests);
+        let target = "http://intranet.example/secret".to_string();
+        let app = Router::new().fallback(move |req: Request| {
+            let seen = Arc::clone(&seen);
+            let target = target.clone();
+            async move {
+                let uri = req.uri().to_string();
+                seen.lock().unwrap().push(uri.clone());
+                if uri.starts_with("http://start.example/") {
+                    (axum::http::StatusCode::FOUND, [(LOCATION, target)]).into_response()
+                } else {
+                    "internal secret".into_response()
+                }
+            }
+        });
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let proxy = ProxyConfig { default: Some(Url::parse(&format!("http://{}", addr)).unwrap()), ..Default::default() };
+        // The redirect target is an internal name; only the proxy would ever resolve it
+        let policy = Arc::new(SsrfPolicy::default().with_resolved_host("intranet.example", vec![IpAddr::V4(Ipv4Addr::new(10, 0, 0, 7))]));
+        let resolver = GuardedResolver::new(Arc::clone(&policy)).with_exempt_hosts(proxy.proxy_hosts());
+        let client = proxy
+            .apply(reqwest::Client::builder())
+            .dns_resolver(Arc::new(resolver))
+            .redirect(redirect_policy(Arc::clone(&policy), Some(proxy)))
+            .build()
+            .unwrap();
+        let scraper = crate::rust_scraper::RustScraper::with_client(client).with_ssrf(policy);
+
+        let err = scraper.scrape_url("http://start.example/page").await.unwrap_err();
+        assert!(err.downcast_ref::<SsrfError>().is_some(), "expected an SSRF refusal, got: {}", err);
+        assert_eq!(*requests.lock().unwrap(), vec!["http://start.example/page".to_string()]);
+    }
+}
@@ -0,0 +1,413 @@
+use crate::types::StructuredData;
+use scraper::{ElementRef, Html, Selector};
+use serde_json::{Map, Value};
+use url::Url;
+
+/// Cap on items kept per syntax (product listings can embed hundreds)
+const MAX_ITEMS: usize = 32;
+
+/// Types that describe the site or page furniture rather than the page's main entity
+const AUXILIARY_TYPES: &[&str] = &[
+    "Organization", "Person", "WebSite", "BreadcrumbList", "ListItem", "SiteNavigationElement",
+    "SearchAction", "ImageObject", "Brand", "Offer", "AggregateRating", "Rating", "PostalAddress",
+    "ContactPoint", "EntryPoint", "WPHeader", "WPFooter", "WPSideBar",
+];
+
+/// Metadata of the page's main entity, used to fill gaps left by the meta tags
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MainEntity {
+    pub headline: Option<String>,
+    pub author: Option<String>,
+    pub date_published: Option<String>,
+    pub date_modified: Option<String>,
+    pub publisher: Option<String>,
+}
+
+/// Collect JSON-LD, Microdata and RDFa items. Returns `None` when the page has none.
+pub fn extract(document: &Html, base: &Url) -> Option<StructuredData> {
+    let data = StructuredData {
+        json_ld: json_ld(document),
+        microdata: microdata(document, base),
+        rdfa: rdfa(document, base),
+    };
+    if data.json_ld.is_empty() && data.microdata.is_empty() && data.rdfa.is_empty() {
+        None
+    } else {
+        Some(data)
+    }
+}
+
+/// Pick the main entity (articles first, then any non-auxiliary item; JSON-LD before
+/// Microdata before RDFa) and read its headline, author, dates and publisher.
+/// `@id` references are resolved against the other JSON-LD items.
+pub fn main_entity(data: &StructuredData) -> MainEntity {
+    let items: Vec<&Value> = data.json_ld.iter().chain(&data.microdata).chain(&data.rdfa).collect();
+    let rank = |item: &Value| {
+        let types = types_of(item);
+        if types.iter().any(|t| t.ends_with("Article") || t.ends_with("Posting") || t == "Report") {
+            Some(0)
+        } else if !types.is_empty() && types.iter().all(|t| !AUXILIARY_TYPES.contains(&t.as_str())) {
+            Some(1)
+        } else {
+            None
+        }
+    };
+    let Some(main) = items
+        .iter()
+        .filter_map(|item| rank(item).map(|r| (r, *item)))
+        .min_by_key(|(r, _)| *r)
+        .map(|(_, item)| item)
+    else {
+        return MainEntity::default();
+    };
+
+    let resolve = |value: &'_ Value| -> Value {
+        match value.get("@id").and_then(Value::as_str) {
+            Some(id) if value.as_object().map(|o| o.len() == 1).unwrap_or(false) => items
+                .iter()
+                .find(|item| item.get("@id").and_then(Value::as_str) == Some(id))
+                .map(|item| (*item).clone())
+                .unwrap_or_else(|| value.clone()),
+            _ => value.clone(),
+        }
+    };
+    let names = |key: &str| {
+        let value = main.get(key)?;
+        let values: Vec<Value> = match value {
+            Value::Array(values) => values.iter().map(&resolve).collect(),
+            value => vec![resolve(value)],
+        };
+        let names: Vec<String> = values.iter().filter_map(name_of).collect();
+        (!names.is_empty()).then(|| names.join(", "))
+    };
+    let text = |keys: &[&str]| keys.iter().find_map(|key| main.get(*key).and_then(first_string));
+
+    MainEntity {
+        headline: text(&["headline", "name"]),
+        author: names("author").or_else(|| names("creator")),
+        date_published: text(&["datePublished", "uploadDate", "dateCreated"]),
+        date_modified: text(&["dateModified"]),
+        publisher: names("publisher"),
+    }
+}
+
+fn json_ld(document: &Html) -> Vec<Value> {
+    let Ok(sel) = Selector::parse(r#"script[type="application/ld+json" i]"#) else {
+        return Vec::new();
+    };
+    let mut items = Vec::new();
+    for script in document.select(&sel) {
+        let raw = script.text().collect::<String>();
+        let raw = raw
+            .trim()
+            .trim_start_matches("<!--")
+            .trim_start_matches("//<![CDATA[")
+            .trim_end_matches("-->")
+            .trim_end_matches("//]]>")
+            .trim();
+        // Raw newlines/tabs inside strings are invalid JSON but common in the wild
+        let parsed = serde_json::from_str::<Value>(raw)
+            .or_else(|_| serde_json::from_str::<Value>(&raw.replace(['\n', '\r', '\t'], " ")));
+        if let Ok(value) = parsed {
+            flatten_json_ld(value, &mut items);
+        }
+    }
+    items.truncate(MAX_ITEMS);
+    items
+}
+
+fn flatten_json_ld(value: Value, out: &mut Vec<Value>) {
+    match value {
+        Value::Array(values) => values.into_iter().for_each(|v| flatten_json_ld(v, out)),
+        Value::Object(mut map) => match map.remove("@graph") {
+            Some(graph) => flatten_json_ld(graph, out),
+            None => out.push(Value::Object(map)),
+        },
+        _ => {}
+    }
+}
+
+fn microdata(document: &Html, base: &Url) -> Vec<Value> {
+    let Ok(sel) = Selector::parse("[itemscope]") else {
+        return Vec::new();
+    };
+    document
+        .select(&sel)
+        // Items that are a property of another item are emitted nested inside it
+        .filter(|el| el.value().attr("itemprop").is_none())
+        .map(|el| {
+            let mut item = Map::new();
+            if let Some(types) = el.value().attr("itemtype").and_then(type_value) {
+                item.insert("@type".to_string(), types);
+            }
+            collect_props(el, base, &Syntax::Microdata, &mut item);
+            Value::Object(item)
+        })
+        .take(MAX_ITEMS)
+        .collect()
+}
+
+fn rdfa(document: &Html, base: &Url) -> Vec<Value> {
+    let Ok(sel) = Selector::parse("[typeof]") else {
+        return Vec::new();
+    };
+    document
+        .select(&sel)
+        .filter(|el| {
+            !el.ancestors()
+                .filter_map(ElementRef::wrap)
+                .any(|a| a.value().attr("typeof").is_some())
+        })
+        .map(|el| rdfa_item(el, base))
+        .take(MAX_ITEMS)
+        .collect()
+}
+
+fn rdfa_item(el: ElementRef, base: &Url) -> Value {
+    let mut item = Map::new();
+    if let Some(types) = el.value().attr("typeof").and_then(type_value) {
+        item.insert("@type".to_string(), types);
+    }
+    if let Some(id) = el.value().attr("resource").or_else(|| el.value().attr("about")) {
+        item.insert("@id".to_string(), Value::String(resolve_url(base, id)));
+    }
+    collect_props(el, base, &Syntax::Rdfa, &mut item);
+    Value::Object(item)
+}
+
+enum Syntax {
+    Microdata,
+    Rdfa,
+}
+
+impl Syntax {
+    fn prop_attr(&self) -> &'static str {
+        match self {
+            Syntax::Microdata => "itemprop",
+            Syntax::Rdfa => "property",
+        }
+    }
+
+    fn scope_attr(&self) -> &'static str {
+        match self {
+            Syntax::Microdata => "itemscope",
+            Syntax::Rdfa => "typeof",
+        }
+    }
+}
+
+/// Walk an item's subtree, adding each property to `item`. Nested items become nested
+/// objects and their own properties aren't attributed to the outer item.
+fn collect_props(el: ElementRef, base: &Url, syntax: &Syntax, item: &mut Map<String, Value>) {
+    for child in el.children().filter_map(ElementRef::wrap) {
+        let nested = child.value().attr(syntax.scope_attr()).is_some();
+        if let Some(props) = child.value().attr(syntax.prop_attr()) {
+            let value = if nested {
+                match syntax {
+                    Syntax::Microdata => {
+                        let mut inner = Map::new();
+                        if let Some(types) = child.value().attr("itemtype").and_then(type_value) {
+                            inner.insert("@type".to_string(), types);
+                        }
+                        collect_props(child, base, syntax, &mut inner);
+                        Value::Object(inner)
+                    }
+                    Syntax::Rdfa => rdfa_item(child, base),
+                }
+            } else {
+                match syntax {
+                    Syntax::Microdata => Value::String(microdata_value(child, base)),
+                    Syntax::Rdfa => Value::String(rdfa_value(child, base)),
+                }
+            };
+            for prop in props.split_whitespace() {
+                insert_prop(item, local_name(prop), value.clone());
+            }
+        }
+        if !nested {
+            collect_props(child, base, syntax, item);
+        }
+    }
+}
+
+/// Repeated properties collect into an array
+fn insert_prop(item: &mut Map<String, Value>, name: &str, value: Value) {
+    if value.as_str().map(str::is_empty).unwrap_or(false) {
+        return;
+    }
+    match item.get_mut(name) {
+        Some(Value::Array(values)) => values.push(value),
+        Some(existing) => {
+            let first = existing.take();
+            *existing = Value::Array(vec![first, value]);
+        }
+        None => {
+            item.insert(name.to_string(), value);
+        }
+    }
+}
+
+/// Property value per the HTML Microdata spec
+fn microdata_value(el: ElementRef, base: &Url) -> String {
+    let attr = |name: &str| el.value().attr(name).map(str::trim).unwrap_or_default().to_string();
+    match el.value().name() {
+        "meta" => attr("content"),
+        "audio" | "embed" | "iframe" | "img" | "source" | "track" | "video" => resolve_url(base, &attr("src")),
+        "a" | "area" | "link" => resolve_url(base, &attr("href")),
+        "object" => resolve_url(base, &attr("data")),
+        "data" | "meter" => attr("value"),
+        "time" if el.value().attr("datetime").is_some() => attr("datetime"),
+        _ => element_text(el),
+    }
+}
+
+/// Property value per RDFa Lite: `content`, then a link target, then the text
+fn rdfa_value(el: ElementRef, base: &Url) -> String {
+    let value = el.value();
+    if let Some(content) = value.attr("content") {
+        return content.trim().to_string();
+    }
+    if let Some(target) = ["href", "src", "resource"].iter().find_map(|a| value.attr(a)) {
+        return resolve_url(base, target);
+    }
+    if let Some(datetime) = value.attr("datetime") {
+        return datetime.trim().to_string();
+    }
+    element_text(el)
+}
+
+fn element_text(el: ElementRef) -> String {
+    el.text().collect::<Vec<_>>().join(" ").split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn resolve_url(base: &Url, raw: &str) -> String {
+    let raw = raw.trim();
+    if raw.is_empty() {
+        return String::new();
+    }
+    base.join(raw).map(|u| u.to_string()).unwrap_or_else(|_| raw.to_string())
+}
+
+/// `https://schema.org/NewsArticle` / `schema:NewsArticle` → `NewsArticle`; several types become an array
+fn type_value(raw: &str) -> Option<Value> {
+    let types: Vec<Value> = raw
+        .split_whitespace()
+        .map(|t| Value::String(local_name(t).to_string()))
+        .collect();
+    match types.len() {
+        0 => None,
+        1 => types.into_iter().next(),
+        _ => Some(Value::Array(types)),
+    }
+}
+
+/// Strip a vocabulary URL or CURIE prefix from a type or property name
+fn local_name(name: &str) -> &str {
+    let name = name.trim_end_matches('/');
+    let name = name.rsplit(['/', '#']).next().unwrap_or(name);
+    name.rsplit(':').next().unwrap_or(name)
+}
+
+fn types_of(item: &Value) -> Vec<String> {
+    match item.get("@type") {
+        Some(Value::String(t)) => vec![local_name(t).to_string()],
+        Some(Value::Array(ts)) => ts.iter().filter_map(Value::as_str).map(|t| local_name(t).to_string()).collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn first_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.split_whitespace().collect::<Vec<_>>().join(" ")).filter(|s| !s.is_empty()),
+        Value::Array(values) => values.iter().find_map(first_string),
+        Value::Object(map) => map.get("@value").and_then(first_string),
+        _ => None,
+    }
+}
+
+/// Display name of a person/organization given as a string or an object
+fn name_of(value: &Value) -> Option<String> {
+    match value {
+        Value::Object(map) => map
+            .get("name")
+            .and_then(first_string)
+            .or_else(|| match (map.get("givenName"), map.get("familyName")) {
+                (Some(given), Some(family)) => Some(format!("{} {}", first_string(given)?, first_string(family)?)),
+                _ => None,
+            }),
+        // Bare URLs are identifiers, not names
+        value => first_string(value).filter(|s| !s.starts_with("http://") && !s.starts_with("https://")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(html: &str) -> Option<StructuredData> {
+        extract(&Html::parse_document(html), &Url::parse("https://news.example.com/2024/story").unwrap())
+    }
+
+    #[test]
+    fn test_json_ld_graph_and_references() {
+        let data = parse(
+            r#"<html><head><script type="application/ld+json">
+            {"@context": "https://schema.org", "@graph": [
+              {"@type": "Organization", "@id": "https://news.example.com/#org", "name": "Example News"},
+              {"@type": "WebSite", "name": "Example"},
+              {"@type": ["NewsArticle"], "headline": "Rates rise
+                again", "datePublished": "2024-03-01T08:00:00Z", "dateModified": "2024-03-02",
+               "author": [{"@type": "Person", "name": "Ann Lee"}, {"@type": "Person", "givenName": "Bo", "familyName": "Chen"}],
+               "publisher": {"@id": "https://news.example.com/#org"}}
+            ]}</script></head><body></body></html>"#,
+        )
+        .unwrap();
+        assert_eq!(data.json_ld.len(), 3);
+        assert!(data.microdata.is_empty());
+
+        let main = main_entity(&data);
+        assert_eq!(main.headline.as_deref(), Some("Rates rise again"));
+        assert_eq!(main.author.as_deref(), Some("Ann Lee, Bo Chen"));
+        assert_eq!(main.date_published.as_deref(), Some("2024-03-01T08:00:00Z"));
+        assert_eq!(main.date_modified.as_deref(), Some("2024-03-02"));
+        assert_eq!(main.publisher.as_deref(), Some("Example News"));
+
+        assert!(parse("<p>no metadata</p>").is_none());
+    }
+
+    #[test]
+    fn test_microdata_and_rdfa() {
+        let data = parse(
+            r#"<div itemscope itemtype="https://schema.org/Recipe">
+                 <h1 itemprop="name">Pancakes</h1>
+                 <div itemprop="author" itemscope itemtype="https://schema.org/Person"><span itemprop="name">Cook</span></div>
+                 <time itemprop="datePublished" datetime="2023-05-01">May 1</time>
+                 <img itemprop="image" src="/p.jpg">
+                 <span itemprop="recipeIngredient">Flour</span><span itemprop="recipeIngredient">Milk</span>
+               </div>
+               <div vocab="https://schema.org/" typeof="BlogPosting">
+                 <h2 property="headline">Post</h2>
+                 <div property="author" typeof="Person"><span property="name">Dee</span></div>
+                 <meta property="dateModified" content="2023-06-01">
+               </div>"#,
+        )
+        .unwrap();
+
+        let recipe = &data.microdata[0];
+        assert_eq!(recipe["@type"], "Recipe");
+        assert_eq!(recipe["author"]["name"], "Cook");
+        assert_eq!(recipe["image"], "https://news.example.com/p.jpg");
+        assert_eq!(recipe["recipeIngredient"], serde_json::json!(["Flour", "Milk"]));
+        // The nested Person's name isn't attributed to the recipe
+        assert_eq!(recipe["name"], "Pancakes");
+
+        assert_eq!(data.rdfa[0]["@type"], "BlogPosting");
+        assert_eq!(data.rdfa[0]["author"]["name"], "Dee");
+
+        // Articles win over other types
+        let main = main_entity(&data);
+        assert_eq!(main.headline.as_deref(), Some("Post"));
+        assert_eq!(main.author.as_deref(), Some("Dee"));
+        assert_eq!(main.date_modified.as_deref(), Some("2023-06-01"));
+    }
+}
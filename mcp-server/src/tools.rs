@@ -0,0 +1,950 @@
+//! The MCP tools: names, descriptions, typed arguments and dispatch. Both front-ends
+//! (the rmcp stdio service and the HTTP `/mcp/tools` + `/mcp/call` routes) are thin
+//! adapters over [`list_tools`] and [`call_tool`], so they can't drift apart.
+
+use crate::error::{ErrorInfo, ErrorKind};
+use crate::history::{EntryType, HistoryEntry};
+use crate::types::{CacheStats, CrawlResponse, ScrapeResponse, SearchResult, SitemapResponse};
+use crate::{crawl, pagination, scrape, search, sitemap, AppState};
+use schemars::gen::SchemaSettings;
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::sync::Arc;
+use tracing::{error, info};
+
+const SEARCH_WEB_DESCRIPTION: &str = "Search the web using SearXNG federated search. Returns ranked results with domain classification and automatic query optimization.\n\nKEY FEATURES:\n• Auto-rewrites developer queries (e.g., 'rust docs' → adds 'site:doc.rust-lang.org')\n• Duplicate detection warns if query searched within 6 hours\n• Extracts domains and classifies sources (docs/repo/blog/news)\n• Shows query suggestions and instant answers when available\n\nAGENT BEST PRACTICES:\n1. Use categories='it' for programming/tech queries (gets better results)\n2. Start with max_results=5-10, increase to 20-50 for comprehensive research\n3. Check duplicate warnings - use research_history tool instead if duplicate detected\n4. Look for 'Query Optimization Tips' in output for better refinements\n5. Use time_range='week' for recent news, 'month' for current tech trends";
+
+const SCRAPE_URL_DESCRIPTION: &str = "Extract clean content from URLs with automatic code block detection, quality scoring, and metadata extraction.\n\nKEY FEATURES:\n• Extracts code blocks with language detection (returns array of {language, code})\n• Quality scoring (0.0-1.0) indicates content reliability\n• Automatic metadata: title, author, publish date, reading time\n• Citation-ready: Use [N] markers to reference extracted links\n• JSON mode: Set output_format='json' for structured data with all metadata\n• Markdown mode: Set output_format='markdown' for CommonMark with headings, code fences, lists, tables and links intact\n\nAGENT BEST PRACTICES:\n1. For code examples: Use output_format='json' to get code_blocks array\n2. Set max_chars based on need: 3000-5000 (summary), 10000 (article), 30000+ (docs)\n3. Check extraction_score: <0.4 = low quality, >0.7 = high quality\n4. Check warnings array: 'short_content' = likely JS-heavy, 'low_extraction_score' = may need browser\n5. For documentation sites: Increase max_chars to 40000+ to capture full tutorials\n6. Use content_links_only=false only when you need navigation/sitemap links";
+
+const RESEARCH_HISTORY_DESCRIPTION: &str = "Search past research using semantic similarity (vector search). Finds related searches/scrapes even with different wording.\n\nKEY FEATURES:\n• Semantic search finds related topics (e.g., 'rust tutorials' finds 'learning rust')\n• Returns similarity scores (0.0-1.0) showing relevance\n• Shows when each search was performed (helps avoid stale info)\n• Includes summaries and domains from past research\n• Persists across sessions (uses Qdrant vector DB)\n• Filter by type: 'search' for web searches, 'scrape' for scraped pages\n\nAGENT BEST PRACTICES:\n1. **Use FIRST before new searches** - Saves API calls and finds existing research\n2. Set threshold=0.6-0.7 for broad exploration, 0.75-0.85 for specific matches\n3. Use entry_type='search' to find past searches, 'scrape' for scraped content history\n4. Check timestamps: Recent results (<24h) are more reliable than old ones\n5. Use limit=5-10 for quick checks, 20+ for comprehensive review\n6. If similarity >0.9, you likely already researched this exact topic\n7. Combine with search_web/scrape_url: Check history first, then fetch if not found\n\nNOTE: Only available when Qdrant is running (QDRANT_URL configured)";
+
+const CRAWL_SITE_DESCRIPTION: &str = "Crawl a site breadth-first from a seed URL, following in-content links within depth/page limits, and return a summary of every page plus a table of contents (page titles and their h2/h3 headings, ordered by path).\n\nAGENT BEST PRACTICES:\n1. Use it to map a documentation site or tutorial series in one call instead of many scrape_url calls\n2. Restrict scope with include_paths (e.g. ['/docs/']) and exclude_paths\n3. Start with max_depth 1-2 and raise max_pages only when needed; then scrape_url the pages you actually need\n4. Pages with the same canonical URL are crawled once; robots.txt and per-host rate limits apply";
+
+const LIST_SITE_PAGES_DESCRIPTION: &str = "List a site's pages from its sitemaps (robots.txt Sitemap: lines or /sitemap.xml, including gzipped sitemaps and sitemap indexes), with lastmod and priority.\n\nAGENT BEST PRACTICES:\n1. Use it to enumerate a site or docs section without link-following, then scrape_url the pages you need\n2. Narrow with path_prefix (e.g. '/docs/') and modified_since (YYYY-MM-DD) to find recently changed pages\n3. Prefer crawl_site when a site has no sitemap";
+
+const CACHE_STATS_DESCRIPTION: &str = "Report cache usage: entry counts for the in-memory search/scrape caches and, when DISK_CACHE_DIR is set, the persistent disk cache's size, cap, TTL, hit/miss counts and evictions.\n\nUse it to check whether repeated lookups are being served from cache across server restarts.";
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct SearchWebArgs {
+    /// Search query. TIP: Use specific terms and quotes for exact phrases. Example: 'rust async' instead of just 'rust'
+    pub query: String,
+    /// Comma-separated engines (e.g., 'google,bing'). TIP: Omit for default. Use 'google,bing' for English content, add 'duckduckgo' for privacy-focused results
+    pub engines: Option<String>,
+    /// Comma-separated categories. WHEN TO USE: 'it' for programming/tech, 'news' for current events, 'science' for research papers, 'general' for mixed. Omit for all categories
+    pub categories: Option<String>,
+    /// Language code (e.g., 'en', 'es', 'fr'). TIP: Use 'en' for English-only results, omit for multilingual
+    pub language: Option<String>,
+    /// Safe search: 0=off, 1=moderate (recommended), 2=strict. Default env setting usually sufficient
+    #[schemars(range(min = 0, max = 2))]
+    pub safesearch: Option<u8>,
+    /// Filter by recency. WHEN TO USE: 'day' for breaking news, 'week' for current events, 'month' for recent tech/trends, 'year' for last 12 months. Omit for all-time results
+    pub time_range: Option<String>,
+    /// Page number for pagination. TIP: Start with page 1, use page 2+ only if initial results insufficient
+    #[schemars(range(min = 1))]
+    pub pageno: Option<u32>,
+    /// Max results to return. GUIDANCE: 5-10 for quick facts, 15-25 for balanced research, 30-50 for comprehensive surveys. Default 10 is good for most queries. Higher = more tokens
+    #[serde(default = "default_max_results")]
+    #[schemars(range(min = 1, max = 100))]
+    pub max_results: usize,
+}
+
+/// Output of `scrape_url`
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ScrapeFormat {
+    #[default]
+    Text,
+    Json,
+    Markdown,
+}
+
+/// Output of the tools that have no markdown rendering
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum TableFormat {
+    #[default]
+    Markdown,
+    Csv,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ScrapeUrlArgs {
+    /// Full URL to scrape. TIP: Works best with article/blog/docs pages. May have limited content for JS-heavy sites or paywalls
+    pub url: String,
+    /// Extract main content links only (true, default) or all page links (false). GUIDANCE: Keep true for articles/blogs to avoid nav clutter. Set false only when you need site-wide links like sitemaps
+    #[serde(default = "default_true")]
+    pub content_links_only: bool,
+    /// Max links in Sources section. GUIDANCE: 20-30 for focused articles, 50-100 (default) for comprehensive pages, 200+ for navigation-heavy docs. Lower = faster response
+    #[schemars(range(min = 1, max = 500))]
+    pub max_links: Option<usize>,
+    /// Max content length. WHEN TO ADJUST: 3000-5000 for quick summaries, 10000 (default) for standard articles, 20000-30000 for long-form content, 40000+ for full documentation pages. Truncated content shows a warning
+    #[schemars(range(min = 100, max = 50000))]
+    pub max_chars: Option<usize>,
+    /// Max age in seconds of a cached copy to return without revalidating (default 1800). Older copies are revalidated with ETag/Last-Modified and reused if unchanged. Use 0 to always revalidate
+    pub max_age: Option<u64>,
+    /// Ignore the cache and refetch the page. Prefer max_age=0, which reuses unchanged pages via a cheap 304 check
+    #[serde(default)]
+    pub force_refresh: bool,
+    /// Output format. 'text' (default) returns formatted markdown for humans. 'json' returns structured JSON for agents/parsing. 'markdown' returns the main content as CommonMark with headings, language-tagged code fences, lists, tables and inline links preserved. AGENT TIP: Use 'json' to get extraction_score, truncated flag, code_blocks array, and all metadata as machine-readable fields; use 'markdown' to quote documentation accurately
+    #[serde(default)]
+    pub output_format: ScrapeFormat,
+    /// How tables are rendered in text output: 'markdown' (default) pipe tables or 'csv'. JSON output always includes a structured tables array (caption, headers, rows)
+    #[serde(default)]
+    pub table_format: TableFormat,
+    /// Follow rel=next, pagination nav and ?page=N links and merge subsequent pages (long tutorials, forum threads) into one result; 'pages' gives each page's URL and char range in clean_content
+    #[serde(default)]
+    pub follow_pagination: bool,
+    /// Maximum pages to fetch in total when follow_pagination is set (default 5, max 20)
+    #[serde(default = "default_scrape_max_pages")]
+    #[schemars(range(min = 1, max = 20))]
+    pub max_pages: usize,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ResearchHistoryArgs {
+    /// Topic or question to search in history. Use natural language. Example: 'rust async web scraping' or 'how to configure Qdrant'
+    pub query: String,
+    /// Max number of results to return. GUIDANCE: 5-10 for quick context, 20+ for comprehensive review
+    #[serde(default = "default_history_limit")]
+    #[schemars(range(min = 1, max = 50))]
+    pub limit: usize,
+    /// Similarity threshold (0-1). GUIDANCE: 0.6-0.7 for broad topics, 0.75-0.85 for specific queries, 0.9+ for near-exact matches
+    #[serde(default = "default_threshold")]
+    #[schemars(range(min = 0, max = 1))]
+    pub threshold: f64,
+    /// Filter by entry type. Use 'search' for past web searches, 'scrape' for scraped pages. Omit to search both types.
+    pub entry_type: Option<EntryType>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct CrawlSiteArgs {
+    /// Seed URL to start crawling from (e.g. a documentation index)
+    pub url: String,
+    /// How many links away from the seed to follow (default 2, max 5)
+    #[serde(default = "default_crawl_max_depth")]
+    #[schemars(range(min = 0, max = 5))]
+    pub max_depth: usize,
+    /// Maximum pages to fetch (default 20, max 100)
+    #[serde(default = "default_crawl_max_pages")]
+    #[schemars(range(min = 1, max = 100))]
+    pub max_pages: usize,
+    /// Only follow links whose path starts with one of these prefixes, e.g. ["/docs/"]
+    #[serde(default)]
+    pub include_paths: Vec<String>,
+    /// Never follow links whose path starts with one of these prefixes, e.g. ["/blog/", "/api/v1/"]
+    #[serde(default)]
+    pub exclude_paths: Vec<String>,
+    /// Stay on the seed URL's host (default true)
+    #[serde(default = "default_true")]
+    pub same_host: bool,
+    /// 'text' (default) returns page summaries and a table of contents; 'json' returns the full crawl result
+    #[serde(default)]
+    pub output_format: OutputFormat,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ListSitePagesArgs {
+    /// Any page of the site (sitemaps are discovered from robots.txt, then /sitemap.xml), or a sitemap URL (.xml / .xml.gz)
+    pub url: String,
+    /// Only return URLs whose path starts with this prefix, e.g. '/docs/'
+    pub path_prefix: Option<String>,
+    /// Only return URLs whose lastmod is on or after this date (YYYY-MM-DD); URLs without lastmod are excluded
+    pub modified_since: Option<String>,
+    /// Maximum URLs to return (default 500, max 5000)
+    #[serde(default = "default_sitemap_limit")]
+    #[schemars(range(min = 1, max = 5000))]
+    pub limit: usize,
+    /// 'text' (default) lists one URL per line; 'json' returns urls with lastmod/priority/changefreq plus the sitemaps read
+    #[serde(default)]
+    pub output_format: OutputFormat,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct CacheStatsArgs {}
+
+/// Structured result of `search_web`
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct SearchWebOutput {
+    pub query: String,
+    /// The query actually sent to SearXNG when it was rewritten
+    pub rewritten_query: Option<String>,
+    /// Results found, before `max_results` was applied
+    pub total_results: usize,
+    pub results: Vec<SearchResult>,
+    /// Instant answers from SearXNG
+    pub answers: Vec<String>,
+    pub suggestions: Vec<String>,
+    /// Spelling corrections ("Did you mean")
+    pub corrections: Vec<String>,
+    pub unresponsive_engines: Vec<String>,
+    /// Set when a similar query was searched recently
+    pub duplicate_warning: Option<String>,
+}
+
+/// Structured result of `research_history`
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ResearchHistoryOutput {
+    pub query: String,
+    pub results: Vec<HistoryMatch>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct HistoryMatch {
+    /// Similarity to the query (0-1)
+    pub similarity: f32,
+    #[serde(flatten)]
+    pub entry: HistoryEntry,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_max_results() -> usize {
+    10
+}
+
+fn default_scrape_max_pages() -> usize {
+    pagination::DEFAULT_MAX_PAGES
+}
+
+fn default_history_limit() -> usize {
+    10
+}
+
+fn default_threshold() -> f64 {
+    0.7
+}
+
+fn default_crawl_max_depth() -> usize {
+    crawl::DEFAULT_MAX_DEPTH
+}
+
+fn default_crawl_max_pages() -> usize {
+    crawl::DEFAULT_MAX_PAGES
+}
+
+fn default_sitemap_limit() -> usize {
+    sitemap::DEFAULT_LIMIT
+}
+
+/// A tool as advertised by both front-ends
+#[derive(Debug, Clone)]
+pub struct ToolSpec {
+    pub name: &'static str,
+    pub description: &'static str,
+    /// JSON Schema of the tool's arguments, generated from its argument struct
+    pub input_schema: Map<String, Value>,
+    /// JSON Schema of the tool's structured content
+    pub output_schema: Map<String, Value>,
+}
+
+impl ToolSpec {
+    fn new<A: JsonSchema, O: JsonSchema>(name: &'static str, description: &'static str) -> Self {
+        Self { name, description, input_schema: input_schema::<A>(), output_schema: output_schema::<O>() }
+    }
+}
+
+/// Every tool, in the order they're listed to clients. `research_history` is always
+/// listed; without `QDRANT_URL` calling it reports `not_configured`.
+pub fn list_tools() -> Vec<ToolSpec> {
+    vec![
+        ToolSpec::new::<SearchWebArgs, SearchWebOutput>("search_web", SEARCH_WEB_DESCRIPTION),
+        ToolSpec {
+            output_schema: scrape_output_schema(),
+            ..ToolSpec::new::<ScrapeUrlArgs, ScrapeResponse>("scrape_url", SCRAPE_URL_DESCRIPTION)
+        },
+        ToolSpec::new::<ResearchHistoryArgs, ResearchHistoryOutput>("research_history", RESEARCH_HISTORY_DESCRIPTION),
+        ToolSpec::new::<CrawlSiteArgs, CrawlResponse>("crawl_site", CRAWL_SITE_DESCRIPTION),
+        ToolSpec::new::<ListSitePagesArgs, SitemapResponse>("list_site_pages", LIST_SITE_PAGES_DESCRIPTION),
+        ToolSpec::new::<CacheStatsArgs, CacheStats>("cache_stats", CACHE_STATS_DESCRIPTION),
+    ]
+}
+
+/// `ScrapeResponse` fields left out of `scrape_url`'s structured content (the raw HTML)
+const SCRAPE_OMITTED_FIELDS: &[&str] = &["content"];
+
+/// Output schema of `scrape_url`: `ScrapeResponse` without `SCRAPE_OMITTED_FIELDS`
+fn scrape_output_schema() -> Map<String, Value> {
+    let mut schema = output_schema::<ScrapeResponse>();
+    if let Some(Value::Object(properties)) = schema.get_mut("properties") {
+        SCRAPE_OMITTED_FIELDS.iter().for_each(|field| {
+            properties.remove(*field);
+        });
+    }
+    if let Some(Value::Array(required)) = schema.get_mut("required") {
+        required.retain(|field| !SCRAPE_OMITTED_FIELDS.contains(&field.as_str().unwrap_or_default()));
+    }
+    schema
+}
+
+/// Structured content of `scrape_url`: the page without raw HTML and with `clean_content`
+/// cut to `max_chars`, like the text output (`truncated` / `actual_chars` tell the full size)
+fn scrape_structured(content: ScrapeResponse, max_chars: usize) -> Value {
+    let clean_content = content.clean_content.chars().take(max_chars).collect();
+    let mut value = serde_json::to_value(ScrapeResponse { content: String::new(), clean_content, ..content }).unwrap_or_default();
+    if let Value::Object(map) = &mut value {
+        SCRAPE_OMITTED_FIELDS.iter().for_each(|field| {
+            map.remove(*field);
+        });
+    }
+    value
+}
+
+/// Self-contained JSON Schema for an argument struct, with optional fields typed without
+/// `null` as MCP clients expect
+pub fn input_schema<A: JsonSchema>() -> Map<String, Value> {
+    schema_for::<A>(false)
+}
+
+/// Self-contained JSON Schema for structured content. `None` fields serialize as `null`,
+/// so optional fields allow it and results validate against the schema.
+pub fn output_schema<O: JsonSchema>() -> Map<String, Value> {
+    schema_for::<O>(true)
+}
+
+/// Subschemas inlined, no `$schema`, title or root description, and schemars' numeric
+/// formats (`uint`, `float`, ...) dropped since strict validators reject unknown formats
+fn schema_for<T: JsonSchema>(nullable_options: bool) -> Map<String, Value> {
+    let generator = SchemaSettings::draft07()
+        .with(|s| {
+            s.option_add_null_type = nullable_options;
+            s.inline_subschemas = true;
+            s.meta_schema = None;
+        })
+        .into_generator();
+    let mut schema = match serde_json::to_value(generator.into_root_schema_for::<T>()) {
+        Ok(Value::Object(map)) => map,
+        _ => Map::new(),
+    };
+    schema.remove("title");
+    schema.remove("description");
+    schema.entry("properties").or_insert_with(|| Value::Object(Map::new()));
+    for value in schema.values_mut() {
+        strip_numeric_formats(value);
+    }
+    schema
+}
+
+fn strip_numeric_formats(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            let numeric = map.get("type").map(|t| {
+                let is_numeric = |t: &Value| matches!(t.as_str(), Some("integer" | "number"));
+                is_numeric(t) || t.as_array().map(|ts| ts.iter().any(is_numeric)).unwrap_or(false)
+            });
+            if numeric == Some(true) {
+                map.remove("format");
+            }
+            map.values_mut().for_each(strip_numeric_formats);
+        }
+        Value::Array(items) => items.iter_mut().for_each(strip_numeric_formats),
+        _ => {}
+    }
+}
+
+/// A completed tool call: human-readable `text` plus `structured` content matching the
+/// tool's output schema. `error` is set instead for tool-level failures (`isError`
+/// results), in which case `text` is [`ErrorInfo::describe`] output and there is no
+/// structured content, since an error wouldn't match the output schema.
+#[derive(Debug, Clone)]
+pub struct ToolOutput {
+    pub text: String,
+    pub structured: Option<Value>,
+    pub error: Option<ErrorInfo>,
+}
+
+impl ToolOutput {
+    pub fn new<T: Serialize>(text: impl Into<String>, structured: &T) -> Self {
+        Self { text: text.into(), structured: serde_json::to_value(structured).ok(), error: None }
+    }
+
+    pub fn failure(context: &str, info: ErrorInfo) -> Self {
+        Self { text: info.describe(context), structured: None, error: Some(info) }
+    }
+
+    pub fn into_call_tool_result(self) -> rmcp::model::CallToolResult {
+        let structured_content = self.structured;
+        let content = vec![rmcp::model::Content::text(self.text)];
+        let mut result = match self.error {
+            None => rmcp::model::CallToolResult::success(content),
+            Some(_) => rmcp::model::CallToolResult::error(content),
+        };
+        result.structured_content = structured_content;
+        result
+    }
+}
+
+/// A call that failed before the tool ran, reported as a protocol error rather than an `isError` result
+#[derive(Debug, thiserror::Error)]
+pub enum ToolCallError {
+    #[error("Unknown tool: {0}")]
+    UnknownTool(String),
+    /// Arguments that don't match the schema, or a URL that can't be fetched at all
+    #[error("{message}")]
+    InvalidParams { message: String, info: Option<ErrorInfo> },
+}
+
+impl ToolCallError {
+    fn invalid(message: impl Into<String>) -> Self {
+        Self::InvalidParams { message: message.into(), info: None }
+    }
+}
+
+/// Categorize a failed operation: invalid URLs are invalid params, anything else is an
+/// `isError` result carrying the error code and retry hint
+fn failure(context: &str, err: &anyhow::Error) -> Result<ToolOutput, ToolCallError> {
+    error!("{}: {}", context, err);
+    let info = ErrorInfo::from_error(err);
+    if info.code == ErrorKind::InvalidUrl {
+        return Err(ToolCallError::InvalidParams { message: info.message.clone(), info: Some(info) });
+    }
+    Ok(ToolOutput::failure(context, info))
+}
+
+fn parse_args<A: DeserializeOwned>(name: &str, arguments: Value) -> Result<A, ToolCallError> {
+    let arguments = match arguments {
+        Value::Null => Value::Object(Map::new()),
+        Value::Object(map) => Value::Object(map),
+        _ => return Err(ToolCallError::invalid(format!("Arguments for {} must be an object", name))),
+    };
+    serde_json::from_value(arguments).map_err(|e| ToolCallError::invalid(format!("Invalid arguments for {}: {}", name, e)))
+}
+
+fn to_json<T: Serialize>(value: &T) -> String {
+    serde_json::to_string_pretty(value).unwrap_or_else(|e| format!(r#"{{"error": "Failed to serialize: {}"}}"#, e))
+}
+
+/// Run a tool. `arguments` is the call's arguments object (`null` when absent).
+pub async fn call_tool(state: &Arc<AppState>, name: &str, arguments: Value) -> Result<ToolOutput, ToolCallError> {
+    info!("MCP tool call: {} with args: {:?}", name, arguments);
+    match name {
+        "search_web" => search_web(state, parse_args(name, arguments)?).await,
+        "scrape_url" => scrape_url(state, parse_args(name, arguments)?).await,
+        "research_history" => research_history(state, parse_args(name, arguments)?).await,
+        "crawl_site" => crawl_site(state, parse_args(name, arguments)?).await,
+        "list_site_pages" => list_site_pages(state, parse_args(name, arguments)?).await,
+        "cache_stats" => {
+            let CacheStatsArgs {} = parse_args(name, arguments)?;
+            let stats = state.cache_stats();
+            Ok(ToolOutput::new(to_json(&stats), &stats))
+        }
+        _ => Err(ToolCallError::UnknownTool(name.to_string())),
+    }
+}
+
+async fn search_web(state: &Arc<AppState>, args: SearchWebArgs) -> Result<ToolOutput, ToolCallError> {
+    let non_empty = |s: Option<String>| s.filter(|s| !s.trim().is_empty());
+    let overrides = search::SearchParamOverrides {
+        engines: non_empty(args.engines),
+        categories: non_empty(args.categories),
+        language: non_empty(args.language),
+        safesearch: args.safesearch.filter(|n| *n <= 2),
+        time_range: non_empty(args.time_range),
+        pageno: args.pageno,
+    };
+    let query = args.query.as_str();
+    let max_results = args.max_results;
+
+    let (results, extras) = match search::search_web_with_params(state, query, Some(overrides)).await {
+        Ok(found) => found,
+        Err(e) => return failure("Search failed", &e),
+    };
+    let output = SearchWebOutput {
+        query: query.to_string(),
+        rewritten_query: extras.query_rewrite.as_ref().and_then(|r| r.rewritten.clone()),
+        total_results: results.len(),
+        results: results.iter().take(max_results).cloned().collect(),
+        answers: extras.answers.clone(),
+        suggestions: extras.suggestions.clone(),
+        corrections: extras.corrections.clone(),
+        unresponsive_engines: extras.unresponsive_engines.clone(),
+        duplicate_warning: extras.duplicate_warning.clone(),
+    };
+
+    if results.is_empty() {
+        let mut text = format!("No search results found for query: '{}'\n\n", query);
+
+        // Show suggestions/corrections to help user refine query
+        if !extras.suggestions.is_empty() {
+            text.push_str(&format!("**Suggestions:** {}\n", extras.suggestions.join(", ")));
+        }
+        if !extras.corrections.is_empty() {
+            text.push_str(&format!("**Did you mean:** {}\n", extras.corrections.join(", ")));
+        }
+        if !extras.unresponsive_engines.is_empty() {
+            text.push_str(&format!("\n**Note:** {} search engine(s) did not respond. Try different engines or retry.\n", extras.unresponsive_engines.len()));
+        }
+        return Ok(ToolOutput::new(text, &output));
+    }
+
+    let result_count = results.len();
+    let mut text = String::new();
+
+    // Phase 2: Show duplicate warning if present
+    if let Some(dup_warning) = &extras.duplicate_warning {
+        text.push_str(&format!("{}\n\n", dup_warning));
+    }
+
+    // Phase 2: Show query rewrite info if query was enhanced
+    if let Some(ref rewrite) = extras.query_rewrite {
+        if rewrite.was_rewritten() {
+            text.push_str(&format!("🔍 **Query Enhanced:** '{}' → '{}'\n\n", rewrite.original, rewrite.best_query()));
+        } else if rewrite.is_developer_query && !rewrite.suggestions.is_empty() {
+            text.push_str("💡 **Query Optimization Tips:**\n");
+            for (i, suggestion) in rewrite.suggestions.iter().take(2).enumerate() {
+                text.push_str(&format!("   {}. {}\n", i + 1, suggestion));
+            }
+            text.push('\n');
+        }
+    }
+
+    text.push_str(&format!("Found {} search results for '{}':", result_count, query));
+    if result_count > max_results {
+        text.push_str(&format!(" (showing top {})\n", max_results));
+    }
+    text.push_str("\n\n");
+
+    // Show instant answers first if available
+    if !extras.answers.is_empty() {
+        text.push_str("**Instant Answers:**\n");
+        for answer in &extras.answers {
+            text.push_str(&format!("📌 {}\n\n", answer));
+        }
+    }
+
+    for (i, result) in output.results.iter().enumerate() {
+        text.push_str(&format!(
+            "{}. **{}**\n   URL: {}\n   Snippet: {}\n\n",
+            i + 1,
+            result.title,
+            result.url,
+            result.content.chars().take(200).collect::<String>()
+        ));
+    }
+
+    // Show helpful metadata at the end
+    if !extras.suggestions.is_empty() {
+        text.push_str(&format!("\n**Related searches:** {}\n", extras.suggestions.join(", ")));
+    }
+    if !extras.unresponsive_engines.is_empty() {
+        text.push_str(&format!("\n⚠️ **Note:** {} engine(s) did not respond (may affect completeness)\n", extras.unresponsive_engines.len()));
+    }
+
+    Ok(ToolOutput::new(text, &output))
+}
+
+async fn scrape_url(state: &Arc<AppState>, args: ScrapeUrlArgs) -> Result<ToolOutput, ToolCallError> {
+    let options = scrape::ScrapeOptions {
+        max_age: args.max_age.map(std::time::Duration::from_secs),
+        force_refresh: args.force_refresh,
+    };
+    let result = if args.follow_pagination {
+        scrape::scrape_url_paginated(state, &args.url, options, args.max_pages).await
+    } else {
+        scrape::scrape_url_with_options(state, &args.url, options).await
+    };
+    let mut content = match result {
+        Ok(content) => content,
+        Err(e) => return failure("Scraping failed", &e),
+    };
+    info!("Scraped content: {} words, {} chars clean_content, score: {:?}",
+          content.word_count, content.clean_content.len(), content.extraction_score);
+
+    let max_chars = args
+        .max_chars
+        .or_else(|| std::env::var("MAX_CONTENT_CHARS").ok().and_then(|s| s.parse().ok()))
+        .unwrap_or(10000);
+
+    // Set truncation metadata (Priority 1)
+    content.actual_chars = content.clean_content.len();
+    content.max_chars_limit = Some(max_chars);
+    content.truncated = content.clean_content.len() > max_chars;
+
+    if content.truncated {
+        content.warnings.push("content_truncated".to_string());
+    }
+    if content.word_count < 50 {
+        content.warnings.push("short_content".to_string());
+    }
+    if content.extraction_score.map(|s| s < 0.4).unwrap_or(false) {
+        content.warnings.push("low_extraction_score".to_string());
+    }
+
+    let text = match args.output_format {
+        ScrapeFormat::Json => to_json(&content),
+        ScrapeFormat::Markdown => scrape::markdown_output(state, &content, max_chars).await,
+        ScrapeFormat::Text => {
+            let max_links = args
+                .max_links
+                .or_else(|| std::env::var("MAX_LINKS").ok().and_then(|s| s.parse().ok()))
+                .unwrap_or(100);
+            render_scrape_text(&content, max_chars, max_links, args.table_format)
+        }
+    };
+    Ok(ToolOutput { text, structured: Some(scrape_structured(content, max_chars)), error: None })
+}
+
+/// The default `scrape_url` output: a content preview with metadata, headings, tables,
+/// feeds and a numbered Sources list matching the `[N]` citation markers
+fn render_scrape_text(content: &ScrapeResponse, max_chars: usize, max_links: usize, table_format: TableFormat) -> String {
+    let content_preview = if content.clean_content.is_empty() {
+        "[No content extracted]\n\n**Possible reasons:**\n\
+        • Page is JavaScript-heavy (requires browser execution)\n\
+        • Content is behind authentication/paywall\n\
+        • Site blocks automated access\n\n\
+        **Suggestion:** For JS-heavy sites, try using the Playwright MCP tool instead.".to_string()
+    } else if content.word_count < 10 {
+        format!("{}\n\n⚠️ **Very short content** ({} words). Page may be mostly dynamic/JS-based.",
+            content.clean_content.chars().take(max_chars).collect::<String>(),
+            content.word_count)
+    } else {
+        let preview = content.clean_content.chars().take(max_chars).collect::<String>();
+        if content.clean_content.len() > max_chars {
+            format!("{}\n\n[Content truncated: {}/{} chars shown. Increase max_chars parameter to see more]",
+                preview, max_chars, content.clean_content.len())
+        } else {
+            preview
+        }
+    };
+
+    // Build Sources section from links
+    let sources_section = if content.links.is_empty() {
+        String::new()
+    } else {
+        let mut sources = String::from("\n\n**Sources:**\n");
+        let link_count = content.links.len();
+        for (i, link) in content.links.iter().take(max_links).enumerate() {
+            if !link.text.is_empty() {
+                sources.push_str(&format!("[{}]: {} ({})", i + 1, link.url, link.text));
+            } else {
+                sources.push_str(&format!("[{}]: {}", i + 1, link.url));
+            }
+            sources.push('\n');
+        }
+        if link_count > max_links {
+            sources.push_str(&format!("\n(Showing {} of {} total links)\n", max_links, link_count));
+        }
+        sources
+    };
+
+    let warnings_line = if content.warnings.is_empty() {
+        String::new()
+    } else {
+        format!("Warnings: {}\n", content.warnings.join(", "))
+    };
+    let pages_line = match content.page_count {
+        Some(n) => format!("Pages: {}\n", n),
+        None => String::new(),
+    };
+
+    let table_format = match table_format {
+        TableFormat::Markdown => "markdown",
+        TableFormat::Csv => "csv",
+    };
+    let tables = scrape::render_tables(content, table_format);
+    let tables_section = if tables.is_empty() {
+        String::new()
+    } else {
+        format!("\n\n**Tables:**\n{}", tables)
+    };
+    let feeds_section = if content.feeds.is_empty() {
+        String::new()
+    } else {
+        let feeds = content.feeds.iter()
+            .map(|f| format!("- {} ({}){}", f.url, f.format, f.title.as_deref().map(|t| format!(": {}", t)).unwrap_or_default()))
+            .collect::<Vec<_>>()
+            .join("\n");
+        format!("\n\n**Feeds:**\n{}", feeds)
+    };
+
+    format!(
+        "**{}**\n\nURL: {}\nWord Count: {}\nLanguage: {}\n{}{}\n**Content:**\n{}\n\n**Metadata:**\n- Description: {}\n- Keywords: {}\n\n**Headings:**\n{}{}{}\n\n**Links Found:** {}\n**Images Found:** {}{}",
+        content.title,
+        content.url,
+        content.word_count,
+        content.language,
+        pages_line,
+        warnings_line,
+        content_preview,
+        content.meta_description,
+        content.meta_keywords,
+        content.headings.iter()
+            .map(|h| format!("- {} {}", h.level.to_uppercase(), h.text))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        tables_section,
+        feeds_section,
+        content.links.len(),
+        content.images.len(),
+        sources_section
+    )
+}
+
+async fn research_history(state: &Arc<AppState>, args: ResearchHistoryArgs) -> Result<ToolOutput, ToolCallError> {
+    let Some(memory) = &state.memory else {
+        return Ok(ToolOutput::failure(
+            "Research history feature is not available",
+            ErrorInfo::new(
+                ErrorKind::NotConfigured,
+                "Set the QDRANT_URL environment variable to enable it (e.g. QDRANT_URL=http://localhost:6333)",
+            ),
+        ));
+    };
+    let query = args.query.as_str();
+    let threshold = args.threshold as f32;
+
+    let results = match memory.search_history(query, args.limit, threshold, args.entry_type).await {
+        Ok(results) => results,
+        Err(e) => return failure("History search failed", &e),
+    };
+
+    let output = ResearchHistoryOutput {
+        query: query.to_string(),
+        results: results
+            .iter()
+            .map(|(entry, similarity)| HistoryMatch { similarity: *similarity, entry: entry.clone() })
+            .collect(),
+    };
+
+    if results.is_empty() {
+        let text = format!(
+            "No relevant history found for: '{}'\n\nTry:\n- Lower threshold (currently {:.2})\n- Broader search terms\n- Check if you have any saved history",
+            query, threshold
+        );
+        return Ok(ToolOutput::new(text, &output));
+    }
+
+    let mut text = format!("Found {} relevant entries for '{}':\n\n", results.len(), query);
+    for (i, (entry, score)) in results.iter().enumerate() {
+        text.push_str(&format!(
+            "{}. [Similarity: {:.3}] **{}** ({})\n   Type: {:?}\n   When: {}\n   Summary: {}\n",
+            i + 1,
+            score,
+            entry.topic,
+            entry.domain.as_deref().unwrap_or("N/A"),
+            entry.entry_type,
+            entry.timestamp.format("%Y-%m-%d %H:%M UTC"),
+            entry.summary.chars().take(150).collect::<String>()
+        ));
+        text.push_str(&format!("   Query: {}\n", entry.query));
+        text.push('\n');
+    }
+    text.push_str(&format!("\n💡 Tip: Use threshold={:.2} for similar results, or higher (0.8-0.9) for more specific matches", threshold));
+
+    Ok(ToolOutput::new(text, &output))
+}
+
+async fn crawl_site(state: &Arc<AppState>, args: CrawlSiteArgs) -> Result<ToolOutput, ToolCallError> {
+    let paths = |paths: Vec<String>| -> Vec<String> {
+        paths.into_iter().map(|p| p.trim().to_string()).filter(|p| !p.is_empty()).collect()
+    };
+    let options = crawl::CrawlOptions {
+        max_depth: args.max_depth,
+        max_pages: args.max_pages,
+        include_paths: paths(args.include_paths),
+        exclude_paths: paths(args.exclude_paths),
+        same_host: args.same_host,
+    };
+
+    match crawl::crawl_site(state, &args.url, options).await {
+        Ok(result) => {
+            let text = match args.output_format {
+                OutputFormat::Json => to_json(&result),
+                OutputFormat::Text => crawl::render_text(&result),
+            };
+            Ok(ToolOutput::new(text, &result))
+        }
+        Err(e) => failure("Crawl failed", &e),
+    }
+}
+
+async fn list_site_pages(state: &Arc<AppState>, args: ListSitePagesArgs) -> Result<ToolOutput, ToolCallError> {
+    let options = sitemap::SitemapOptions::new(args.path_prefix.as_deref(), args.modified_since.as_deref(), args.limit)
+        .map_err(|e| ToolCallError::invalid(e.to_string()))?;
+
+    match sitemap::list_site_pages(state, &args.url, options).await {
+        Ok(result) => {
+            let text = match args.output_format {
+                OutputFormat::Json => to_json(&result),
+                OutputFormat::Text => sitemap::render_text(&result),
+            };
+            Ok(ToolOutput::new(text, &result))
+        }
+        Err(e) => failure("Listing site pages failed", &e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_input_schemas() {
+        let tools = list_tools();
+        let names: Vec<_> = tools.iter().map(|t| t.name).collect();
+        assert_eq!(names, ["search_web", "scrape_url", "research_history", "crawl_site", "list_site_pages", "cache_stats"]);
+
+        let scrape = &tools[1].input_schema;
+        assert_eq!(scrape["type"], "object");
+        assert_eq!(scrape["required"], serde_json::json!(["url"]));
+        let props = &scrape["properties"];
+        assert_eq!(props["max_chars"]["type"], "integer");
+        assert_eq!(props["max_chars"]["maximum"], 50000.0);
+        assert_eq!(props["output_format"]["enum"], serde_json::json!(["text", "json", "markdown"]));
+        assert_eq!(props["output_format"]["default"], "text");
+        assert_eq!(props["max_pages"]["default"], 5);
+        assert!(props["url"]["description"].as_str().unwrap().starts_with("Full URL to scrape."));
+        assert!(!serde_json::to_string(scrape).unwrap().contains("$ref"));
+
+        let history = &tools[2].input_schema["properties"];
+        assert_eq!(history["entry_type"]["enum"], serde_json::json!(["search", "scrape"]));
+        assert_eq!(tools[5].input_schema["properties"], serde_json::json!({}));
+    }
+
+    #[tokio::test]
+    async fn test_call_tool_validates_arguments() {
+        let state = Arc::new(AppState::new("http://localhost:8888".to_string(), reqwest::Client::new()));
+
+        let missing = call_tool(&state, "scrape_url", Value::Null).await.unwrap_err();
+        assert!(matches!(&missing, ToolCallError::InvalidParams { message, .. } if message.contains("missing field `url`")), "{}", missing);
+        let wrong_type = call_tool(&state, "search_web", serde_json::json!({ "query": "x", "max_results": "ten" })).await.unwrap_err();
+        assert!(matches!(wrong_type, ToolCallError::InvalidParams { .. }));
+        let bad_format = call_tool(&state, "crawl_site", serde_json::json!({ "url": "https://a.test/", "output_format": "markdown" })).await.unwrap_err();
+        assert!(matches!(bad_format, ToolCallError::InvalidParams { .. }));
+        let bad_url = call_tool(&state, "scrape_url", serde_json::json!({ "url": "not a url" })).await.unwrap_err();
+        assert!(matches!(bad_url, ToolCallError::InvalidParams { info: Some(ErrorInfo { code: ErrorKind::InvalidUrl, .. }), .. }));
+        assert!(matches!(call_tool(&state, "nope", Value::Null).await, Err(ToolCallError::UnknownTool(_))));
+
+        let history = call_tool(&state, "research_history", serde_json::json!({ "query": "rust" })).await.unwrap();
+        assert_eq!(history.error.as_ref().map(|e| e.code), Some(ErrorKind::NotConfigured));
+        let result = history.into_call_tool_result();
+        assert_eq!(result.is_error, Some(true));
+        assert!(result.structured_content.is_none());
+
+        let stats = call_tool(&state, "cache_stats", Value::Null).await.unwrap();
+        assert!(stats.error.is_none());
+        assert!(serde_json::from_str::<Value>(&stats.text).is_ok());
+    }
+
+    /// Structural check of `value` against a generated schema: declared types, required
+    /// properties present and no undeclared ones
+    fn check_schema(value: &Value, schema: &Value, path: &str) -> Result<(), String> {
+        if let Some(variants) = schema["anyOf"].as_array() {
+            return match variants.iter().any(|v| check_schema(value, v, path).is_ok()) {
+                true => Ok(()),
+                false => Err(format!("{}: {} matches no variant", path, value)),
+            };
+        }
+        let type_name = match value {
+            Value::Null => "null",
+            Value::Bool(_) => "boolean",
+            Value::Number(n) if n.is_f64() => "number",
+            Value::Number(_) => "integer",
+            Value::String(_) => "string",
+            Value::Array(_) => "array",
+            Value::Object(_) => "object",
+        };
+        let allowed: Vec<&str> = match &schema["type"] {
+            Value::String(t) => vec![t.as_str()],
+            Value::Array(ts) => ts.iter().filter_map(Value::as_str).collect(),
+            _ => return Ok(()),
+        };
+        let accepted = allowed.contains(&type_name) || (type_name == "integer" && allowed.contains(&"number"));
+        if !accepted {
+            return Err(format!("{}: {} is not {:?}", path, type_name, allowed));
+        }
+        match value {
+            Value::Object(map) if schema["properties"].is_object() => {
+                for required in schema["required"].as_array().into_iter().flatten() {
+                    if !map.contains_key(required.as_str().unwrap_or_default()) {
+                        return Err(format!("{}: missing {}", path, required));
+                    }
+                }
+                map.iter().try_for_each(|(key, field)| match &schema["properties"][key] {
+                    Value::Null => Err(format!("{}: undeclared {}", path, key)),
+                    declared => check_schema(field, declared, &format!("{}.{}", path, key)),
+                })
+            }
+            Value::Array(items) => items.iter().try_for_each(|item| check_schema(item, &schema["items"], path)),
+            _ => Ok(()),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_structured_content_matches_output_schema() {
+        use axum::{response::Html, routing::get, Router};
+
+        let page = "<html><head><title>Guide</title><link rel=\"alternate\" type=\"application/rss+xml\" href=\"/feed.xml\"></head>\
+            <body><article><h1>Guide</h1><p>Some words about the guide that are long enough to count as content.</p>\
+            <pre><code class=\"language-rust\">fn main() {}</code></pre><a href=\"/next\">Next</a></article></body></html>";
+        let long = format!("<html><body><article><h1>Long</h1><p>{}</p></article></body></html>", "word ".repeat(500));
+        let app = Router::new()
+            .route("/guide", get(move || async move { Html(page) }))
+            .route("/long", get(move || async move { Html(long) }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let state = AppState::new("http://localhost:8888".to_string(), reqwest::Client::new())
+            .with_ssrf_policy(crate::ssrf::SsrfPolicy::disabled());
+        let state = Arc::new(state);
+        let schemas: std::collections::HashMap<_, _> = list_tools().into_iter().map(|t| (t.name, Value::Object(t.output_schema))).collect();
+
+        let scrape = call_tool(&state, "scrape_url", serde_json::json!({ "url": format!("http://{}/guide", addr) })).await.unwrap();
+        assert!(scrape.text.starts_with("**Guide**"), "{}", scrape.text);
+        let structured = scrape.structured.clone().unwrap();
+        assert_eq!(structured["title"], "Guide");
+        assert!(structured.get("content").is_none(), "raw HTML in structured content");
+        check_schema(&structured, &schemas["scrape_url"], "scrape_url").unwrap();
+        assert!(schemas["scrape_url"]["properties"].get("content").is_none());
+        let result = scrape.into_call_tool_result();
+        assert_eq!(result.is_error, Some(false));
+        assert_eq!(result.structured_content, Some(structured));
+
+        // Structured clean_content is cut to max_chars like the text
+        let long = call_tool(&state, "scrape_url", serde_json::json!({ "url": format!("http://{}/long", addr), "max_chars": 100 })).await.unwrap();
+        let structured = long.structured.unwrap();
+        assert_eq!(structured["clean_content"].as_str().unwrap().chars().count(), 100);
+        assert_eq!(structured["truncated"], true);
+        assert!(structured["actual_chars"].as_u64().unwrap() > 100);
+
+        let stats = call_tool(&state, "cache_stats", Value::Null).await.unwrap();
+        check_schema(stats.structured.as_ref().unwrap(), &schemas["cache_stats"], "cache_stats").unwrap();
+
+        let history = ResearchHistoryOutput { query: "q".into(), results: Vec::new() };
+        check_schema(&serde_json::to_value(&history).unwrap(), &schemas["research_history"], "research_history").unwrap();
+        let search = SearchWebOutput {
+            query: "q".into(),
+            rewritten_query: None,
+            total_results: 1,
+            results: vec![SearchResult {
+                url: "https://a.test/".into(),
+                title: "A".into(),
+                content: "a".into(),
+                engine: Some("bing".into()),
+                score: Some(1.0),
+                domain: None,
+                source_type: Some("docs".into()),
+            }],
+            answers: Vec::new(),
+            suggestions: vec!["b".into()],
+            corrections: Vec::new(),
+            unresponsive_engines: Vec::new(),
+            duplicate_warning: None,
+        };
+        check_schema(&serde_json::to_value(&search).unwrap(), &schemas["search_web"], "search_web").unwrap();
+        assert!(check_schema(&serde_json::json!({ "query": 1 }), &schemas["search_web"], "search_web").is_err());
+        assert_eq!(schemas["research_history"]["properties"]["results"]["items"]["properties"]["entry_type"]["enum"], serde_json::json!(["search", "scrape"]));
+    }
+}
@@ -1,3 +1,5 @@
+use schemars::JsonSchema;
+use scraper::ElementRef;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -10,7 +12,7 @@ pub struct SearchResponse {
     pub results: Vec<SearchResult>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct SearchResult {
     pub url: String,
     pub title: String,
@@ -27,15 +29,27 @@ pub struct SearchResult {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ScrapeRequest {
     pub url: String,
+    /// Serve a cached copy younger than this many seconds without revalidating
+    #[serde(default)]
+    pub max_age: Option<u64>,
+    /// Bypass the cache and refetch
+    #[serde(default)]
+    pub force_refresh: Option<bool>,
     #[serde(default)]
     pub content_links_only: Option<bool>,
     #[serde(default)]
     pub max_links: Option<usize>,
     #[serde(default)]
     pub max_images: Option<usize>,
+    /// Follow pagination links and merge subsequent pages
+    #[serde(default)]
+    pub follow_pagination: Option<bool>,
+    /// Page limit for `follow_pagination` (default 5, max 20)
+    #[serde(default)]
+    pub max_pages: Option<usize>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct ScrapeResponse {
     pub url: String,
     pub title: String,
@@ -60,6 +74,8 @@ pub struct ScrapeResponse {
     #[serde(default)]
     pub published_at: Option<String>,
     #[serde(default)]
+    pub modified_at: Option<String>,
+    #[serde(default)]
     pub og_title: Option<String>,
     #[serde(default)]
     pub og_description: Option<String>,
@@ -82,9 +98,355 @@ pub struct ScrapeResponse {
     pub warnings: Vec<String>,
     #[serde(default)]
     pub domain: Option<String>,
+    /// Character encoding the body was decoded from (e.g. "Shift_JIS")
+    #[serde(default)]
+    pub encoding: Option<String>,
+    /// Number of pages for paged documents (PDF) and articles stitched with `follow_pagination`
+    #[serde(default)]
+    pub page_count: Option<usize>,
+    /// Where each page starts and ends in `clean_content`
+    #[serde(default)]
+    pub pages: Vec<PageBoundary>,
+    /// schema.org data embedded in the page (JSON-LD, Microdata, RDFa)
+    #[serde(default)]
+    pub structured_data: Option<StructuredData>,
+    /// Data tables found in the page
+    #[serde(default)]
+    pub tables: Vec<Table>,
+    /// Parsed entries when the URL is an RSS, Atom or JSON Feed document
+    #[serde(default)]
+    pub feed: Option<Feed>,
+    /// Feeds the page advertises via `<link rel="alternate">`
+    #[serde(default)]
+    pub feeds: Vec<FeedLink>,
+}
+
+/// One page visited by `crawl_site`
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+pub struct CrawlPage {
+    pub url: String,
+    #[serde(default)]
+    pub canonical_url: Option<String>,
+    pub title: String,
+    /// Link distance from the seed URL
+    pub depth: usize,
+    pub word_count: usize,
+    /// Meta description, or the start of the page's clean content
+    pub summary: String,
+    /// h1-h3 headings, used for the table of contents
+    #[serde(default)]
+    pub headings: Vec<Heading>,
+}
+
+/// A page `crawl_site` tried and failed to scrape
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+pub struct CrawlError {
+    pub url: String,
+    pub depth: usize,
+    pub error: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+pub struct CrawlResponse {
+    pub seed_url: String,
+    pub pages: Vec<CrawlPage>,
+    #[serde(default)]
+    pub errors: Vec<CrawlError>,
+    /// Pages skipped because their canonical URL was already crawled
+    #[serde(default)]
+    pub duplicates: usize,
+    /// Discovered in-scope links left unvisited because a depth or page limit was hit
+    #[serde(default)]
+    pub unvisited: usize,
+    /// Markdown outline of the crawled pages (ordered by path) and their headings
+    pub table_of_contents: String,
+}
+
+/// A `<url>` entry from a sitemap
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+pub struct SitemapUrl {
+    pub url: String,
+    #[serde(default)]
+    pub lastmod: Option<String>,
+    #[serde(default)]
+    pub priority: Option<f32>,
+    #[serde(default)]
+    pub changefreq: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+pub struct SitemapResponse {
+    pub site: String,
+    /// Sitemap files read, including nested ones from sitemap indexes
+    pub sitemaps: Vec<String>,
+    pub urls: Vec<SitemapUrl>,
+    /// URLs matching the filters before `limit` was applied
+    pub total_matched: usize,
+    /// More URLs matched than were returned, or the sitemap file limit was hit
+    #[serde(default)]
+    pub truncated: bool,
+    /// Sitemaps that couldn't be fetched or parsed
+    #[serde(default)]
+    pub errors: Vec<String>,
+}
+
+/// A syndication feed: RSS 2.0 / 1.0, Atom or JSON Feed
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+pub struct Feed {
+    /// "rss", "atom" or "json"
+    pub format: String,
+    pub title: String,
+    #[serde(default)]
+    pub link: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Feed-level last update (RFC 3339 when the source date could be parsed)
+    #[serde(default)]
+    pub updated: Option<String>,
+    pub entries: Vec<FeedEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+pub struct FeedEntry {
+    pub title: String,
+    #[serde(default)]
+    pub link: Option<String>,
+    /// RFC 3339 when the source date could be parsed, otherwise as given
+    #[serde(default)]
+    pub published: Option<String>,
+    #[serde(default)]
+    pub author: Option<String>,
+    /// Plain-text summary (HTML stripped, shortened)
+    #[serde(default)]
+    pub summary: Option<String>,
+}
+
+/// A feed advertised by an HTML page
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+pub struct FeedLink {
+    pub url: String,
+    #[serde(default)]
+    pub title: Option<String>,
+    /// "rss", "atom" or "json"
+    pub format: String,
+}
+
+/// An HTML data table. Spanning cells are expanded (their text repeated in every
+/// row/column they cover) so each row has one cell per column.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+pub struct Table {
+    #[serde(default)]
+    pub caption: Option<String>,
+    /// Column headers; empty when the table has no header row
+    #[serde(default)]
+    pub headers: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    /// Build from a `<table>` element: thead/leading `th` rows become headers (merged per
+    /// column when there are several), colspan/rowspan are expanded into a rectangular grid.
+    /// `cell_text` renders a cell or caption; rows of nested tables are skipped.
+    pub fn from_html(table: ElementRef, cell_text: impl Fn(ElementRef) -> String) -> Self {
+        const MAX_SPAN: usize = 100;
+
+        fn nearest<'a>(el: ElementRef<'a>, names: &[&str]) -> Option<ElementRef<'a>> {
+            el.ancestors()
+                .filter_map(ElementRef::wrap)
+                .find(|a| names.contains(&a.value().name()))
+        }
+
+        // Build the grid; `carried[col]` holds a rowspan cell still covering upcoming rows
+        let mut carried: Vec<Option<(usize, String)>> = Vec::new();
+        let mut grid: Vec<(Vec<String>, bool)> = Vec::new();
+        for tr in table.descendants().filter_map(ElementRef::wrap).filter(|e| e.value().name() == "tr") {
+            if nearest(tr, &["table"]).map(|t| t.id()) != Some(table.id()) {
+                continue;
+            }
+            let in_thead = nearest(tr, &["thead", "tbody", "tfoot", "table"])
+                .map(|s| s.value().name() == "thead")
+                .unwrap_or(false);
+            let mut row: Vec<String> = Vec::new();
+            let mut all_th = true;
+            let take_carried = |row: &mut Vec<String>, carried: &mut Vec<Option<(usize, String)>>| {
+                while let Some(Some((left, text))) = carried.get_mut(row.len()) {
+                    row.push(text.clone());
+                    *left -= 1;
+                    if *left == 0 {
+                        carried[row.len() - 1] = None;
+                    }
+                }
+            };
+            for cell in tr.children().filter_map(ElementRef::wrap) {
+                let name = cell.value().name();
+                if name != "td" && name != "th" {
+                    continue;
+                }
+                all_th &= name == "th";
+                take_carried(&mut row, &mut carried);
+                let span = |attr: &str| {
+                    cell.value().attr(attr).and_then(|v| v.trim().parse::<usize>().ok()).unwrap_or(1).clamp(1, MAX_SPAN)
+                };
+                let text = cell_text(cell);
+                let rowspan = span("rowspan");
+                for _ in 0..span("colspan") {
+                    if rowspan > 1 {
+                        if carried.len() <= row.len() {
+                            carried.resize(row.len() + 1, None);
+                        }
+                        carried[row.len()] = Some((rowspan - 1, text.clone()));
+                    }
+                    row.push(text.clone());
+                }
+            }
+            // Rowspans can also cover trailing columns
+            while row.len() < carried.len() {
+                take_carried(&mut row, &mut carried);
+                if row.len() < carried.len() && carried[row.len()].is_none() {
+                    row.push(String::new());
+                }
+            }
+            if !row.is_empty() {
+                grid.push((row, in_thead || all_th));
+            }
+        }
+
+        let columns = grid.iter().map(|(row, _)| row.len()).max().unwrap_or(0);
+        let header_rows = grid.iter().take_while(|(_, header)| *header).count();
+        for (row, _) in grid.iter_mut() {
+            row.resize(columns, String::new());
+        }
+
+        // Multi-row headers are merged per column ("Q1 / Revenue")
+        let headers = if header_rows == 0 {
+            Vec::new()
+        } else {
+            (0..columns)
+                .map(|col| {
+                    let mut parts: Vec<&str> = Vec::new();
+                    for (row, _) in &grid[..header_rows] {
+                        let text = row[col].as_str();
+                        if !text.is_empty() && !parts.contains(&text) {
+                            parts.push(text);
+                        }
+                    }
+                    parts.join(" / ")
+                })
+                .collect()
+        };
+        let caption = table
+            .children()
+            .filter_map(ElementRef::wrap)
+            .find(|c| c.value().name() == "caption")
+            .map(&cell_text)
+            .filter(|c| !c.is_empty());
+
+        Self {
+            caption,
+            headers,
+            rows: grid.into_iter().skip(header_rows).map(|(row, _)| row).collect(),
+        }
+    }
+
+    /// Number of columns (headers and rows are padded to the same width by `from_html`)
+    pub fn columns(&self) -> usize {
+        self.headers.len().max(self.rows.iter().map(Vec::len).max().unwrap_or(0))
+    }
+
+    /// GFM pipe table (an empty header row is emitted when there are no headers)
+    pub fn to_markdown(&self) -> String {
+        let columns = self.columns();
+        let line = |cells: &[String]| {
+            let cells: Vec<String> = (0..columns)
+                .map(|i| cells.get(i).map(|c| c.replace('|', "\\|")).unwrap_or_default())
+                .collect();
+            format!("| {} |", cells.join(" | "))
+        };
+        let mut lines = vec![line(&self.headers), format!("|{}", " --- |".repeat(columns))];
+        lines.extend(self.rows.iter().map(|row| line(row)));
+        lines.join("\n")
+    }
+
+    /// RFC 4180 CSV, header line first when there are headers
+    pub fn to_csv(&self) -> String {
+        let field = |cell: &String| {
+            if cell.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", cell.replace('"', "\"\""))
+            } else {
+                cell.clone()
+            }
+        };
+        let header = (!self.headers.is_empty()).then_some(&self.headers);
+        header
+            .into_iter()
+            .chain(&self.rows)
+            .map(|row| row.iter().map(field).collect::<Vec<_>>().join(","))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
 }
 
+/// schema.org items found in a page. Microdata and RDFa items are converted to the
+/// JSON-LD shape (`{"@type": "Article", "headline": ..., "author": {"@type": "Person", ...}}`)
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
+pub struct StructuredData {
+    /// `<script type="application/ld+json">` items, with `@graph` containers flattened
+    #[serde(default)]
+    pub json_ld: Vec<serde_json::Value>,
+    /// Top-level `itemscope` items
+    #[serde(default)]
+    pub microdata: Vec<serde_json::Value>,
+    /// Top-level `typeof` items
+    #[serde(default)]
+    pub rdfa: Vec<serde_json::Value>,
+}
+
+/// Char offsets of one page within `clean_content` (end exclusive)
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+pub struct PageBoundary {
+    pub page: usize,
+    pub start_char: usize,
+    pub end_char: usize,
+    /// Source URL of a stitched web page (`None` for PDF pages)
+    #[serde(default)]
+    pub url: Option<String>,
+}
+
+/// HTTP validators used to revalidate a cached scrape
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct Validators {
+    #[serde(default)]
+    pub etag: Option<String>,
+    #[serde(default)]
+    pub last_modified: Option<String>,
+}
+
+impl Validators {
+    pub fn is_empty(&self) -> bool {
+        self.etag.is_none() && self.last_modified.is_none()
+    }
+}
+
+/// Scrape cache entry: the response plus what's needed to revalidate it
 #[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CachedScrape {
+    pub response: ScrapeResponse,
+    #[serde(default)]
+    pub validators: Validators,
+    pub fetched_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Cache usage reported by `/stats/cache` and the `cache_stats` tool
+#[derive(Debug, Serialize, Clone, JsonSchema)]
+pub struct CacheStats {
+    pub search_memory_entries: u64,
+    pub scrape_memory_entries: u64,
+    /// `None` when DISK_CACHE_DIR is not configured
+    pub disk: Option<crate::disk_cache::DiskCacheStats>,
+}
+
+/// `start_char`/`end_char` locate the code in `clean_content` (char offsets, end exclusive)
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct CodeBlock {
     pub language: Option<String>,
     pub code: String,
@@ -94,19 +456,24 @@ pub struct CodeBlock {
     pub end_char: Option<usize>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct Heading {
     pub level: String,
     pub text: String,
+    /// Where the heading text appears in `clean_content` (char offsets, end exclusive)
+    #[serde(default)]
+    pub start_char: Option<usize>,
+    #[serde(default)]
+    pub end_char: Option<usize>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct Link {
     pub url: String,
     pub text: String,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct Image {
     pub src: String,
     pub alt: String,